@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageProcessing {
+    /// Whether the `auto` format of `resize_image` picks AVIF for lossy images and lossless WebP
+    /// for the other ones instead of JPEG and PNG. Defaults to `false`
+    pub prefer_modern_formats: bool,
+    /// Whether processed images keep the EXIF data of their source, except for their orientation
+    /// which is already applied to the pixels. Defaults to `false`
+    pub keep_exif: bool,
+    /// Whether processed images keep the ICC color profile of their source. Defaults to `false`
+    pub keep_icc_profile: bool,
+    /// Whether GPS positions are kept in the EXIF data of processed images and returned by
+    /// `get_image_metadata`. Defaults to `false`
+    pub keep_gps: bool,
+    /// The layouts of the images made by `social_card`, by name. The `default` one is used when
+    /// no name is given and falls back to `SocialCardTemplate::default()` if not set
+    pub social_cards: HashMap<String, SocialCardTemplate>,
+}
+
+/// The layout of an Open Graph image made by `social_card`: the title at the top and the
+/// metadata lines at the bottom, over a background image or color
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SocialCardTemplate {
+    /// In pixels, defaults to 1200x630 which is what most platforms expect
+    pub width: u32,
+    pub height: u32,
+    /// An image resized to fill the card, found like the images of `resize_image`
+    pub background: Option<String>,
+    /// Used when there is no background image, as `#rrggbb`. Defaults to `#1e293b`
+    pub background_color: String,
+    /// As `#rrggbb` or `#rrggbbaa`. Defaults to `#ffffff`
+    pub text_color: String,
+    /// A TrueType or OpenType font, found like the images of `resize_image`.
+    /// Defaults to the bundled DejaVu Sans Bold
+    pub font: Option<String>,
+    /// In pixels, the title is made smaller if it doesn't fit otherwise. Defaults to 64
+    pub title_size: u32,
+    /// In pixels. Defaults to 32
+    pub metadata_size: u32,
+    /// Between the text and the edges of the card, in pixels. Defaults to 80
+    pub padding: u32,
+}
+
+impl Default for SocialCardTemplate {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 630,
+            background: None,
+            background_color: "#1e293b".to_string(),
+            text_color: "#ffffff".to_string(),
+            font: None,
+            title_size: 64,
+            metadata_size: 32,
+            padding: 80,
+        }
+    }
+}
@@ -17,7 +17,8 @@ pub struct LanguageOptions {
     /// Whether to generate feeds for that language, defaults to `false`
     pub generate_feeds: bool,
     /// The filenames to use for feeds. Used to find the templates, too.
-    /// Defaults to ["atom.xml"], with "rss.xml" also having a template provided out of the box.
+    /// Defaults to ["atom.xml"], with "rss.xml" and "feed.json" also having a template provided
+    /// out of the box.
     pub feed_filenames: Vec<String>,
     pub taxonomies: Vec<taxonomies::TaxonomyConfig>,
     /// Whether to generate search index for that language, defaults to `false`
@@ -1,4 +1,4 @@
-use std::{path::Path, sync::Arc};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
 use libs::syntect::{
     highlighting::{Theme, ThemeSet},
@@ -22,6 +22,148 @@ pub struct ThemeCss {
     pub filename: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownImages {
+    /// Whether the colocated JPEG, PNG and WebP images are resized to `widths` and rendered
+    /// in a `<picture>` with their dimensions. Defaults to false
+    pub responsive: bool,
+    /// The widths of the variants, the ones larger than the image being skipped
+    pub widths: Vec<u32>,
+    /// The formats of the variants as accepted by `resize_image`, the last one being used
+    /// by the `<img>`. Defaults to `["auto"]`
+    pub formats: Vec<String>,
+    /// The `sizes` attribute of the images. Defaults to `100vw`
+    pub sizes: String,
+}
+
+impl Default for MarkdownImages {
+    fn default() -> MarkdownImages {
+        MarkdownImages {
+            responsive: false,
+            widths: vec![480, 800, 1200, 1600],
+            formats: vec!["auto".to_owned()],
+            sizes: "100vw".to_owned(),
+        }
+    }
+}
+
+/// The extensions of the markdown parser, on top of CommonMark
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownExtensions {
+    /// Defaults to true
+    pub tables: bool,
+    /// Defaults to true
+    pub footnotes: bool,
+    /// `~~text~~`. Defaults to true
+    pub strikethrough: bool,
+    /// `- [x] item`. Defaults to true
+    pub tasklists: bool,
+    /// `# Title {#id .class}`. Defaults to true
+    pub heading_attributes: bool,
+    /// A term on its own line followed by lines starting with `: `. Defaults to false
+    pub definition_lists: bool,
+    /// `^text^`. Defaults to false
+    pub superscript: bool,
+    /// `~text~`, strikethrough then needing two tildes. Defaults to false
+    pub subscript: bool,
+    /// `{#id .class key=value}` at the end of paragraphs, list items and definition list
+    /// items. Defaults to false
+    pub attributes: bool,
+    /// `[[Page Title]]` and `[[slug#heading|label]]` links to pages and sections.
+    /// Defaults to false
+    pub wikilinks: bool,
+}
+
+impl Default for MarkdownExtensions {
+    fn default() -> MarkdownExtensions {
+        MarkdownExtensions {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+            heading_attributes: true,
+            definition_lists: false,
+            superscript: false,
+            subscript: false,
+            attributes: false,
+            wikilinks: false,
+        }
+    }
+}
+
+/// The admonition types always available, with their default title
+const BUILTIN_ADMONITIONS: [(&str, &str); 5] = [
+    ("note", "Note"),
+    ("tip", "Tip"),
+    ("important", "Important"),
+    ("warning", "Warning"),
+    ("caution", "Caution"),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Admonitions {
+    /// Whether GitHub-style alerts like `> [!NOTE]` are rendered as admonitions. Defaults to false
+    pub alerts: bool,
+    /// Whether `:::note` ... `:::` containers are rendered as admonitions. Defaults to false
+    pub containers: bool,
+    /// The class of the admonitions, which also get `{class}-{type}`. Defaults to `admonition`
+    pub class: String,
+    /// The class of the title of the admonitions. Defaults to `admonition-title`
+    pub title_class: String,
+    /// Extra admonition types with their default title. The types note, tip, important,
+    /// warning and caution are always there but their title can be changed here
+    pub types: HashMap<String, String>,
+}
+
+impl Admonitions {
+    /// Whether any admonition syntax is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.alerts || self.containers
+    }
+
+    /// The default title of the given admonition type, `None` if it isn't one.
+    /// Types are case insensitive.
+    pub fn default_title(&self, kind: &str) -> Option<&str> {
+        self.types
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(kind))
+            .map(|(_, title)| title.as_str())
+            .or_else(|| {
+                BUILTIN_ADMONITIONS
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(kind))
+                    .map(|(_, title)| *title)
+            })
+    }
+}
+
+impl Default for Admonitions {
+    fn default() -> Admonitions {
+        Admonitions {
+            alerts: false,
+            containers: false,
+            class: "admonition".to_owned(),
+            title_class: "admonition-title".to_owned(),
+            types: HashMap::new(),
+        }
+    }
+}
+
+/// How the `$...$` and `$$...$$` math of the content is rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MathRendering {
+    /// The dollars are left as they are, eg for KaTeX to render them in the browser
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// The TeX is converted to MathML at build time
+    #[serde(rename = "mathml")]
+    MathMl,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Markdown {
@@ -55,6 +197,14 @@ pub struct Markdown {
     pub extra_theme_set: Arc<Option<ThemeSet>>,
     /// Add loading="lazy" decoding="async" to img tags. When turned on, the alt text must be plain text. Defaults to false
     pub lazy_async_image: bool,
+    /// The processing of the images of the content
+    pub images: MarkdownImages,
+    /// How math is rendered. Defaults to none
+    pub math: MathRendering,
+    /// The rendering of callouts like `> [!NOTE]`
+    pub admonitions: Admonitions,
+    /// Which extensions of the markdown parser are enabled
+    pub extensions: MarkdownExtensions,
 }
 
 impl Markdown {
@@ -210,6 +360,10 @@ impl Default for Markdown {
             extra_syntax_set: None,
             extra_theme_set: Arc::new(None),
             lazy_async_image: false,
+            images: MarkdownImages::default(),
+            math: MathRendering::default(),
+            admonitions: Admonitions::default(),
+            extensions: MarkdownExtensions::default(),
         }
     }
 }
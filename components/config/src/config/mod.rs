@@ -1,8 +1,11 @@
+pub mod image_processing;
 pub mod languages;
 pub mod link_checker;
 pub mod markup;
+pub mod podcast;
 pub mod search;
 pub mod slugify;
+pub mod static_images;
 pub mod taxonomies;
 
 use std::collections::HashMap;
@@ -20,6 +23,48 @@ use utils::slugs::slugify_paths;
 
 // We want a default base url for tests
 static DEFAULT_BASE_URL: &str = "http://a-website.com";
+/// Environment variables starting with that prefix override config values, eg `ZOLA_BASE_URL`
+static ENV_VAR_PREFIX: &str = "ZOLA_";
+/// Used to pick the environment if `--env` is not passed
+pub static ENV_VAR_ENVIRONMENT: &str = "ZOLA_ENV";
+/// Environment variables with the prefix that are not config overrides.
+/// `ZOLA_VERSION` is used by hosting providers to pick the version of Zola.
+static RESERVED_ENV_VARS: &[&str] = &[ENV_VAR_ENVIRONMENT, "ZOLA_PERF_LOG", "ZOLA_VERSION"];
+/// The top-level keys of the config that environment variables can override. The other
+/// `ZOLA_*` variables are ignored since they are likely meant for something else.
+static ENV_OVERRIDABLE_KEYS: &[&str] = &[
+    "base_url",
+    "theme",
+    "title",
+    "description",
+    "default_language",
+    "languages",
+    "translations",
+    "generate_feeds",
+    "feed_limit",
+    "feed_filenames",
+    "hard_link_static",
+    "taxonomies",
+    "author",
+    "compile_sass",
+    "minify_html",
+    "build_search_index",
+    "ignored_content",
+    "ignored_static",
+    "output_dir",
+    "preserve_dotfiles_in_output",
+    "link_checker",
+    "slugify",
+    "permalinks",
+    "podcast",
+    "image_processing",
+    "static_images",
+    "search",
+    "markdown",
+    "extra",
+    "generate_sitemap",
+    "generate_robots_txt",
+];
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -54,7 +99,8 @@ pub struct Config {
     /// The number of articles to include in the feed. Defaults to including all items.
     pub feed_limit: Option<usize>,
     /// The filenames to use for feeds. Used to find the templates, too.
-    /// Defaults to ["atom.xml"], with "rss.xml" also having a template provided out of the box.
+    /// Defaults to ["atom.xml"], with "rss.xml" and "feed.json" also having a template provided
+    /// out of the box.
     pub feed_filenames: Vec<String>,
     /// If set, files from static/ will be hardlinked instead of copied to the output dir.
     pub hard_link_static: bool,
@@ -84,6 +130,10 @@ pub struct Config {
     /// command being used.
     #[serde(skip_serializing)]
     pub mode: Mode,
+    /// The environment the site is built for, eg `production` with `--env production`.
+    /// Its `config.{environment}.toml` file is merged over the config file.
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub environment: Option<String>,
 
     pub output_dir: String,
     /// Whether dotfiles inside the output directory are preserved when rebuilding the site
@@ -92,6 +142,16 @@ pub struct Config {
     pub link_checker: link_checker::LinkChecker,
     /// The setup for which slugification strategies to use for paths, taxonomies and anchors
     pub slugify: slugify::Slugify,
+    /// The default permalink pattern of the pages of a section, by section path, eg
+    /// `posts = "/:year/:month/:slug/"`. The `permalink` of the section front matter takes precedence.
+    pub permalinks: HashMap<String, String>,
+    /// The show information used in the feeds of the sections marked as a podcast
+    pub podcast: podcast::Podcast,
+    /// The config for the images processed with `resize_image`
+    pub image_processing: image_processing::ImageProcessing,
+    /// The optimization of the images copied from `static` and the page assets, disabled
+    /// unless the section is set
+    pub static_images: Option<static_images::StaticImages>,
     /// The search config, telling what to include in the search index
     pub search: search::Search,
     /// The config for the Markdown rendering: syntax highlighting and everything
@@ -108,6 +168,7 @@ pub struct Config {
 pub struct SerializedConfig<'a> {
     base_url: &'a str,
     mode: Mode,
+    environment: &'a Option<String>,
     title: &'a Option<String>,
     description: &'a Option<String>,
     languages: HashMap<&'a String, &'a languages::LanguageOptions>,
@@ -120,6 +181,7 @@ pub struct SerializedConfig<'a> {
     build_search_index: bool,
     extra: &'a HashMap<String, Toml>,
     markdown: &'a markup::Markdown,
+    podcast: &'a podcast::Podcast,
     search: search::SerializedSearch<'a>,
     generate_sitemap: bool,
     generate_robots_txt: bool,
@@ -154,6 +216,10 @@ impl Config {
         let static_glob_set = build_ignore_glob_set(&config.ignored_static, "static")?;
         config.ignored_static_globset = Some(static_glob_set);
 
+        if let Some(static_images) = config.static_images.as_mut() {
+            static_images.resolve()?;
+        }
+
         Ok(config)
     }
 
@@ -166,10 +232,38 @@ impl Config {
 
     /// Parses a config file from the given path
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config> {
+        Config::from_file_with_env(path, None)
+    }
+
+    /// Parses a config file from the given path for the given environment, falling back to
+    /// the `ZOLA_ENV` environment variable.
+    /// The `config.{env}.toml` file next to it is merged over it if it exists, as well as the
+    /// values from the `ZOLA_*` environment variables.
+    pub fn from_file_with_env<P: AsRef<Path>>(path: P, env: Option<&str>) -> Result<Config> {
         let path = path.as_ref();
-        let content = read_file(path)?;
+        let mut content = read_file(path)?;
+        let environment =
+            env.map(|e| e.to_string()).or_else(|| std::env::var(ENV_VAR_ENVIRONMENT).ok());
+
+        let env_path = environment.as_ref().map(|e| env_config_path(path, e));
+        let overrides = env_overrides();
+        if env_path.as_ref().is_some_and(|p| p.exists()) || !overrides.is_empty() {
+            let mut value: Toml = libs::toml::from_str(&content)
+                .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+            if let Some(env_path) = env_path.filter(|p| p.exists()) {
+                let mut env_value: Toml = libs::toml::from_str(&read_file(&env_path)?)
+                    .map_err(|e| anyhow!("Failed to parse {}: {}", env_path.display(), e))?;
+                // The values of the environment config win
+                let names = (env_path.display().to_string(), path.display().to_string());
+                merge(&mut env_value, &value, (&names.0, &names.1))?;
+                value = env_value;
+            }
+            apply_env_overrides(&mut value, overrides)?;
+            content = libs::toml::to_string(&value)?;
+        }
 
         let mut config = Config::parse(&content)?;
+        config.environment = environment;
         let config_dir = path
             .parent()
             .ok_or_else(|| anyhow!("Failed to find directory containing the config file."))?;
@@ -250,7 +344,7 @@ impl Config {
                 self.extra.insert(key.to_string(), val.clone());
                 continue;
             }
-            merge(self.extra.get_mut(key).unwrap(), val)?;
+            merge(self.extra.get_mut(key).unwrap(), val, ("config.toml", "theme.toml"))?;
         }
         Ok(())
     }
@@ -325,6 +419,7 @@ impl Config {
         SerializedConfig {
             base_url: &self.base_url,
             mode: self.mode,
+            environment: &self.environment,
             title: &options.title,
             description: &options.description,
             languages: self.languages.iter().filter(|(k, _)| k.as_str() != lang).collect(),
@@ -337,6 +432,7 @@ impl Config {
             build_search_index: options.build_search_index,
             extra: &self.extra,
             markdown: &self.markdown,
+            podcast: &self.podcast,
             search: self.search.serialize(),
             generate_sitemap: self.generate_sitemap,
             generate_robots_txt: self.generate_robots_txt,
@@ -345,7 +441,8 @@ impl Config {
 }
 
 // merge TOML data that can be a table, or anything else
-pub fn merge(into: &mut Toml, from: &Toml) -> Result<()> {
+// `names` are the files `into` and `from` come from, for the error message
+pub fn merge(into: &mut Toml, from: &Toml, names: (&str, &str)) -> Result<()> {
     match (from.is_table(), into.is_table()) {
         (false, false) => {
             // These are not tables so we have nothing to merge
@@ -361,15 +458,88 @@ pub fn merge(into: &mut Toml, from: &Toml) -> Result<()> {
                     continue;
                 }
                 // Two entries to compare, recurse
-                merge(into_table.get_mut(key).unwrap(), val)?;
+                merge(into_table.get_mut(key).unwrap(), val, names)?;
             }
             Ok(())
         }
         _ => {
             // Trying to merge a table with something else
-            Err(anyhow!("Cannot merge config.toml with theme.toml because the following values have incompatibles types:\n- {}\n - {}", into, from))
+            Err(anyhow!("Cannot merge {} with {} because the following values have incompatibles types:\n- {}\n - {}", names.0, names.1, into, from))
+        }
+    }
+}
+
+/// The path of the config file of the given environment: `config.production.toml` for
+/// `config.toml` and `production`
+pub fn env_config_path(config_path: &Path, env: &str) -> PathBuf {
+    let stem = config_path.file_stem().unwrap_or_default().to_string_lossy();
+    match config_path.extension() {
+        Some(ext) => {
+            config_path.with_file_name(format!("{}.{}.{}", stem, env, ext.to_string_lossy()))
+        }
+        None => config_path.with_file_name(format!("{}.{}", stem, env)),
+    }
+}
+
+/// The config keys set by a `ZOLA_*` environment variable, `__` separating nested keys.
+/// Keys in uppercase are lowercased.
+fn env_var_keys(name: &str) -> Vec<String> {
+    name[ENV_VAR_PREFIX.len()..]
+        .split("__")
+        .map(|k| if k.chars().any(|c| c.is_lowercase()) { k.to_string() } else { k.to_lowercase() })
+        .collect()
+}
+
+/// Whether the environment variable overrides a config value
+fn is_env_override(name: &str) -> bool {
+    name.starts_with(ENV_VAR_PREFIX)
+        && !RESERVED_ENV_VARS.contains(&name)
+        && ENV_OVERRIDABLE_KEYS.contains(&env_var_keys(name)[0].as_str())
+}
+
+/// All the `ZOLA_*` environment variables overriding config values, sorted by name
+pub fn env_overrides() -> Vec<(String, String)> {
+    filter_env_overrides(std::env::vars())
+}
+
+/// The given environment variables that override config values, sorted by name
+fn filter_env_overrides(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    let mut vars: Vec<_> = vars.filter(|(k, _)| is_env_override(k)).collect();
+    vars.sort();
+    vars
+}
+
+/// Sets the values of `ZOLA_*` environment variables in the config, `__` separating nested keys:
+/// `ZOLA_BASE_URL` sets `base_url` and `ZOLA_EXTRA__analytics_id` sets `analytics_id` in `[extra]`.
+/// Keys in uppercase are lowercased and values are parsed as TOML, falling back to strings.
+fn apply_env_overrides(config: &mut Toml, vars: Vec<(String, String)>) -> Result<()> {
+    for (name, raw_value) in vars {
+        let keys = env_var_keys(&name);
+        if keys.iter().any(|k| k.is_empty()) {
+            bail!("Invalid config override in environment variable `{}`", name);
         }
+
+        let value = libs::toml::from_str::<libs::toml::Table>(&format!("value = {}", raw_value))
+            .ok()
+            .and_then(|mut t| t.remove("value"))
+            .unwrap_or_else(|| Toml::String(raw_value.clone()));
+
+        let (last, parents) = keys.split_last().unwrap();
+        let mut table = config
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("The config file should be a TOML table"))?;
+        for key in parents {
+            table = table
+                .entry(key.as_str())
+                .or_insert_with(|| Toml::Table(Default::default()))
+                .as_table_mut()
+                .ok_or_else(|| {
+                    anyhow!("Cannot set `{}` from `{}`: `{}` is not a table", last, name, key)
+                })?;
+        }
+        table.insert(last.to_string(), value);
     }
+    Ok(())
 }
 
 impl Default for Config {
@@ -390,6 +560,7 @@ impl Default for Config {
             compile_sass: false,
             minify_html: false,
             mode: Mode::Build,
+            environment: None,
             build_search_index: false,
             ignored_content: Vec::new(),
             ignored_content_globset: None,
@@ -400,6 +571,10 @@ impl Default for Config {
             preserve_dotfiles_in_output: false,
             link_checker: link_checker::LinkChecker::default(),
             slugify: slugify::Slugify::default(),
+            permalinks: HashMap::new(),
+            podcast: podcast::Podcast::default(),
+            image_processing: image_processing::ImageProcessing::default(),
+            static_images: None,
             search: search::Search::default(),
             markdown: markup::Markdown::default(),
             extra: HashMap::new(),
@@ -1066,4 +1241,168 @@ base_url = "example.com"
         let config = Config::parse(config).unwrap();
         assert!(config.generate_robots_txt);
     }
+
+    #[test]
+    fn can_get_env_config_path() {
+        assert_eq!(
+            env_config_path(Path::new("site/config.toml"), "production"),
+            PathBuf::from("site/config.production.toml")
+        );
+    }
+
+    #[test]
+    fn can_merge_env_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+base_url = "https://example.com"
+generate_feeds = true
+[extra]
+author = "Vincent"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("config.staging.toml"),
+            r#"
+base_url = "https://staging.example.com"
+[extra]
+robots = "noindex"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file_with_env(&path, Some("staging")).unwrap();
+        assert_eq!(config.base_url, "https://staging.example.com");
+        assert_eq!(config.environment, Some("staging".to_string()));
+        // Values only in the main config file are kept
+        assert!(config.generate_feeds);
+        assert_eq!(config.extra["author"].as_str(), Some("Vincent"));
+        assert_eq!(config.extra["robots"].as_str(), Some("noindex"));
+
+        // No config file for that environment: we only set it
+        let config = Config::from_file_with_env(&path, Some("preview")).unwrap();
+        assert_eq!(config.environment, Some("preview".to_string()));
+        assert_eq!(config.base_url, "https://example.com");
+    }
+
+    #[test]
+    fn env_config_merge_errors_name_the_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "base_url = \"https://example.com\"\n[markdown]\n").unwrap();
+        std::fs::write(dir.path().join("config.staging.toml"), "markdown = true").unwrap();
+
+        let err = Config::from_file_with_env(&path, Some("staging")).unwrap_err().to_string();
+        assert!(err.starts_with(&format!(
+            "Cannot merge {} with {}",
+            dir.path().join("config.staging.toml").display(),
+            path.display()
+        )));
+    }
+
+    #[test]
+    fn can_override_config_with_env_vars() {
+        let mut value: Toml = libs::toml::from_str(
+            r#"
+base_url = "https://example.com"
+[markdown]
+highlight_code = false
+[extra]
+menu = ["a"]
+"#,
+        )
+        .unwrap();
+        let vars = vec![
+            ("ZOLA_BASE_URL".to_string(), "https://preview.example.com".to_string()),
+            ("ZOLA_MARKDOWN__HIGHLIGHT_CODE".to_string(), "true".to_string()),
+            ("ZOLA_EXTRA__analytics_id".to_string(), "UA-1234".to_string()),
+            ("ZOLA_EXTRA__social__twitter".to_string(), "zola".to_string()),
+        ];
+        apply_env_overrides(&mut value, vars).unwrap();
+        let config = Config::parse(&libs::toml::to_string(&value).unwrap()).unwrap();
+        assert_eq!(config.base_url, "https://preview.example.com");
+        assert!(config.markdown.highlight_code);
+        assert_eq!(config.extra["analytics_id"].as_str(), Some("UA-1234"));
+        assert_eq!(config.extra["social"]["twitter"].as_str(), Some("zola"));
+        assert_eq!(config.extra["menu"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ignores_env_vars_that_are_not_config_keys() {
+        assert!(is_env_override("ZOLA_BASE_URL"));
+        assert!(is_env_override("ZOLA_EXTRA__analytics_id"));
+        assert!(!is_env_override("ZOLA_VERSION"));
+        assert!(!is_env_override("ZOLA_ENV"));
+        assert!(!is_env_override("ZOLA_SOMETHING_ELSE"));
+
+        let vars = vec![
+            // Set by hosting providers like Netlify to pick the version of Zola
+            ("ZOLA_VERSION".to_string(), "0.17.1".to_string()),
+            ("ZOLA_ENV".to_string(), "production".to_string()),
+            ("ZOLA_BASE_URL".to_string(), "https://preview.example.com".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+        let overrides = filter_env_overrides(vars.into_iter());
+        assert_eq!(overrides.len(), 1);
+        let mut value: Toml = libs::toml::from_str("base_url = \"https://example.com\"").unwrap();
+        apply_env_overrides(&mut value, overrides).unwrap();
+        let config = Config::parse(&libs::toml::to_string(&value).unwrap()).unwrap();
+        assert_eq!(config.base_url, "https://preview.example.com");
+    }
+
+    #[test]
+    fn static_images_are_only_enabled_with_their_section() {
+        let config = Config::parse("base_url = \"https://example.com\"").unwrap();
+        assert!(config.static_images.is_none());
+
+        let config_str = r#"
+base_url = "https://example.com"
+[static_images]
+max_width = 1600
+formats = ["webp"]
+        "#;
+        let config = Config::parse(config_str).unwrap();
+        let static_images = config.static_images.unwrap();
+        assert_eq!(static_images.max_width, Some(1600));
+        assert!(static_images.is_included(Path::new("photos/cat.jpg")));
+        assert!(!static_images.is_included(Path::new("photos/cat.svg")));
+    }
+
+    #[test]
+    fn errors_on_invalid_static_images_format() {
+        let config_str = r#"
+base_url = "https://example.com"
+[static_images]
+formats = ["bmp"]
+        "#;
+        let err = Config::parse(config_str).unwrap_err();
+        assert!(format!("{:?}", err).contains("Invalid static_images.formats value: bmp"));
+    }
+
+    #[test]
+    fn can_add_admonition_types() {
+        let config_str = r#"
+base_url = "https://example.com"
+[markdown.admonitions]
+alerts = true
+types = { danger = "Danger", note = "Remarque" }
+        "#;
+        let config = Config::parse(config_str).unwrap();
+        let admonitions = &config.markdown.admonitions;
+        assert!(admonitions.alerts);
+        assert_eq!(admonitions.default_title("DANGER"), Some("Danger"));
+        assert_eq!(admonitions.default_title("note"), Some("Remarque"));
+        assert_eq!(admonitions.default_title("tip"), Some("Tip"));
+        assert_eq!(admonitions.default_title("nope"), None);
+    }
+
+    #[test]
+    fn errors_when_overriding_a_value_that_is_not_a_table() {
+        let mut value: Toml = libs::toml::from_str("base_url = \"https://example.com\"").unwrap();
+        let vars = vec![("ZOLA_BASE_URL__NOPE".to_string(), "1".to_string())];
+        assert!(apply_env_overrides(&mut value, vars).is_err());
+    }
 }
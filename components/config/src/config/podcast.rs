@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// The show information used in the feeds of the sections marked as a podcast
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Podcast {
+    /// The author of the show, defaults to the `author` of the config in the feed
+    pub author: Option<String>,
+    /// The name of the owner of the show, used by directories to contact them
+    pub owner_name: Option<String>,
+    /// The email of the owner of the show
+    pub owner_email: Option<String>,
+    /// The artwork of the show, either a URL or a path in `static`
+    pub image: Option<String>,
+    /// The Apple Podcasts categories, a subcategory being written as `Technology > Tech News`
+    pub categories: Vec<String>,
+    /// Whether the show contains explicit content, defaults to `false`
+    pub explicit: bool,
+    /// The globally unique identifier of the show, see the Podcasting 2.0 `podcast:guid` tag
+    pub guid: Option<String>,
+    /// Whether the show can be imported by other platforms, see the Podcasting 2.0
+    /// `podcast:locked` tag. Defaults to `false`
+    pub locked: bool,
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use libs::globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// The formats the static images can also be converted to
+const CONVERSION_FORMATS: [&str; 2] = ["webp", "avif"];
+
+/// Re-encoding of the images of `static` and of the page assets when they are copied to the
+/// output directory. Only enabled if the `[static_images]` section is in the config.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StaticImages {
+    /// The quality of the JPEGs and of the lossy conversions, in percent. Defaults to the
+    /// ones of `resize_image`
+    pub quality: Option<u8>,
+    /// Larger images are scaled down to fit, keeping their aspect ratio
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// Other formats each image is also converted to, written next to it with the extension
+    /// of the format, eg `photo.webp` for `photo.jpg`. Either `webp` or `avif`
+    pub formats: Vec<String>,
+    /// The glob patterns of the images to optimize, relative to `static` or to `content` for
+    /// page assets. Defaults to all the JPEG and PNG files
+    pub include: Vec<String>,
+    /// The glob patterns of the images to copy as they are even if they are included
+    pub exclude: Vec<String>,
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub include_globset: Option<GlobSet>,
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub exclude_globset: Option<GlobSet>,
+}
+
+impl Default for StaticImages {
+    fn default() -> Self {
+        Self {
+            quality: None,
+            max_width: None,
+            max_height: None,
+            formats: Vec::new(),
+            include: vec!["**/*.{jpg,jpeg,JPG,JPEG,png,PNG}".to_string()],
+            exclude: Vec::new(),
+            include_globset: None,
+            exclude_globset: None,
+        }
+    }
+}
+
+fn build_glob_set(patterns: &[String], name: &str) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => builder.add(glob),
+            Err(e) => {
+                bail!("Invalid static_images.{} glob pattern: {}, error = {}", name, pattern, e)
+            }
+        };
+    }
+    Ok(builder.build()?)
+}
+
+impl StaticImages {
+    /// Checks the options and compiles the glob patterns
+    pub fn resolve(&mut self) -> Result<()> {
+        if let Some(quality) = self.quality {
+            if quality == 0 || quality > 100 {
+                bail!("static_images.quality must be in range 1-100");
+            }
+        }
+        if self.max_width == Some(0) || self.max_height == Some(0) {
+            bail!("static_images.max_width and max_height must be greater than 0");
+        }
+        for format in &self.formats {
+            if !CONVERSION_FORMATS.contains(&format.as_str()) {
+                bail!(
+                    "Invalid static_images.formats value: {}, expected one of {}",
+                    format,
+                    CONVERSION_FORMATS.join(", ")
+                );
+            }
+        }
+        self.include_globset = Some(build_glob_set(&self.include, "include")?);
+        self.exclude_globset = Some(build_glob_set(&self.exclude, "exclude")?);
+        Ok(())
+    }
+
+    /// Whether the file at that path, relative to `static` or `content`, is optimized
+    pub fn is_included(&self, relative_path: &Path) -> bool {
+        let matches =
+            |gs: &Option<GlobSet>| gs.as_ref().is_some_and(|gs| gs.is_match(relative_path));
+        matches(&self.include_globset) && !matches(&self.exclude_globset)
+    }
+}
@@ -15,6 +15,8 @@ pub struct TaxonomyConfig {
     pub render: bool,
     /// Whether to generate a feed only for each taxonomy term, defaults to `false`
     pub feed: bool,
+    /// Whether `/` in terms creates nested terms, eg `programming/rust`, defaults to `false`
+    pub hierarchical: bool,
 }
 
 impl Default for TaxonomyConfig {
@@ -26,6 +28,7 @@ impl Default for TaxonomyConfig {
             paginate_path: None,
             render: true,
             feed: false,
+            hierarchical: false,
         }
     }
 }
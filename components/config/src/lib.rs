@@ -5,18 +5,22 @@ mod theme;
 use std::path::Path;
 
 pub use crate::config::{
+    image_processing::{ImageProcessing, SocialCardTemplate},
     languages::LanguageOptions,
     link_checker::LinkChecker,
     link_checker::LinkCheckerLevel,
+    markup::{Admonitions, MarkdownExtensions, MathRendering},
+    podcast::Podcast,
     search::{IndexFormat, Search},
     slugify::Slugify,
+    static_images::StaticImages,
     taxonomies::TaxonomyConfig,
-    Config,
+    {env_config_path, env_overrides, Config},
 };
 use errors::Result;
 
-/// Get and parse the config.
+/// Get and parse the config for the given environment, if any.
 /// If it doesn't succeed, exit
-pub fn get_config(filename: &Path) -> Result<Config> {
-    Config::from_file(filename)
+pub fn get_config(filename: &Path, env: Option<&str>) -> Result<Config> {
+    Config::from_file_with_env(filename, env)
 }
@@ -1,7 +1,13 @@
 mod page;
+mod podcast;
 mod section;
 mod split;
+mod term;
 
 pub use page::PageFrontMatter;
+pub use podcast::{media_mime_type, EpisodeFrontMatter};
 pub use section::SectionFrontMatter;
-pub use split::{split_page_content, split_section_content};
+pub use split::{
+    front_matter_lines, split_page_content, split_section_content, split_term_content,
+};
+pub use term::TermFrontMatter;
@@ -9,6 +9,7 @@ use time::{Date, OffsetDateTime, PrimitiveDateTime};
 use errors::{bail, Result};
 use utils::de::{fix_toml_dates, from_unknown_datetime};
 
+use crate::front_matter::podcast::EpisodeFrontMatter;
 use crate::front_matter::split::RawFrontMatter;
 
 /// The front matter of every page
@@ -37,6 +38,18 @@ pub struct PageFrontMatter {
     /// The converted date into a (year, month, day) tuple
     #[serde(default, skip_deserializing)]
     pub datetime_tuple: Option<(i32, u8, u8)>,
+    /// The page is only rendered from that date on
+    #[serde(default, deserialize_with = "from_unknown_datetime")]
+    pub publish_date: Option<String>,
+    /// Datetime the page is published
+    #[serde(default, skip_deserializing)]
+    pub publish_datetime: Option<OffsetDateTime>,
+    /// The page is not rendered anymore from that date on
+    #[serde(default, deserialize_with = "from_unknown_datetime")]
+    pub expiry_date: Option<String>,
+    /// Datetime the page expires
+    #[serde(default, skip_deserializing)]
+    pub expiry_datetime: Option<OffsetDateTime>,
     /// Whether this page is a draft
     pub draft: bool,
     /// Prevent generation of a folder for current page
@@ -66,6 +79,8 @@ pub struct PageFrontMatter {
     /// Defaults to `true` but is only used if search if explicitly enabled in the config.
     #[serde(skip_serializing)]
     pub in_search_index: bool,
+    /// The episode information if the page is an episode of a podcast
+    pub podcast: Option<EpisodeFrontMatter>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -123,6 +138,22 @@ impl PageFrontMatter {
             }
         }
 
+        if let Some(ref date) = f.publish_date {
+            if f.publish_datetime.is_none() {
+                bail!("`publish_date` could not be parsed: {}.", date);
+            }
+        }
+
+        if let Some(ref date) = f.expiry_date {
+            if f.expiry_datetime.is_none() {
+                bail!("`expiry_date` could not be parsed: {}.", date);
+            }
+        }
+
+        if let Some(ref podcast) = f.podcast {
+            podcast.validate()?;
+        }
+
         Ok(f)
     }
 
@@ -135,6 +166,19 @@ impl PageFrontMatter {
         self.updated_datetime = self.updated.as_ref().map(|s| s.as_ref()).and_then(parse_datetime);
         self.updated_datetime_tuple =
             self.updated_datetime.map(|dt| (dt.year(), dt.month().into(), dt.day()));
+
+        self.publish_datetime = self.publish_date.as_deref().and_then(parse_datetime);
+        self.expiry_datetime = self.expiry_date.as_deref().and_then(parse_datetime);
+    }
+
+    /// Whether the page has a `publish_date` in the future
+    pub fn is_scheduled(&self) -> bool {
+        self.publish_datetime.is_some_and(|d| d > OffsetDateTime::now_utc())
+    }
+
+    /// Whether the page has an `expiry_date` in the past
+    pub fn is_expired(&self) -> bool {
+        self.expiry_datetime.is_some_and(|d| d <= OffsetDateTime::now_utc())
     }
 
     pub fn weight(&self) -> usize {
@@ -154,6 +198,10 @@ impl Default for PageFrontMatter {
             date: None,
             datetime: None,
             datetime_tuple: None,
+            publish_date: None,
+            publish_datetime: None,
+            expiry_date: None,
+            expiry_datetime: None,
             draft: false,
             render: true,
             slug: None,
@@ -163,6 +211,7 @@ impl Default for PageFrontMatter {
             authors: Vec::new(),
             aliases: Vec::new(),
             template: None,
+            podcast: None,
             extra: Map::new(),
         }
     }
@@ -563,4 +612,26 @@ authors:
             res2.authors
         );
     }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+publish_date = 2999-01-01
+expiry_date = 2000-01-01T10:00:00Z
+"#); "toml")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+publish_date: 2999-01-01
+expiry_date: 2000-01-01T10:00:00Z
+"#); "yaml")]
+    fn can_parse_publish_and_expiry_dates(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content).unwrap();
+        assert_eq!(res.publish_datetime, Some(datetime!(2999-01-01 0:00 UTC)));
+        assert!(res.is_scheduled());
+        assert_eq!(res.expiry_datetime, Some(datetime!(2000-01-01 10:00 UTC)));
+        assert!(res.is_expired());
+    }
+
+    #[test]
+    fn errors_on_invalid_publish_date() {
+        let content = &RawFrontMatter::Toml(r#"publish_date = "next week""#);
+        assert!(PageFrontMatter::parse(content).is_err());
+    }
 }
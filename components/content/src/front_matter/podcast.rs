@@ -0,0 +1,126 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use errors::{bail, Result};
+
+/// The `[podcast]` table of the front matter of a page that is a podcast episode
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EpisodeFrontMatter {
+    /// The audio file of the episode: the name of a colocated asset, a path in `static`
+    /// starting with `/` or a URL. Defaults to the first audio asset of the page
+    pub audio: Option<String>,
+    /// The size in bytes of the audio file, only needed if it is not a colocated asset
+    pub length: Option<u64>,
+    /// The MIME type of the audio file, guessed from its extension if not set
+    pub mime_type: Option<String>,
+    /// The duration of the episode, either in seconds or as `HH:MM:SS`
+    #[serde(deserialize_with = "from_seconds_or_string")]
+    pub duration: Option<String>,
+    /// The episode number
+    pub episode: Option<u32>,
+    /// The season number
+    pub season: Option<u32>,
+    /// One of `full`, `trailer` or `bonus`
+    pub episode_type: Option<String>,
+    /// Whether the episode contains explicit content, defaults to the show setting
+    pub explicit: Option<bool>,
+    /// The artwork of the episode, either a URL or a path like the `audio` one
+    pub image: Option<String>,
+}
+
+fn from_seconds_or_string<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Duration {
+        Seconds(u64),
+        Text(String),
+    }
+
+    Ok(Option::<Duration>::deserialize(deserializer)?.map(|d| match d {
+        Duration::Seconds(s) => s.to_string(),
+        Duration::Text(s) => s,
+    }))
+}
+
+impl EpisodeFrontMatter {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref duration) = self.duration {
+            let parts: Vec<_> = duration.split(':').collect();
+            if parts.len() > 3 || parts.iter().any(|p| p.is_empty() || p.parse::<u64>().is_err()) {
+                bail!(
+                    "`podcast.duration` needs to be a number of seconds or `HH:MM:SS`, got `{}`",
+                    duration
+                );
+            }
+        }
+
+        if let Some(ref episode_type) = self.episode_type {
+            if !["full", "trailer", "bonus"].contains(&episode_type.as_str()) {
+                bail!(
+                    "`podcast.episode_type` needs to be one of `full`, `trailer` or `bonus`, got `{}`",
+                    episode_type
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Guesses the MIME type of an audio or video file from its extension, as expected in
+/// the `<enclosure>` of a feed
+pub fn media_mime_type(filename: &str) -> Option<&'static str> {
+    let (_, extension) = filename.rsplit_once('.')?;
+    let mime_type = match extension.to_ascii_lowercase().as_str() {
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/x-m4a",
+        "aac" => "audio/aac",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "m4v" => "video/x-m4v",
+        "mov" => "video/quicktime",
+        _ => return None,
+    };
+    Some(mime_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::front_matter::split::RawFrontMatter;
+
+    #[test]
+    fn can_parse_duration_in_seconds_or_text() {
+        for (raw, expected) in
+            [("duration = 3600", "3600"), ("duration = \"01:00:00\"", "01:00:00")]
+        {
+            let episode: EpisodeFrontMatter = RawFrontMatter::Toml(raw).deserialize().unwrap();
+            assert!(episode.validate().is_ok());
+            assert_eq!(episode.duration.as_deref(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn errors_on_invalid_episode() {
+        for raw in
+            ["duration = \"an hour\"", "duration = \"1:2:3:4\"", "episode_type = \"full-ish\""]
+        {
+            let episode: EpisodeFrontMatter = RawFrontMatter::Toml(raw).deserialize().unwrap();
+            assert!(episode.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn can_guess_media_mime_type() {
+        assert_eq!(media_mime_type("episode-1.MP3"), Some("audio/mpeg"));
+        assert_eq!(media_mime_type("episode.m4a"), Some("audio/x-m4a"));
+        assert_eq!(media_mime_type("cover.jpg"), None);
+        assert_eq!(media_mime_type("mp3"), None);
+    }
+}
@@ -63,6 +63,9 @@ pub struct SectionFrontMatter {
     /// Optional template for all pages in this section (including the pages of children section)
     #[serde(skip_serializing)]
     pub page_template: Option<String>,
+    /// Optional pattern for the path of the pages of this section, eg `/:year/:month/:slug/`
+    #[serde(skip_serializing)]
+    pub permalink: Option<String>,
     /// All aliases for that page. Zola will create HTML templates that will
     /// redirect to this
     #[serde(skip_serializing)]
@@ -70,6 +73,9 @@ pub struct SectionFrontMatter {
     /// Whether to generate a feed for the current section
     #[serde(skip_serializing)]
     pub generate_feeds: bool,
+    /// Whether the section is a podcast, its pages being the episodes.
+    /// Its RSS feed will then have the iTunes and Podcasting 2.0 tags. Defaults to `false`
+    pub podcast: bool,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -112,8 +118,10 @@ impl Default for SectionFrontMatter {
             in_search_index: true,
             transparent: false,
             page_template: None,
+            permalink: None,
             aliases: Vec::new(),
             generate_feeds: false,
+            podcast: false,
             extra: Map::new(),
             draft: false,
         }
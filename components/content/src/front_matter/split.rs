@@ -7,6 +7,7 @@ use libs::{serde_yaml, toml};
 
 use crate::front_matter::page::PageFrontMatter;
 use crate::front_matter::section::SectionFrontMatter;
+use crate::front_matter::term::TermFrontMatter;
 
 static TOML_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
@@ -70,6 +71,12 @@ fn split_content<'c>(file_path: &Path, content: &'c str) -> Result<(RawFrontMatt
     }
 }
 
+/// How many lines of the file are before the content returned by the `split_*_content`
+/// functions, that content being the end of the file
+pub fn front_matter_lines(file_content: &str, content: &str) -> usize {
+    file_content[..file_content.len() - content.len()].matches('\n').count()
+}
+
 /// Split a file between the front matter and its content.
 /// Returns a parsed `SectionFrontMatter` and the rest of the content
 pub fn split_section_content<'c>(
@@ -97,6 +104,19 @@ pub fn split_page_content<'c>(
     Ok((meta, content))
 }
 
+/// Split a file between the front matter and its content
+/// Returns a parsed `TermFrontMatter` and the rest of the content
+pub fn split_term_content<'c>(
+    file_path: &Path,
+    content: &'c str,
+) -> Result<(TermFrontMatter, &'c str)> {
+    let (front_matter, content) = split_content(file_path, content)?;
+    let meta = TermFrontMatter::parse(&front_matter).with_context(|| {
+        format!("Error when parsing front matter of term `{}`", file_path.to_string_lossy())
+    })?;
+    Ok((meta, content))
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
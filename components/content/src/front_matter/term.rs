@@ -0,0 +1,65 @@
+use libs::tera::{Map, Value};
+use serde::{Deserialize, Serialize};
+
+use errors::Result;
+use utils::de::fix_toml_dates;
+
+use crate::front_matter::split::RawFrontMatter;
+
+/// The front matter of the optional file describing a taxonomy term
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TermFrontMatter {
+    /// <title> of the term page, the term name is used if not set
+    pub title: Option<String>,
+    /// Description in <meta> that appears when linked, e.g. on twitter
+    pub description: Option<String>,
+    /// Optional template, if we want to specify which template to render for that term
+    #[serde(skip_serializing)]
+    pub template: Option<String>,
+    /// Any extra parameter present in the front matter
+    pub extra: Map<String, Value>,
+}
+
+impl TermFrontMatter {
+    pub fn parse(raw: &RawFrontMatter) -> Result<TermFrontMatter> {
+        let mut f: TermFrontMatter = raw.deserialize()?;
+
+        f.extra = match fix_toml_dates(f.extra) {
+            Value::Object(o) => o,
+            _ => unreachable!("Got something other than a table in term extra"),
+        };
+
+        Ok(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RawFrontMatter, TermFrontMatter};
+
+    #[test]
+    fn can_parse_term_front_matter() {
+        let content = r#"
+title = "Rust"
+description = "All about Rust"
+template = "rust.html"
+
+[extra]
+cover = "rust.png"
+"#;
+        let res = TermFrontMatter::parse(&RawFrontMatter::Toml(content)).unwrap();
+        assert_eq!(res.title.unwrap(), "Rust");
+        assert_eq!(res.template.unwrap(), "rust.html");
+        assert_eq!(res.extra["cover"], "rust.png");
+    }
+
+    #[test]
+    fn errors_on_unknown_fields() {
+        let content = r#"
+title = "Rust"
+date = 2002-10-02
+"#;
+        assert!(TermFrontMatter::parse(&RawFrontMatter::Toml(content)).is_err());
+    }
+}
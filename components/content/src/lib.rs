@@ -12,10 +12,10 @@ mod types;
 mod utils;
 
 pub use file_info::FileInfo;
-pub use front_matter::{PageFrontMatter, SectionFrontMatter};
+pub use front_matter::{EpisodeFrontMatter, PageFrontMatter, SectionFrontMatter};
 pub use library::Library;
-pub use page::Page;
+pub use page::{Enclosure, Page};
 pub use pagination::Paginator;
 pub use section::Section;
-pub use taxonomies::{Taxonomy, TaxonomyTerm};
+pub use taxonomies::{is_terms_directory, Taxonomy, TaxonomyTerm, TaxonomyTermLink};
 pub use types::*;
@@ -174,6 +174,14 @@ impl Library {
     /// Sort all sections pages according to sorting method given
     /// Pages that cannot be sorted are set to the section.ignored_pages instead
     pub fn sort_section_pages(&mut self) {
+        // Pages are stored in a hashmap so we sort unsorted sections by path to keep their
+        // order stable from one build to the next
+        for section in self.sections.values_mut() {
+            if section.meta.sort_by == SortBy::None {
+                section.pages.sort();
+            }
+        }
+
         let mut updates = AHashMap::new();
         for (path, section) in &self.sections {
             let pages: Vec<_> = section.pages.iter().map(|p| &self.pages[p]).collect();
@@ -425,6 +433,24 @@ mod tests {
         assert!(collisions[0].1.contains(&section2.file.path));
     }
 
+    #[test]
+    fn can_find_collisions_from_permalink_patterns() {
+        let config = Config::default_for_test();
+        let mut library = Library::default();
+        for (path, content) in [
+            ("content/posts/hello.md", "+++\ndate = 2018-01-05\n+++\n"),
+            ("content/news/hello.md", "+++\ndate = 2018-03-10\n+++\n"),
+        ] {
+            let mut page = Page::parse(Path::new(path), content, &config, Path::new("")).unwrap();
+            page.apply_permalink_pattern("/:year/:slug/", &config).unwrap();
+            library.insert_page(page);
+        }
+
+        let collisions = library.find_path_collisions();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].0, "/2018/hello/");
+    }
+
     #[derive(Debug, Clone)]
     enum PageSort {
         None,
@@ -685,6 +711,51 @@ mod tests {
         assert_eq!(authors.items[0].permalink, "http://a-website.com/authors/vincent-prouillet/");
     }
 
+    #[test]
+    fn can_make_hierarchical_taxonomies() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().taxonomies = vec![TaxonomyConfig {
+            name: "categories".to_string(),
+            hierarchical: true,
+            ..TaxonomyConfig::default()
+        }];
+        config.slugify_taxonomies();
+
+        let page1 = create_page_w_taxa(
+            "a.md",
+            "en",
+            vec![("categories", vec!["Programming/Rust/Async", "Programming/Rust"])],
+        );
+        let page2 = create_page_w_taxa("b.md", "en", vec![("categories", vec!["Programming/Go"])]);
+        let taxonomies = taxonomies!(config, [page1, page2]);
+
+        let categories = &taxonomies[0];
+        let names: Vec<_> = categories.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Programming", "Programming/Go", "Programming/Rust", "Programming/Rust/Async"]
+        );
+
+        let root = &categories.items[0];
+        assert_eq!(root.permalink, "http://a-website.com/categories/programming/");
+        assert_eq!(root.pages.len(), 2);
+        assert_eq!(root.depth, 0);
+        assert!(root.parent.is_none());
+        let children: Vec<_> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(children, vec!["Programming/Go", "Programming/Rust"]);
+
+        let rust = &categories.items[2];
+        assert_eq!(rust.permalink, "http://a-website.com/categories/programming/rust/");
+        // a.md is in both the term and its child but only listed once
+        assert_eq!(rust.pages.len(), 1);
+        assert_eq!(rust.parent.as_ref().unwrap().name, "Programming");
+
+        let leaf = &categories.items[3];
+        assert_eq!(leaf.path, "/categories/programming/rust/async/");
+        assert_eq!(leaf.depth, 2);
+        assert!(leaf.children.is_empty());
+    }
+
     #[test]
     fn can_make_multiple_language_taxonomies() {
         let mut config = Config::default_for_test();
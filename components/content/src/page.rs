@@ -3,25 +3,30 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use libs::once_cell::sync::Lazy;
-use libs::regex::Regex;
-use libs::tera::{Context as TeraContext, Tera};
+use libs::regex::{Captures, Regex};
+use libs::tera::{Context as TeraContext, Tera, Value};
+use serde::Serialize;
 
 use config::Config;
-use errors::{Context, Result};
+use errors::{bail, Context, Result};
 use markdown::{render_content, RenderContext};
+use utils::site::WikilinkIndex;
 use utils::slugs::slugify_paths;
 use utils::table_of_contents::Heading;
 use utils::templates::{render_template, ShortcodeDefinition};
 use utils::types::InsertAnchor;
 
 use crate::file_info::FileInfo;
-use crate::front_matter::{split_page_content, PageFrontMatter};
+use crate::front_matter::{
+    front_matter_lines, media_mime_type, split_page_content, PageFrontMatter,
+};
 use crate::library::Library;
 use crate::ser::SerializingPage;
 use crate::utils::get_reading_analytics;
 use crate::utils::{find_related_assets, has_anchor};
 use utils::anchors::has_anchor_id;
 use utils::fs::read_file;
+use utils::net::is_external_link;
 
 // Based on https://regex101.com/r/H2n38Z/1/tests
 // A regex parsing RFC3339 date followed by {_,-} and some characters
@@ -31,6 +36,21 @@ static RFC3339_DATE: Lazy<Regex> = Lazy::new(|| {
     ).unwrap()
 });
 
+// The tokens of permalink patterns, eg `:year` or `:extra.category`
+static PERMALINK_TOKEN: Lazy<Regex> = Lazy::new(|| Regex::new(r":(extra\.[\w-]+|[a-z]+)").unwrap());
+
+/// The media file of a podcast episode, as found in the `<enclosure>` of the RSS feed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Enclosure {
+    pub url: String,
+    /// The size of the file in bytes, 0 if unknown
+    pub length: u64,
+    pub mime_type: String,
+    /// The path of the file relative to the page if it is a colocated asset
+    #[serde(skip_serializing)]
+    pub asset: Option<String>,
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Page {
     /// All info about the actual file
@@ -41,10 +61,14 @@ pub struct Page {
     pub ancestors: Vec<String>,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// How many lines of the file are before `raw_content`
+    pub front_matter_lines: usize,
     /// All the non-md files we found next to the .md file
     pub assets: Vec<PathBuf>,
     /// All the non-md files we found next to the .md file
     pub serialized_assets: Vec<String>,
+    /// The audio file of the page if it is a podcast episode or has a colocated audio asset
+    pub enclosure: Option<Enclosure>,
     /// The HTML rendered of the page
     pub content: String,
     /// The slug of that page.
@@ -100,13 +124,14 @@ impl Page {
         config: &Config,
         base_path: &Path,
     ) -> Result<Page> {
-        let (meta, content) = split_page_content(file_path, content)?;
+        let (meta, raw_content) = split_page_content(file_path, content)?;
         let mut page = Page::new(file_path, meta, base_path);
+        page.front_matter_lines = front_matter_lines(content, raw_content);
 
         page.lang =
             page.file.find_language(&config.default_language, &config.other_languages_codes())?;
 
-        page.raw_content = content.to_string();
+        page.raw_content = raw_content.to_string();
         let (word_count, reading_time) = get_reading_analytics(&page.raw_content);
         page.word_count = Some(word_count);
         page.reading_time = Some(reading_time);
@@ -168,20 +193,87 @@ impl Page {
 
             format!("/{}", path)
         };
+        page.set_path(page.path.clone(), config);
+
+        Ok(page)
+    }
 
-        if !page.path.ends_with('/') {
-            page.path = format!("{}/", page.path);
+    fn set_path(&mut self, path: String, config: &Config) {
+        self.path = path;
+        if !self.path.ends_with('/') {
+            self.path = format!("{}/", self.path);
         }
 
-        page.components = page
+        self.components = self
             .path
             .split('/')
             .map(|p| p.to_string())
             .filter(|p| !p.is_empty())
             .collect::<Vec<_>>();
-        page.permalink = config.make_permalink(&page.path);
+        self.permalink = config.make_permalink(&self.path);
 
-        Ok(page)
+        if let Some(Enclosure { url, asset: Some(asset), .. }) = self.enclosure.as_mut() {
+            *url = format!("{}{}", self.permalink, asset);
+        }
+    }
+
+    /// Sets the path of the page from the permalink pattern of its section, eg
+    /// `/:year/:month/:slug/`. A `path` set in the front matter takes precedence.
+    pub fn apply_permalink_pattern(&mut self, pattern: &str, config: &Config) -> Result<()> {
+        if self.meta.path.is_some() {
+            return Ok(());
+        }
+
+        let mut error = None;
+        let path = PERMALINK_TOKEN.replace_all(pattern, |caps: &Captures| {
+            match self.permalink_token(&caps[1], config) {
+                Ok(value) => value,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    String::new()
+                }
+            }
+        });
+        if let Some(e) = error {
+            return Err(e).with_context(|| {
+                format!(
+                    "Failed to apply the permalink pattern `{}` to {}",
+                    pattern,
+                    self.file.path.display()
+                )
+            });
+        }
+
+        // Empty tokens, eg `:section` for pages of the index, should not create empty components
+        let mut path = path.split('/').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("/");
+        if self.lang != config.default_language {
+            path = format!("{}/{}", self.lang, path);
+        }
+        self.set_path(format!("/{}", path), config);
+        Ok(())
+    }
+
+    fn permalink_token(&self, token: &str, config: &Config) -> Result<String> {
+        if let Some(key) = token.strip_prefix("extra.") {
+            return match self.meta.extra.get(key) {
+                Some(Value::String(s)) => Ok(slugify_paths(s, config.slugify.paths)),
+                Some(Value::Number(n)) => Ok(n.to_string()),
+                Some(_) => bail!("`extra.{}` needs to be a string or a number", key),
+                None => bail!("`extra.{}` is not set in the front matter", key),
+            };
+        }
+
+        match token {
+            "year" | "month" | "day" => match self.meta.datetime_tuple {
+                Some((year, _, _)) if token == "year" => Ok(format!("{:04}", year)),
+                Some((_, month, _)) if token == "month" => Ok(format!("{:02}", month)),
+                Some((_, _, day)) => Ok(format!("{:02}", day)),
+                None => bail!("`:{}` needs the page to have a date", token),
+            },
+            "slug" => Ok(self.slug.clone()),
+            "section" => Ok(self.file.components.join("/")),
+            _ => bail!("Unknown token `:{}` in permalink pattern", token),
+        }
     }
 
     pub fn find_language(&mut self) {}
@@ -199,15 +291,84 @@ impl Page {
         } else {
             page.assets = vec![];
         }
+        page.enclosure = page.find_enclosure(config)?;
 
         Ok(page)
     }
 
+    /// Finds the audio file of the page: the one set in the `[podcast]` front matter or the
+    /// first colocated audio asset. The size and MIME type of colocated assets are detected.
+    fn find_enclosure(&self, config: &Config) -> Result<Option<Enclosure>> {
+        let podcast = self.meta.podcast.as_ref();
+        let mime_type = |name: &str| match podcast.and_then(|p| p.mime_type.clone()) {
+            Some(mime_type) => mime_type,
+            None => media_mime_type(name).unwrap_or("application/octet-stream").to_string(),
+        };
+        let parent = self.file.path.parent().unwrap();
+        let asset_name = |asset: &PathBuf| {
+            asset.strip_prefix(parent).ok()?.to_str().map(|s| s.replace('\\', "/"))
+        };
+
+        let asset = match podcast.and_then(|p| p.audio.as_deref()) {
+            Some(audio) if is_external_link(audio) || audio.starts_with('/') => {
+                let url = if audio.starts_with('/') {
+                    let mut url = config.make_permalink(audio);
+                    if !audio.ends_with('/') && url.ends_with('/') {
+                        url.pop(); // A file, not a page
+                    }
+                    url
+                } else {
+                    audio.to_string()
+                };
+                return Ok(Some(Enclosure {
+                    length: podcast.and_then(|p| p.length).unwrap_or(0),
+                    mime_type: mime_type(audio),
+                    url,
+                    asset: None,
+                }));
+            }
+            Some(audio) => {
+                let audio = audio.trim_start_matches("./");
+                match self.assets.iter().find(|a| asset_name(a).as_deref() == Some(audio)) {
+                    Some(asset) => asset,
+                    None => bail!(
+                        "`podcast.audio` is set to `{}` in {} but there is no such colocated asset",
+                        audio,
+                        self.file.path.display()
+                    ),
+                }
+            }
+            None => {
+                let is_audio = |a: &&PathBuf| {
+                    asset_name(a)
+                        .and_then(|n| media_mime_type(&n))
+                        .is_some_and(|m| m.starts_with("audio/"))
+                };
+                match self.assets.iter().find(is_audio) {
+                    Some(asset) => asset,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let name = asset_name(asset).unwrap();
+        let length = std::fs::metadata(asset)
+            .with_context(|| format!("Failed to read {}", asset.display()))?
+            .len();
+        Ok(Some(Enclosure {
+            url: format!("{}{}", self.permalink, name),
+            length,
+            mime_type: mime_type(&name),
+            asset: Some(name),
+        }))
+    }
+
     /// We need access to all pages url to render links relative to content
     /// so that can't happen at the same time as parsing
     pub fn render_markdown(
         &mut self,
         permalinks: &HashMap<String, String>,
+        wikilinks: &WikilinkIndex,
         tera: &Tera,
         config: &Config,
         anchor_insert: InsertAnchor,
@@ -222,7 +383,9 @@ impl Page {
             anchor_insert,
         );
         context.set_shortcode_definitions(shortcode_definitions);
+        context.set_wikilinks(wikilinks);
         context.set_current_page_path(&self.file.relative);
+        context.set_line_offset(self.front_matter_lines);
         context.tera_context.insert("page", &SerializingPage::new(self, None, false));
 
         let res = render_content(&self.raw_content, &context)
@@ -307,6 +470,7 @@ mod tests {
 
     use crate::Page;
     use config::{Config, LanguageOptions};
+    use utils::site::WikilinkIndex;
     use utils::slugs::SlugifyStrategy;
     use utils::types::InsertAnchor;
 
@@ -325,6 +489,7 @@ Hello world"#;
         let mut page = res.unwrap();
         page.render_markdown(
             &HashMap::default(),
+            &WikilinkIndex::default(),
             &Tera::default(),
             &config,
             InsertAnchor::None,
@@ -338,6 +503,27 @@ Hello world"#;
         assert_eq!(page.content, "<p>Hello world</p>\n".to_string());
     }
 
+    #[test]
+    fn math_errors_point_to_the_line_in_the_file() {
+        let mut config = Config::default_for_test();
+        config.markdown.math = config::MathRendering::MathMl;
+        let content = "+++\ntitle = \"Hello\"\n+++\nSome text\n\nand then $x + \\frac{1}$ here";
+        let mut page =
+            Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        let err = page
+            .render_markdown(
+                &HashMap::default(),
+                &WikilinkIndex::default(),
+                &Tera::default(),
+                &config,
+                InsertAnchor::None,
+                &HashMap::new(),
+            )
+            .unwrap_err();
+        let msg = format!("{:?}", err);
+        assert!(msg.contains("Invalid TeX math at line 6, column 10 of post.md"), "{}", msg);
+    }
+
     #[test]
     fn can_parse_author() {
         let config = Config::default_for_test();
@@ -353,6 +539,7 @@ Hello world"#;
         let mut page = res.unwrap();
         page.render_markdown(
             &HashMap::default(),
+            &WikilinkIndex::default(),
             &Tera::default(),
             &config,
             InsertAnchor::None,
@@ -382,6 +569,60 @@ Hello world"#;
         assert_eq!(page.permalink, "http://hello.com/posts/intro/hello-world/");
     }
 
+    #[test]
+    fn can_apply_permalink_pattern() {
+        let content = r#"
++++
+title = "Hello"
+date = 2018-01-05
+slug = "hello-world"
+[extra]
+category = "Rust Tips"
++++
+Hello world"#;
+        let config = Config::default();
+        let mut page =
+            Page::parse(Path::new("content/posts/hello.md"), content, &config, Path::new(""))
+                .unwrap();
+        page.apply_permalink_pattern("/:section/:year/:month/:day/:extra.category/:slug/", &config)
+            .unwrap();
+        assert_eq!(page.path, "/posts/2018/01/05/rust-tips/hello-world/");
+        assert_eq!(page.components.len(), 6);
+        assert_eq!(page.permalink, config.make_permalink("posts/2018/01/05/rust-tips/hello-world"));
+    }
+
+    #[test]
+    fn permalink_pattern_does_not_override_path() {
+        let content = r#"
++++
+path = "hello"
+date = 2018-01-05
++++
+Hello world"#;
+        let config = Config::default();
+        let mut page =
+            Page::parse(Path::new("content/posts/hello.md"), content, &config, Path::new(""))
+                .unwrap();
+        page.apply_permalink_pattern("/:year/:slug/", &config).unwrap();
+        assert_eq!(page.path, "/hello/");
+    }
+
+    #[test]
+    fn errors_on_invalid_permalink_pattern() {
+        let content = r#"
++++
+title = "Hello"
++++
+Hello world"#;
+        let config = Config::default();
+        let mut page =
+            Page::parse(Path::new("content/posts/hello.md"), content, &config, Path::new(""))
+                .unwrap();
+        assert!(page.apply_permalink_pattern("/:year/:slug/", &config).is_err());
+        assert!(page.apply_permalink_pattern("/:extra.missing/:slug/", &config).is_err());
+        assert!(page.apply_permalink_pattern("/:title/", &config).is_err());
+    }
+
     #[test]
     fn can_make_url_from_slug_only() {
         let content = r#"
@@ -523,6 +764,7 @@ Hello world
         let mut page = res.unwrap();
         page.render_markdown(
             &HashMap::default(),
+            &WikilinkIndex::default(),
             &Tera::default(),
             &config,
             InsertAnchor::None,
@@ -557,6 +799,7 @@ And here's another. [^3]
         let mut page = res.unwrap();
         page.render_markdown(
             &HashMap::default(),
+            &WikilinkIndex::default(),
             &Tera::default(),
             &config,
             InsertAnchor::None,
@@ -694,6 +937,54 @@ And here's another. [^3]
         assert_eq!(page.assets[0].file_name().unwrap().to_str(), Some("graph.jpg"));
     }
 
+    #[test]
+    fn page_with_audio_asset_gets_an_enclosure() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        create_dir(path.join("content")).expect("create content temp dir");
+        create_dir(path.join("content").join("podcast")).expect("create podcast temp dir");
+        let nested_path = path.join("content").join("podcast").join("episode-1");
+        create_dir(&nested_path).expect("create nested temp dir");
+        let mut f = File::create(nested_path.join("index.md")).unwrap();
+        f.write_all(b"+++\n[podcast]\nepisode = 1\n+++\n").unwrap();
+        File::create(nested_path.join("cover.jpg")).unwrap();
+        let mut f = File::create(nested_path.join("episode.mp3")).unwrap();
+        f.write_all(&[0; 42]).unwrap();
+
+        let mut page =
+            Page::from_file(nested_path.join("index.md").as_path(), &Config::default(), path)
+                .unwrap();
+        let enclosure = page.enclosure.clone().unwrap();
+        assert_eq!(enclosure.url, "http://a-website.com/podcast/episode-1/episode.mp3");
+        assert_eq!(enclosure.length, 42);
+        assert_eq!(enclosure.mime_type, "audio/mpeg");
+
+        // The URL follows the page when its path changes
+        page.apply_permalink_pattern("/episodes/:slug/", &Config::default()).unwrap();
+        assert_eq!(
+            page.enclosure.unwrap().url,
+            "http://a-website.com/episodes/episode-1/episode.mp3"
+        );
+
+        let mut f = File::create(nested_path.join("index.md")).unwrap();
+        f.write_all(
+            b"+++\n[podcast]\naudio = \"https://cdn.example.com/1.m4a\"\nlength = 1000\n+++\n",
+        )
+        .unwrap();
+        let page =
+            Page::from_file(nested_path.join("index.md").as_path(), &Config::default(), path)
+                .unwrap();
+        let enclosure = page.enclosure.unwrap();
+        assert_eq!(enclosure.url, "https://cdn.example.com/1.m4a");
+        assert_eq!(enclosure.length, 1000);
+        assert_eq!(enclosure.mime_type, "audio/x-m4a");
+
+        let mut f = File::create(nested_path.join("index.md")).unwrap();
+        f.write_all(b"+++\n[podcast]\naudio = \"missing.mp3\"\n+++\n").unwrap();
+        let res = Page::from_file(nested_path.join("index.md").as_path(), &Config::default(), path);
+        assert!(res.is_err());
+    }
+
     // https://github.com/getzola/zola/issues/1566
     #[test]
     fn colocated_page_with_slug_and_date_in_path() {
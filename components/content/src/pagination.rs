@@ -6,7 +6,7 @@ use std::path::PathBuf;
 
 use errors::{Context as ErrorContext, Result};
 use libs::tera::{to_value, Context, Tera, Value};
-use utils::templates::{check_template_fallbacks, render_template};
+use utils::templates::render_template;
 
 use crate::library::Library;
 use crate::ser::{SectionSerMode, SerializingPage, SerializingSection};
@@ -96,10 +96,7 @@ impl<'a> Paginator<'a> {
         theme: &Option<String>,
     ) -> Paginator<'a> {
         let paginate_by = taxonomy.kind.paginate_by.unwrap();
-        // Check for taxon-specific template, or use generic as fallback.
-        let specific_template = format!("{}/single.html", taxonomy.kind.name);
-        let template = check_template_fallbacks(&specific_template, tera, theme)
-            .unwrap_or("taxonomy_single.html");
+        let template = taxonomy.term_template(item, tera, theme);
         let mut paginator = Paginator {
             all_pages: Cow::Borrowed(&item.pages),
             pagers: Vec::with_capacity(item.pages.len() / paginate_by),
@@ -110,7 +107,7 @@ impl<'a> Paginator<'a> {
             path: item.path.clone(),
             paginate_path: taxonomy.kind.paginate_path().to_owned(),
             is_index: false,
-            template: template.to_string(),
+            template,
         };
 
         // taxonomy paginators have no sorting so we won't have to reverse
@@ -398,6 +395,14 @@ mod tests {
             path: "/some-tags/something/".to_string(),
             permalink: "https://vincent.is/some-tags/something/".to_string(),
             pages: library.pages.keys().cloned().collect(),
+            parent: None,
+            children: Vec::new(),
+            depth: 0,
+            file: None,
+            meta: Default::default(),
+            raw_content: String::new(),
+            front_matter_lines: 0,
+            content: String::new(),
         };
         let taxonomy = Taxonomy {
             kind: taxonomy_def,
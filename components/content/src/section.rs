@@ -8,11 +8,12 @@ use errors::{Context, Result};
 use markdown::{render_content, RenderContext};
 use utils::fs::read_file;
 use utils::net::is_external_link;
+use utils::site::WikilinkIndex;
 use utils::table_of_contents::Heading;
 use utils::templates::{render_template, ShortcodeDefinition};
 
 use crate::file_info::FileInfo;
-use crate::front_matter::{split_section_content, SectionFrontMatter};
+use crate::front_matter::{front_matter_lines, split_section_content, SectionFrontMatter};
 use crate::library::Library;
 use crate::ser::{SectionSerMode, SerializingSection};
 use crate::utils::{find_related_assets, get_reading_analytics, has_anchor};
@@ -32,6 +33,8 @@ pub struct Section {
     pub permalink: String,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// How many lines of the file are before `raw_content`
+    pub front_matter_lines: usize,
     /// The HTML rendered of the page
     pub content: String,
     /// All the non-md files we found next to the .md file
@@ -81,12 +84,13 @@ impl Section {
         config: &Config,
         base_path: &Path,
     ) -> Result<Section> {
-        let (meta, content) = split_section_content(file_path, content)?;
+        let (meta, raw_content) = split_section_content(file_path, content)?;
         let mut section = Section::new(file_path, meta, base_path);
+        section.front_matter_lines = front_matter_lines(content, raw_content);
         section.lang = section
             .file
             .find_language(&config.default_language, &config.other_languages_codes())?;
-        section.raw_content = content.to_string();
+        section.raw_content = raw_content.to_string();
         let (word_count, reading_time) = get_reading_analytics(&section.raw_content);
         section.word_count = Some(word_count);
         section.reading_time = Some(reading_time);
@@ -147,6 +151,7 @@ impl Section {
     pub fn render_markdown(
         &mut self,
         permalinks: &HashMap<String, String>,
+        wikilinks: &WikilinkIndex,
         tera: &Tera,
         config: &Config,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
@@ -160,7 +165,9 @@ impl Section {
             self.meta.insert_anchor_links,
         );
         context.set_shortcode_definitions(shortcode_definitions);
+        context.set_wikilinks(wikilinks);
         context.set_current_page_path(&self.file.relative);
+        context.set_line_offset(self.front_matter_lines);
         context
             .tera_context
             .insert("section", &SerializingSection::new(self, SectionSerMode::ForMarkdown));
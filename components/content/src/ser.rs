@@ -3,7 +3,9 @@ use std::path::Path;
 
 use serde::Serialize;
 
+use crate::front_matter::EpisodeFrontMatter;
 use crate::library::Library;
+use crate::page::Enclosure;
 use crate::{Page, Section};
 use libs::tera::{Map, Value};
 use utils::table_of_contents::Heading;
@@ -66,6 +68,8 @@ pub struct SerializingPage<'a> {
     assets: &'a [String],
     draft: bool,
     lang: &'a str,
+    podcast: &'a Option<EpisodeFrontMatter>,
+    enclosure: &'a Option<Enclosure>,
     lower: Option<Box<SerializingPage<'a>>>,
     higher: Option<Box<SerializingPage<'a>>>,
     translations: Vec<TranslatedContent<'a>>,
@@ -130,6 +134,8 @@ impl<'a> SerializingPage<'a> {
             assets: &page.serialized_assets,
             draft: page.meta.draft,
             lang: &page.lang,
+            podcast: &page.meta.podcast,
+            enclosure: &page.enclosure,
             lower,
             higher,
             translations,
@@ -162,6 +168,7 @@ pub struct SerializingSection<'a> {
     backlinks: Vec<BackLink<'a>>,
     generate_feeds: bool,
     transparent: bool,
+    podcast: bool,
 }
 
 #[derive(Debug)]
@@ -222,6 +229,7 @@ impl<'a> SerializingSection<'a> {
             lang: &section.lang,
             generate_feeds: section.meta.generate_feeds,
             transparent: section.meta.transparent,
+            podcast: section.meta.podcast,
             pages,
             subsections,
             translations,
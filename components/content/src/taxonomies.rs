@@ -1,29 +1,56 @@
 use std::cmp::Ordering;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use serde::Serialize;
 
 use config::{Config, TaxonomyConfig};
 use errors::{Context as ErrorContext, Result};
-use libs::ahash::AHashMap;
-use libs::tera::{Context, Tera};
+use libs::ahash::{AHashMap, AHashSet};
+use libs::tera::{Context, Map, Tera, Value};
+use markdown::{render_content, RenderContext};
+use utils::fs::read_file;
+use utils::site::WikilinkIndex;
 use utils::slugs::slugify_paths;
-use utils::templates::{check_template_fallbacks, render_template};
+use utils::templates::{check_template_fallbacks, render_template, ShortcodeDefinition};
+use utils::types::InsertAnchor;
 
+use crate::file_info::FileInfo;
+use crate::front_matter::{front_matter_lines, split_term_content, TermFrontMatter};
 use crate::library::Library;
 use crate::ser::SerializingPage;
 use crate::{Page, SortBy};
 
 use crate::sorting::sort_pages;
 
+/// Whether the directory named after a taxonomy contains the files describing its terms.
+/// It is a regular section instead if it has an `_index.md` file, in any language.
+pub fn is_terms_directory(dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    !entries.filter_map(|e| e.ok()).any(|e| {
+        let name = e.file_name();
+        let name = name.to_string_lossy();
+        name.starts_with("_index.") && name.ends_with(".md")
+    })
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct SerializedTaxonomyTerm<'a> {
     name: &'a str,
     slug: &'a str,
     path: &'a str,
     permalink: &'a str,
+    title: Option<&'a str>,
+    description: Option<&'a str>,
+    extra: &'a Map<String, Value>,
+    content: &'a str,
     pages: Vec<SerializingPage<'a>>,
     page_count: usize,
+    parent: Option<&'a TaxonomyTermLink>,
+    children: &'a [TaxonomyTermLink],
+    depth: usize,
 }
 
 impl<'a> SerializedTaxonomyTerm<'a> {
@@ -41,8 +68,35 @@ impl<'a> SerializedTaxonomyTerm<'a> {
             slug: &item.slug,
             path: &item.path,
             permalink: &item.permalink,
+            title: item.meta.title.as_deref(),
+            description: item.meta.description.as_deref(),
+            extra: &item.meta.extra,
+            content: &item.content,
             pages,
             page_count: item.pages.len(),
+            parent: item.parent.as_ref(),
+            children: &item.children,
+            depth: item.depth,
+        }
+    }
+}
+
+/// A reference to another term of the same hierarchical taxonomy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaxonomyTermLink {
+    pub name: String,
+    pub slug: String,
+    pub path: String,
+    pub permalink: String,
+}
+
+impl TaxonomyTermLink {
+    fn from_term(term: &TaxonomyTerm) -> Self {
+        TaxonomyTermLink {
+            name: term.name.clone(),
+            slug: term.slug.clone(),
+            path: term.path.clone(),
+            permalink: term.permalink.clone(),
         }
     }
 }
@@ -55,6 +109,22 @@ pub struct TaxonomyTerm {
     pub path: String,
     pub permalink: String,
     pub pages: Vec<PathBuf>,
+    /// Only set in hierarchical taxonomies
+    pub parent: Option<TaxonomyTermLink>,
+    /// Only set in hierarchical taxonomies, sorted like the taxonomy items
+    pub children: Vec<TaxonomyTermLink>,
+    /// How deeply nested that term is in a hierarchical taxonomy, 0 for top-level terms
+    pub depth: usize,
+    /// The file describing that term, if there is one
+    pub file: Option<FileInfo>,
+    /// The front matter of the file describing that term
+    pub meta: TermFrontMatter,
+    /// The content of the file describing that term, in markdown
+    pub raw_content: String,
+    /// How many lines of the file are before `raw_content`
+    pub front_matter_lines: usize,
+    /// The HTML rendered content of the file describing that term
+    pub content: String,
 }
 
 impl TaxonomyTerm {
@@ -66,6 +136,36 @@ impl TaxonomyTerm {
         config: &Config,
     ) -> Self {
         let item_slug = slugify_paths(name, config.slugify.taxonomies);
+        Self::with_slug(name, item_slug, lang, taxo_slug, taxo_pages, config)
+    }
+
+    /// A term of a hierarchical taxonomy: each `/`-separated part of the name is slugified
+    /// on its own so the term is nested under its parents
+    pub fn new_nested(
+        name: &str,
+        lang: &str,
+        taxo_slug: &str,
+        taxo_pages: &[&Page],
+        config: &Config,
+    ) -> Self {
+        let item_slug = name
+            .split('/')
+            .map(|part| slugify_paths(part, config.slugify.taxonomies))
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut term = Self::with_slug(name, item_slug, lang, taxo_slug, taxo_pages, config);
+        term.depth = name.matches('/').count();
+        term
+    }
+
+    fn with_slug(
+        name: &str,
+        item_slug: String,
+        lang: &str,
+        taxo_slug: &str,
+        taxo_pages: &[&Page],
+        config: &Config,
+    ) -> Self {
         let path = if lang != config.default_language {
             format!("/{}/{}/{}/", lang, taxo_slug, item_slug)
         } else {
@@ -79,7 +179,59 @@ impl TaxonomyTerm {
         let (mut pages, ignored_pages) = sort_pages(taxo_pages, SortBy::Date);
         // We still append pages without dates at the end
         pages.extend(ignored_pages);
-        TaxonomyTerm { name: name.to_string(), permalink, path, slug: item_slug, pages }
+        TaxonomyTerm {
+            name: name.to_string(),
+            permalink,
+            path,
+            slug: item_slug,
+            pages,
+            parent: None,
+            children: Vec::new(),
+            depth: 0,
+            file: None,
+            meta: TermFrontMatter::default(),
+            raw_content: String::new(),
+            front_matter_lines: 0,
+            content: String::new(),
+        }
+    }
+
+    /// Reads the front matter and content of the given file describing that term
+    pub fn load_metadata(&mut self, path: &Path, base_path: &Path) -> Result<()> {
+        let content = read_file(path)?;
+        let (meta, raw_content) = split_term_content(path, &content)?;
+        self.file = Some(FileInfo::new_page(path, base_path));
+        self.meta = meta;
+        self.raw_content = raw_content.to_string();
+        self.front_matter_lines = front_matter_lines(&content, raw_content);
+        Ok(())
+    }
+
+    /// Renders the content of the file describing that term, if there is one
+    pub fn render_markdown(
+        &mut self,
+        lang: &str,
+        permalinks: &HashMap<String, String>,
+        wikilinks: &WikilinkIndex,
+        tera: &Tera,
+        config: &Config,
+        shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
+    ) -> Result<()> {
+        let file = match self.file {
+            Some(ref f) => f,
+            None => return Ok(()),
+        };
+        let mut context =
+            RenderContext::new(tera, config, lang, &self.permalink, permalinks, InsertAnchor::None);
+        context.set_shortcode_definitions(shortcode_definitions);
+        context.set_wikilinks(wikilinks);
+        context.set_current_page_path(&file.relative);
+        context.set_line_offset(self.front_matter_lines);
+
+        let res = render_content(&self.raw_content, &context)
+            .with_context(|| format!("Failed to render content of {}", file.path.display()))?;
+        self.content = res.body;
+        Ok(())
     }
 
     pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializedTaxonomyTerm<'a> {
@@ -94,7 +246,12 @@ impl TaxonomyTerm {
     }
 
     pub fn merge(&mut self, other: Self) {
-        self.pages.extend(other.pages);
+        for page in other.pages {
+            // Pages can be in both terms in hierarchical taxonomies
+            if !self.pages.contains(&page) {
+                self.pages.push(page);
+            }
+        }
     }
 }
 
@@ -145,8 +302,20 @@ impl Taxonomy {
     pub(crate) fn new(tax_found: TaxonomyFound, config: &Config) -> Self {
         let mut sorted_items = vec![];
         let slug = tax_found.slug;
-        for (name, pages) in tax_found.terms {
-            sorted_items.push(TaxonomyTerm::new(name, tax_found.lang, &slug, &pages, config));
+        if tax_found.config.hierarchical {
+            for (name, pages) in nest_terms(tax_found.terms) {
+                sorted_items.push(TaxonomyTerm::new_nested(
+                    &name,
+                    tax_found.lang,
+                    &slug,
+                    &pages,
+                    config,
+                ));
+            }
+        } else {
+            for (name, pages) in tax_found.terms {
+                sorted_items.push(TaxonomyTerm::new(name, tax_found.lang, &slug, &pages, config));
+            }
         }
 
         sorted_items.sort_by(|a, b| match a.slug.cmp(&b.slug) {
@@ -165,6 +334,9 @@ impl Taxonomy {
                 false
             }
         });
+        if tax_found.config.hierarchical {
+            link_nested_terms(&mut sorted_items);
+        }
         let path = if tax_found.lang != config.default_language {
             format!("/{}/{}/", tax_found.lang, slug)
         } else {
@@ -182,6 +354,54 @@ impl Taxonomy {
         }
     }
 
+    /// Looks for the optional files describing the terms, eg `content/tags/rust.md` or
+    /// `content/tags/rust.fr.md` for another language.
+    /// There are none if `content/tags` is a section.
+    /// Returns the paths of the files found.
+    pub fn load_terms_metadata(
+        &mut self,
+        base_path: &Path,
+        config: &Config,
+    ) -> Result<Vec<PathBuf>> {
+        let extension = if self.lang != config.default_language {
+            format!("{}.md", self.lang)
+        } else {
+            "md".to_string()
+        };
+        let dir = base_path.join("content").join(&self.slug);
+        if !is_terms_directory(&dir) {
+            return Ok(Vec::new());
+        }
+
+        let mut found = Vec::new();
+        for item in self.items.iter_mut() {
+            let path = dir.join(format!("{}.{}", item.slug, extension));
+            if path.is_file() {
+                item.load_metadata(&path, base_path)?;
+                found.push(path);
+            }
+        }
+        Ok(found)
+    }
+
+    /// The template to render a term with: the one set in its file, the taxonomy-specific one
+    /// or the generic one
+    pub fn term_template(
+        &self,
+        item: &TaxonomyTerm,
+        tera: &Tera,
+        theme: &Option<String>,
+    ) -> String {
+        if let Some(ref template) = item.meta.template {
+            return template.clone();
+        }
+        // Check for taxon-specific template, or use generic as fallback.
+        let specific_template = format!("{}/single.html", self.kind.name);
+        check_template_fallbacks(&specific_template, tera, theme)
+            .unwrap_or("taxonomy_single.html")
+            .to_string()
+    }
+
     pub fn render_term(
         &self,
         item: &TaxonomyTerm,
@@ -190,13 +410,9 @@ impl Taxonomy {
         library: &Library,
     ) -> Result<String> {
         let context = self.build_term_context(item, config, library);
+        let template = self.term_template(item, tera, &config.theme);
 
-        // Check for taxon-specific template, or use generic as fallback.
-        let specific_template = format!("{}/single.html", self.kind.name);
-        let template = check_template_fallbacks(&specific_template, tera, &config.theme)
-            .unwrap_or("taxonomy_single.html");
-
-        render_template(template, tera, context, &config.theme)
+        render_template(&template, tera, context, &config.theme)
             .with_context(|| format!("Failed to render single term {} page.", self.kind.name))
     }
 
@@ -257,6 +473,50 @@ impl Taxonomy {
     }
 }
 
+/// Adds the pages of each term of a hierarchical taxonomy to all its ancestors, creating
+/// the ancestors without pages of their own along the way
+fn nest_terms<'a>(terms: AHashMap<&str, Vec<&'a Page>>) -> AHashMap<String, Vec<&'a Page>> {
+    let mut nested: AHashMap<String, Vec<&'a Page>> = AHashMap::new();
+    let mut seen: AHashSet<(String, &Path)> = AHashSet::new();
+
+    for (name, pages) in terms {
+        let parts: Vec<_> = name.split('/').map(str::trim).filter(|p| !p.is_empty()).collect();
+        for i in 1..=parts.len() {
+            let ancestor = parts[..i].join("/");
+            let ancestor_pages = nested.entry(ancestor.clone()).or_default();
+            for page in &pages {
+                if seen.insert((ancestor.clone(), page.file.path.as_path())) {
+                    ancestor_pages.push(page);
+                }
+            }
+        }
+    }
+
+    nested
+}
+
+/// Sets the parent and children of each term of a hierarchical taxonomy, based on their paths
+fn link_nested_terms(items: &mut [TaxonomyTerm]) {
+    let parent_path = |path: &str| -> Option<String> {
+        let trimmed = path.trim_end_matches('/');
+        trimmed.rfind('/').map(|i| trimmed[..=i].to_string())
+    };
+    let links: AHashMap<String, TaxonomyTermLink> =
+        items.iter().map(|i| (i.path.clone(), TaxonomyTermLink::from_term(i))).collect();
+
+    let mut children: AHashMap<String, Vec<TaxonomyTermLink>> = AHashMap::new();
+    for item in items.iter() {
+        if let Some(parent) = parent_path(&item.path).filter(|p| links.contains_key(p)) {
+            children.entry(parent).or_default().push(TaxonomyTermLink::from_term(item));
+        }
+    }
+
+    for item in items.iter_mut() {
+        item.parent = parent_path(&item.path).and_then(|p| links.get(&p).cloned());
+        item.children = children.remove(&item.path).unwrap_or_default();
+    }
+}
+
 /// Only used while building the taxonomies
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct TaxonomyFound<'a> {
@@ -0,0 +1,222 @@
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read};
+use std::path::Path;
+
+use errors::{anyhow, Result};
+use libs::image::buffer::ConvertBuffer;
+use libs::image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use libs::image::error::{DecodingError, ImageError};
+use libs::image::io::Reader as ImageReader;
+use libs::image::{
+    self, AnimationDecoder, Delay, DynamicImage, Frame, ImageFormat, ImageResult, RgbImage,
+    RgbaImage,
+};
+use libs::{image_webp, webp};
+
+/// The `image` decoder of WebP doesn't tell when the animation ends, so we use the underlying one
+fn webp_decoder(path: &Path) -> ImageResult<image_webp::WebPDecoder<BufReader<File>>> {
+    image_webp::WebPDecoder::new(BufReader::new(File::open(path)?)).map_err(webp_error)
+}
+
+fn webp_error(e: image_webp::DecodingError) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormat::WebP.into(), e))
+}
+
+/// The frames of an animated image, composited to the full size of the image, with the
+/// time in milliseconds they stay on screen. A still image only has one.
+pub(crate) fn read_frames(path: &Path) -> ImageResult<Vec<Frame>> {
+    let format = ImageReader::open(path)?.with_guessed_format()?.format();
+    match format {
+        Some(ImageFormat::Gif) => {
+            let reader = BufReader::new(File::open(path)?);
+            GifDecoder::new(reader)?.into_frames().collect_frames()
+        }
+        Some(ImageFormat::WebP) => {
+            let mut decoder = webp_decoder(path)?;
+            if !decoder.is_animated() {
+                return Ok(vec![Frame::new(image::open(path)?.into_rgba8())]);
+            }
+            let (width, height) = decoder.dimensions();
+            let buffer_size = decoder
+                .output_buffer_size()
+                .ok_or_else(|| webp_error(image_webp::DecodingError::ImageTooLarge))?;
+            let mut frames = Vec::with_capacity(decoder.num_frames() as usize);
+            for _ in 0..decoder.num_frames() {
+                let mut buffer = vec![0; buffer_size];
+                let delay = decoder.read_frame(&mut buffer).map_err(webp_error)?;
+                let img = if decoder.has_alpha() {
+                    RgbaImage::from_raw(width, height, buffer)
+                } else {
+                    RgbImage::from_raw(width, height, buffer).map(|img| img.convert())
+                }
+                .expect("the buffer has the size of the frame");
+                frames.push(Frame::from_parts(img, 0, 0, Delay::from_numer_denom_ms(delay, 1)));
+            }
+            Ok(frames)
+        }
+        _ => Ok(vec![Frame::new(image::open(path)?.into_rgba8())]),
+    }
+}
+
+/// Skips `n` bytes of the reader
+fn skip(reader: &mut impl Read, n: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(n), &mut io::sink())? < n {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Skips the data sub-blocks of a GIF, each starting with its size, until the empty one
+fn skip_gif_sub_blocks(reader: &mut impl Read) -> io::Result<()> {
+    loop {
+        let mut size = [0];
+        reader.read_exact(&mut size)?;
+        if size[0] == 0 {
+            return Ok(());
+        }
+        skip(reader, size[0] as u64)?;
+    }
+}
+
+/// Counts the image descriptors of a GIF, skipping over the image data rather than decoding it
+fn count_gif_frames(mut reader: impl Read) -> io::Result<u32> {
+    // The header and the logical screen descriptor, which tells if there is a global color table
+    let mut header = [0; 13];
+    reader.read_exact(&mut header)?;
+    if header[10] & 0x80 != 0 {
+        skip(&mut reader, 3 << ((header[10] & 0x07) + 1))?;
+    }
+
+    let mut frames = 0;
+    loop {
+        let mut block = [0];
+        if reader.read(&mut block)? == 0 {
+            break;
+        }
+        match block[0] {
+            // An extension: its label and its sub-blocks
+            0x21 => {
+                skip(&mut reader, 1)?;
+                skip_gif_sub_blocks(&mut reader)?;
+            }
+            // An image: its descriptor, its local color table, the LZW code size and its data
+            0x2C => {
+                frames += 1;
+                let mut descriptor = [0; 9];
+                reader.read_exact(&mut descriptor)?;
+                if descriptor[8] & 0x80 != 0 {
+                    skip(&mut reader, 3 << ((descriptor[8] & 0x07) + 1))?;
+                }
+                skip(&mut reader, 1)?;
+                skip_gif_sub_blocks(&mut reader)?;
+            }
+            // The trailer
+            _ => break,
+        }
+    }
+    Ok(frames)
+}
+
+/// How many frames the image has, only animated GIF and WebP have more than one.
+/// Only the structure of the file is read, without decoding the frames. The images whose frames
+/// can't be counted are considered still, decoding them will tell what is wrong.
+pub(crate) fn count_frames(path: &Path, format: Option<ImageFormat>) -> u32 {
+    let frames = match format {
+        Some(ImageFormat::Gif) => {
+            File::open(path).and_then(|f| count_gif_frames(BufReader::new(f))).ok()
+        }
+        Some(ImageFormat::WebP) => webp_decoder(path).ok().map(|decoder| {
+            if decoder.is_animated() {
+                decoder.num_frames()
+            } else {
+                1
+            }
+        }),
+        _ => None,
+    };
+    frames.filter(|f| *f > 0).unwrap_or(1)
+}
+
+fn delay_ms(frame: &Frame) -> u32 {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    numer.checked_div(denom).unwrap_or(0)
+}
+
+/// Encodes the frames as an animated WebP, `None` meaning lossless like for still images
+pub(crate) fn encode_webp(frames: &[Frame], quality: Option<u8>) -> Result<Vec<u8>> {
+    let (width, height) = frames[0].buffer().dimensions();
+    let mut config =
+        webp::WebPConfig::new().map_err(|_| anyhow!("Unable to configure the webp encoder"))?;
+    match quality {
+        Some(q) => config.quality = q as f32,
+        None => config.lossless = 1,
+    }
+
+    let mut encoder = webp::AnimEncoder::new(width, height, &config);
+    let mut timestamp = 0;
+    for frame in frames {
+        let buffer = frame.buffer();
+        encoder.add_frame(webp::AnimFrame::from_rgba(buffer, width, height, timestamp as i32));
+        timestamp += delay_ms(frame);
+    }
+    let memory =
+        encoder.try_encode().map_err(|e| anyhow!("Unable to encode the animated webp: {:?}", e))?;
+    let mut encoded = memory.to_vec();
+    fix_last_duration(&mut encoded, timestamp);
+    Ok(encoded)
+}
+
+/// `webp` doesn't tell libwebp when the animation ends, which then gives the last frame the
+/// average duration of the others. We set it back to whatever is left of the total duration,
+/// since libwebp merges identical consecutive frames.
+fn fix_last_duration(encoded: &mut [u8], total_ms: u32) {
+    let read_u24 = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+
+    // The chunks start after the RIFF header, each one with its name and size. In ANMF chunks,
+    // the duration comes after the position and the size of the frame.
+    let mut durations = Vec::new();
+    let mut offset = 12;
+    while offset + 8 <= encoded.len() {
+        let size = &encoded[offset + 4..offset + 8];
+        let size = u32::from_le_bytes([size[0], size[1], size[2], size[3]]) as usize;
+        let duration_offset = offset + 8 + 12;
+        if &encoded[offset..offset + 4] == b"ANMF" && duration_offset + 3 <= encoded.len() {
+            durations.push((duration_offset, read_u24(&encoded[duration_offset..])));
+        }
+        // Chunks are padded to an even size
+        offset += 8 + size + (size & 1);
+    }
+
+    if let Some(((last_offset, _), others)) = durations.split_last() {
+        let others: u32 = others.iter().map(|(_, duration)| duration).sum();
+        let last = total_ms.saturating_sub(others).min(0xFF_FFFF);
+        encoded[*last_offset..*last_offset + 3].copy_from_slice(&last.to_le_bytes()[..3]);
+    }
+}
+
+/// Encodes the frames as a GIF looping forever
+pub(crate) fn encode_gif(frames: Vec<Frame>) -> Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+    {
+        // The default speed of the `gif` crate, the slowest one takes ages on large animations
+        let mut encoder = GifEncoder::new_with_speed(&mut buffer, 10);
+        encoder.set_repeat(Repeat::Infinite)?;
+        encoder.encode_frames(frames)?;
+    }
+    Ok(buffer.into_inner())
+}
+
+/// Applies the given transformation to every frame, keeping its delay
+pub(crate) fn map_frames(
+    frames: Vec<Frame>,
+    transform: impl Fn(DynamicImage) -> DynamicImage,
+) -> Vec<Frame> {
+    frames
+        .into_iter()
+        .map(|frame| {
+            let delay = frame.delay();
+            let img = transform(DynamicImage::ImageRgba8(frame.into_buffer()));
+            Frame::from_parts(img.into_rgba8(), 0, 0, delay)
+        })
+        .collect()
+}
@@ -0,0 +1,110 @@
+use errors::{anyhow, Result};
+use libs::image::DynamicImage;
+
+/// De-serialized & sanitized `filters` argument of `resize_image`, applied in order
+/// once the image is resized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// Clockwise
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Grayscale,
+    /// Gaussian blur, with its sigma in hundredths so it can be hashed
+    Blur(u32),
+    /// Unsharp mask, with its sigma in hundredths so it can be hashed
+    Sharpen(u32),
+    /// Added to every channel of every pixel, from -255 to 255
+    Brightness(i32),
+}
+
+impl Filter {
+    /// Parses a filter, written either as its name (`grayscale`) or as its name followed
+    /// by its value in parentheses (`blur(2.5)`)
+    pub fn from_arg(filter: &str) -> Result<Self> {
+        use Filter::*;
+
+        let (name, value) = match filter.trim().split_once('(') {
+            Some((name, rest)) => match rest.strip_suffix(')') {
+                Some(value) => (name.trim(), Some(value.trim())),
+                None => return Err(anyhow!("Invalid filter: {}", filter)),
+            },
+            None => (filter.trim(), None),
+        };
+        let number = |default: Option<f32>| -> Result<f32> {
+            match value {
+                Some(v) => v
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|v| v.is_finite())
+                    .ok_or_else(|| anyhow!("Invalid value for filter `{}`: {}", name, v)),
+                None => default.ok_or_else(|| anyhow!("Filter `{}` requires a value", name)),
+            }
+        };
+        let sigma = |default| -> Result<u32> {
+            let sigma = number(Some(default))?;
+            if sigma <= 0.0 {
+                return Err(anyhow!("The value of filter `{}` must be positive", name));
+            }
+            Ok((sigma * 100.0).round() as u32)
+        };
+        if value.is_some() && matches!(name, "grayscale" | "flip_horizontal" | "flip_vertical") {
+            return Err(anyhow!("Filter `{}` doesn't take a value", name));
+        }
+
+        Ok(match name {
+            "rotate" => match number(None)? as i32 {
+                90 | -270 => Rotate90,
+                180 | -180 => Rotate180,
+                270 | -90 => Rotate270,
+                _ => return Err(anyhow!("Images can only be rotated by 90, 180 or 270 degrees")),
+            },
+            "flip_horizontal" => FlipHorizontal,
+            "flip_vertical" => FlipVertical,
+            "grayscale" => Grayscale,
+            "blur" => Blur(sigma(1.0)?),
+            "sharpen" => Sharpen(sigma(1.0)?),
+            "brightness" => {
+                let brightness = number(None)?;
+                if !(-255.0..=255.0).contains(&brightness) {
+                    return Err(anyhow!(
+                        "The value of filter `brightness` must be in range -255-255"
+                    ));
+                }
+                Brightness(brightness.round() as i32)
+            }
+            _ => return Err(anyhow!("Invalid image filter: {}", name)),
+        })
+    }
+
+    /// Whether the filter turns the image by a quarter, swapping its width and height
+    pub fn swaps_dimensions(&self) -> bool {
+        matches!(self, Filter::Rotate90 | Filter::Rotate270)
+    }
+
+    pub fn apply(&self, img: DynamicImage) -> DynamicImage {
+        use Filter::*;
+
+        match *self {
+            Rotate90 => img.rotate90(),
+            Rotate180 => img.rotate180(),
+            Rotate270 => img.rotate270(),
+            FlipHorizontal => img.fliph(),
+            FlipVertical => img.flipv(),
+            Grayscale => {
+                // Keep the image in RGB(A), not every encoder takes grayscale images
+                let img = img.grayscale();
+                if img.color().has_alpha() {
+                    DynamicImage::ImageRgba8(img.into_rgba8())
+                } else {
+                    DynamicImage::ImageRgb8(img.into_rgb8())
+                }
+            }
+            Blur(sigma) => img.blur(sigma as f32 / 100.0),
+            Sharpen(sigma) => img.unsharpen(sigma as f32 / 100.0, 0),
+            Brightness(value) => img.brighten(value),
+        }
+    }
+}
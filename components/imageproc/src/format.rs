@@ -1,7 +1,10 @@
-use errors::{anyhow, Result};
+use errors::{anyhow, bail, Result};
 use std::hash::{Hash, Hasher};
 
-const DEFAULT_Q_JPG: u8 = 75;
+pub(crate) const DEFAULT_Q_JPG: u8 = 75;
+const DEFAULT_Q_AVIF: u8 = 80;
+/// Encoding AVIF is slow, the speed of the encoder goes from 1 (slowest, smallest files) to 10
+const DEFAULT_SPEED_AVIF: u8 = 6;
 
 /// Thumbnail image format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -12,31 +15,49 @@ pub enum Format {
     Png,
     /// WebP, The `u8` argument is WebP quality (in percent), None meaning lossless.
     WebP(Option<u8>),
+    /// AVIF, The arguments are the quality (in percent) and the speed of the encoder (1-10).
+    Avif(u8, u8),
+    /// GIF, only worth it for animations
+    Gif,
 }
 
 impl Format {
-    pub fn from_args(is_lossy: bool, format: &str, quality: Option<u8>) -> Result<Format> {
+    /// `prefer_modern` makes `auto` pick AVIF for lossy images and lossless WebP for the others
+    pub fn from_args(
+        is_lossy: bool,
+        format: &str,
+        quality: Option<u8>,
+        speed: Option<u8>,
+        prefer_modern: bool,
+    ) -> Result<Format> {
         use Format::*;
         if let Some(quality) = quality {
             assert!(quality > 0 && quality <= 100, "Quality must be within the range [1; 100]");
         }
+        if let Some(speed) = speed {
+            if !(1..=10).contains(&speed) {
+                bail!("Speed must be within the range [1; 10]");
+            }
+        }
         let jpg_quality = quality.unwrap_or(DEFAULT_Q_JPG);
+        let avif = Avif(quality.unwrap_or(DEFAULT_Q_AVIF), speed.unwrap_or(DEFAULT_SPEED_AVIF));
         match format {
-            "auto" => {
-                if is_lossy {
-                    Ok(Jpeg(jpg_quality))
-                } else {
-                    Ok(Png)
-                }
-            }
+            "auto" => match (is_lossy, prefer_modern) {
+                (true, true) => Ok(avif),
+                (true, false) => Ok(Jpeg(jpg_quality)),
+                (false, true) => Ok(WebP(None)),
+                (false, false) => Ok(Png),
+            },
             "jpeg" | "jpg" => Ok(Jpeg(jpg_quality)),
             "png" => Ok(Png),
             "webp" => Ok(WebP(quality)),
+            "avif" => Ok(avif),
+            "gif" => Ok(Gif),
             _ => Err(anyhow!("Invalid image format: {}", format)),
         }
     }
 
-    pub fn extension(&self) -> &str {
+    pub fn extension(&self) -> &'static str {
         // Kept in sync with RESIZED_FILENAME and op_filename
         use Format::*;
 
@@ -44,6 +65,25 @@ impl Format {
             Png => "png",
             Jpeg(_) => "jpg",
             WebP(_) => "webp",
+            Avif(..) => "avif",
+            Gif => "gif",
+        }
+    }
+
+    /// Whether the format can hold all the frames of an animated image
+    pub fn supports_animation(&self) -> bool {
+        matches!(self, Format::WebP(_) | Format::Gif)
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        use Format::*;
+
+        match *self {
+            Png => "image/png",
+            Jpeg(_) => "image/jpeg",
+            WebP(_) => "image/webp",
+            Avif(..) => "image/avif",
+            Gif => "image/gif",
         }
     }
 }
@@ -58,6 +98,9 @@ impl Hash for Format {
             Jpeg(q) => 1001 + q as u16,
             WebP(None) => 2000,
             WebP(Some(q)) => 2001 + q as u16,
+            // The speed is at most 10 so it can't overlap with the next quality
+            Avif(q, speed) => 3000 + (q as u16) * 16 + speed as u16,
+            Gif => 5000,
         };
 
         hasher.write_u16(q);
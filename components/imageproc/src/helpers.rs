@@ -4,19 +4,28 @@ use std::hash::{Hash, Hasher};
 use std::path::Path;
 
 use crate::format::Format;
-use crate::ResizeOperation;
+use crate::metadata::KeepMetadata;
+use crate::{Filter, ResizeOperation};
 use libs::image::DynamicImage;
 
-/// Apply image rotation based on EXIF data
-/// Returns `None` if no transformation is needed
-pub fn fix_orientation(img: &DynamicImage, path: &Path) -> Option<DynamicImage> {
+/// Reads the EXIF data of an image, if it has any
+pub(crate) fn read_exif(path: &Path) -> Option<exif::Exif> {
     let file = std::fs::File::open(path).ok()?;
     let mut buf_reader = std::io::BufReader::new(&file);
     let exif_reader = exif::Reader::new();
-    let exif = exif_reader.read_from_container(&mut buf_reader).ok()?;
-    let orientation =
-        exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?.value.get_uint(0)?;
-    match orientation {
+    exif_reader.read_from_container(&mut buf_reader).ok()
+}
+
+/// The EXIF orientation of an image, if it has one
+pub(crate) fn get_orientation(path: &Path) -> Option<u32> {
+    let exif = read_exif(path)?;
+    exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?.value.get_uint(0)
+}
+
+/// Apply image rotation based on EXIF data
+/// Returns `None` if no transformation is needed
+pub fn fix_orientation(img: &DynamicImage, path: &Path) -> Option<DynamicImage> {
+    match get_orientation(path)? {
         // Values are taken from the page 30 of
         // https://www.cipa.jp/std/documents/e/DC-008-2012_E.pdf
         // For more details check http://sylvana.net/jpegcrop/exif_orientation.html
@@ -39,12 +48,27 @@ pub fn get_processed_filename(
     input_path: &Path,
     input_src: &str,
     op: &ResizeOperation,
+    filters: &[Filter],
     format: &Format,
+    keep_metadata: &KeepMetadata,
+    animated: bool,
 ) -> String {
     let mut hasher = DefaultHasher::new();
     hasher.write(input_src.as_ref());
     op.hash(&mut hasher);
+    // Images without filters keep the same filename as before filters existed
+    if !filters.is_empty() {
+        filters.hash(&mut hasher);
+    }
     format.hash(&mut hasher);
+    // Same for images without metadata
+    if keep_metadata.any() {
+        keep_metadata.hash(&mut hasher);
+    }
+    // Animated outputs used to be the first frame only, they need a new filename
+    if animated {
+        animated.hash(&mut hasher);
+    }
     let hash = hasher.finish();
     let filename = input_path
         .file_stem()
@@ -1,10 +1,25 @@
+mod animation;
+mod filters;
 mod format;
 mod helpers;
+mod manifest;
 mod meta;
+mod metadata;
 mod ops;
+mod placeholder;
 mod processor;
+mod responsive;
+mod social_card;
+mod static_images;
 
+pub use filters::Filter;
 pub use helpers::fix_orientation;
+pub use manifest::{is_processor_file, MANIFEST_FILENAME};
 pub use meta::{read_image_metadata, ImageMeta, ImageMetaResponse};
-pub use ops::{ResizeInstructions, ResizeOperation};
+pub use metadata::{ExifData, GpsPosition, KeepMetadata};
+pub use ops::{FocalPoint, ResizeInstructions, ResizeOperation};
+pub use placeholder::Placeholder;
 pub use processor::{EnqueueResponse, Processor, RESIZED_SUBDIR};
+pub use responsive::{ResponsiveImage, ResponsiveOptions, ResponsiveSource};
+pub use social_card::{SocialCard, SocialCardResponse};
+pub use static_images::StaticImageOptimizer;
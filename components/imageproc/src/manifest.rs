@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use errors::{Context, Result};
+use libs::serde_json;
+use libs::sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use utils::fs as ufs;
+
+use crate::placeholder::PLACEHOLDERS_FILENAME;
+use crate::processor::{Processor, RESIZED_SUBDIR};
+
+/// The file, in the processed images directory, listing the images it contains
+pub static MANIFEST_FILENAME: &str = "manifest.json";
+/// Bump whenever the manifest format changes
+const MANIFEST_VERSION: u32 = 1;
+
+/// Where a processed image comes from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) struct ManifestEntry {
+    /// Relative to the root of the site if the image is in it. Social cards without a
+    /// background image don't have any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    /// The SHA256 of the content of the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    version: u32,
+    /// Keyed by the filename of the processed image, which includes the hash of the operation
+    pub images: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    /// The manifest of the previous build, if there is one and it can be read
+    pub fn read(path: &Path) -> Option<Self> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Manifest>(&content).ok())
+            .filter(|m| m.version == MANIFEST_VERSION)
+    }
+
+    pub fn new(images: BTreeMap<String, ManifestEntry>) -> Self {
+        Manifest { version: MANIFEST_VERSION, images }
+    }
+}
+
+pub(crate) fn hash_file(input_path: &Path) -> Result<String> {
+    let content = fs::read(input_path)
+        .with_context(|| format!("Failed to read image: {}", input_path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(content)))
+}
+
+impl Processor {
+    /// The SHA256 of the content of the given image, computed only once per image
+    pub(crate) fn source_hash(&mut self, input_path: &Path) -> Result<String> {
+        if !self.source_hashes.contains_key(input_path) {
+            let hash = hash_file(input_path)?;
+            self.source_hashes.insert(input_path.to_path_buf(), hash);
+        }
+        Ok(self.source_hashes[input_path].clone())
+    }
+
+    /// The manifest entry of an image processed from the given source
+    pub(crate) fn manifest_entry(&mut self, input_path: &Path) -> Result<ManifestEntry> {
+        let source_hash = self.source_hash(input_path)?;
+        let source = input_path.strip_prefix(&self.base_path).unwrap_or(input_path).to_path_buf();
+        Ok(ManifestEntry { source: Some(source), source_hash: Some(source_hash) })
+    }
+
+    /// The images that should be listed in the manifest at the end of this build: the ones
+    /// enqueued, and on partial builds the ones of the previous build that are still there
+    pub(crate) fn manifest_images(&self) -> BTreeMap<String, ManifestEntry> {
+        let mut images = BTreeMap::new();
+        if let (true, Some(previous_images)) = (self.partial_build, &self.previous_images) {
+            for (filename, entry) in previous_images {
+                if self.output_dir.join(filename).exists() {
+                    images.insert(filename.clone(), entry.clone());
+                }
+            }
+        }
+        for op in &self.img_ops {
+            images.insert(op.filename(), op.manifest_entry.clone());
+        }
+        for op in &self.card_ops {
+            images.insert(op.filename(), op.manifest_entry.clone());
+        }
+        images
+    }
+
+    /// Removes the processed images whose source changed or doesn't exist anymore, as well as
+    /// the files not listed in the manifest, without needing to build the site. The images
+    /// without a source are kept since we can't know whether they are still used.
+    /// Nothing is removed if there is no manifest since we can't know which images are valid.
+    /// Returns how many images were removed.
+    pub fn clean(&mut self) -> Result<usize> {
+        if !self.output_dir.exists() {
+            return Ok(0);
+        }
+        let previous_images = match self.previous_images.take() {
+            Some(images) => images,
+            None => return Ok(0),
+        };
+
+        let mut images = BTreeMap::new();
+        for (filename, entry) in previous_images {
+            let up_to_date = match (&entry.source, &entry.source_hash) {
+                (Some(source), Some(source_hash)) => {
+                    let source_path = self.base_path.join(source);
+                    source_path.exists() && &hash_file(&source_path)? == source_hash
+                }
+                _ => true,
+            };
+            if up_to_date {
+                images.insert(filename, entry);
+            }
+        }
+
+        let mut removed = 0;
+        for dir_entry in fs::read_dir(&self.output_dir)? {
+            let path = dir_entry?.path();
+            let filename = path.file_name().unwrap().to_string_lossy();
+            if !path.is_file() || filename == MANIFEST_FILENAME || filename == PLACEHOLDERS_FILENAME
+            {
+                continue;
+            }
+            if !images.contains_key(filename.as_ref()) {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+
+        let manifest_path = self.output_dir.join(MANIFEST_FILENAME);
+        let manifest = Manifest::new(images);
+        if manifest.images.is_empty() {
+            if manifest_path.exists() {
+                fs::remove_file(&manifest_path)?;
+            }
+        } else {
+            ufs::create_file(&manifest_path, &serde_json::to_string(&manifest)?)?;
+        }
+        self.previous_images = Some(manifest.images);
+        Ok(removed)
+    }
+}
+
+/// Whether the file, given its path relative to the `static` folder, is one of the files kept
+/// next to the processed images for the next builds, which are not published
+pub fn is_processor_file(relative_path: &Path) -> bool {
+    relative_path.parent() == Some(Path::new(RESIZED_SUBDIR))
+        && relative_path
+            .file_name()
+            .is_some_and(|f| f == MANIFEST_FILENAME || f == PLACEHOLDERS_FILENAME)
+}
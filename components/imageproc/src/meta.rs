@@ -6,21 +6,37 @@ use serde::Serialize;
 use std::ffi::OsStr;
 use std::path::Path;
 
+use crate::animation::count_frames;
+use crate::helpers::get_orientation;
+
 /// Size and format read cheaply with `image`'s `Reader`.
 #[derive(Debug)]
 pub struct ImageMeta {
-    /// (w, h)
+    /// (w, h), once the image is turned according to its EXIF orientation
     pub size: (u32, u32),
     pub format: Option<ImageFormat>,
+    /// How many frames the image has, more than one for animated GIF and WebP.
+    /// They are counted from the structure of the file, other formats are assumed to be still.
+    pub frames: u32,
 }
 
 impl ImageMeta {
     pub fn read(path: &Path) -> ImageResult<Self> {
         let reader = ImgReader::open(path).and_then(ImgReader::with_guessed_format)?;
         let format = reader.format();
-        let size = reader.into_dimensions()?;
+        let (width, height) = reader.into_dimensions()?;
+        // Orientations 5 to 8 turn the image by a quarter
+        let size = match get_orientation(path) {
+            Some(5..=8) => (height, width),
+            _ => (width, height),
+        };
+        let frames = count_frames(path, format);
+
+        Ok(Self { size, format, frames })
+    }
 
-        Ok(Self { size, format })
+    pub fn is_animated(&self) -> bool {
+        self.frames > 1
     }
 
     pub fn is_lossy(&self) -> bool {
@@ -38,11 +54,20 @@ pub struct ImageMetaResponse {
     pub height: u32,
     pub format: Option<&'static str>,
     pub mime: Option<&'static str>,
+    pub frames: u32,
+    pub animated: bool,
 }
 
 impl ImageMetaResponse {
     pub fn new_svg(width: u32, height: u32) -> Self {
-        Self { width, height, format: Some("svg"), mime: Some("text/svg+xml") }
+        Self {
+            width,
+            height,
+            format: Some("svg"),
+            mime: Some("text/svg+xml"),
+            frames: 1,
+            animated: false,
+        }
     }
 }
 
@@ -53,6 +78,8 @@ impl From<ImageMeta> for ImageMetaResponse {
             height: im.size.1,
             format: im.format.and_then(|f| f.extensions_str().first()).copied(),
             mime: im.format.map(|f| f.to_mime_type()),
+            frames: im.frames,
+            animated: im.is_animated(),
         }
     }
 }
@@ -0,0 +1,202 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use config::ImageProcessing;
+use exif::experimental::Writer;
+use exif::{Exif, Field, In, Tag, Value};
+use libs::img_parts::{Bytes, DynImage, ImageEXIF, ImageICC};
+use serde::Serialize;
+
+use crate::helpers::read_exif;
+use crate::processor::Processor;
+
+/// Which metadata of their source the processed images keep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct KeepMetadata {
+    pub exif: bool,
+    pub icc_profile: bool,
+    /// Also applies to `get_image_metadata`
+    pub gps: bool,
+}
+
+impl KeepMetadata {
+    pub fn new(config: &ImageProcessing) -> Self {
+        Self { exif: config.keep_exif, icc_profile: config.keep_icc_profile, gps: config.keep_gps }
+    }
+
+    /// Whether the processed images have any metadata at all
+    pub fn any(&self) -> bool {
+        self.exif || self.icc_profile
+    }
+}
+
+/// The EXIF fields of a photo returned by `get_image_metadata`
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct ExifData {
+    /// The brand of the camera
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub lens: Option<String>,
+    /// In seconds, as a fraction for the fast ones, eg `1/250`
+    pub exposure_time: Option<String>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    /// In millimeters
+    pub focal_length: Option<f64>,
+    /// As `YYYY-MM-DDTHH:MM:SS`, in the local time of the camera
+    pub date_taken: Option<String>,
+    /// Only set if `keep_gps` is enabled
+    pub gps: Option<GpsPosition>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct GpsPosition {
+    /// In degrees, negative in the southern hemisphere
+    pub latitude: f64,
+    /// In degrees, negative west of Greenwich
+    pub longitude: f64,
+    /// In meters
+    pub altitude: Option<f64>,
+}
+
+impl ExifData {
+    fn new(exif: &Exif, with_gps: bool) -> Self {
+        let field = |tag| exif.get_field(tag, In::PRIMARY).map(|f| &f.value);
+        let string = |tag| match field(tag)? {
+            Value::Ascii(values) => {
+                let value = String::from_utf8_lossy(values.first()?);
+                let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+                (!value.is_empty()).then(|| value.to_string())
+            }
+            _ => None,
+        };
+        let rationals = |tag| match field(tag)? {
+            Value::Rational(values) if !values.is_empty() => {
+                Some(values.iter().map(|r| r.to_f64()).collect::<Vec<_>>())
+            }
+            _ => None,
+        };
+        let rational = |tag| rationals(tag).map(|values| values[0]);
+
+        let exposure_time = match field(Tag::ExposureTime) {
+            Some(Value::Rational(values)) if !values.is_empty() && values[0].denom != 0 => {
+                let time = values[0];
+                if time.num == 1 || time.to_f64() >= 1.0 {
+                    Some(format!("{}", time))
+                } else {
+                    Some(format!("1/{}", (1.0 / time.to_f64()).round()))
+                }
+            }
+            _ => None,
+        };
+        let date_taken = match field(Tag::DateTimeOriginal).or_else(|| field(Tag::DateTime)) {
+            Some(Value::Ascii(values)) => {
+                values.first().and_then(|v| exif::DateTime::from_ascii(v).ok()).map(|d| {
+                    format!(
+                        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                        d.year, d.month, d.day, d.hour, d.minute, d.second
+                    )
+                })
+            }
+            _ => None,
+        };
+        let gps = if with_gps {
+            // Degrees, minutes and seconds with the hemisphere in another field
+            let coordinate = |tag, ref_tag, negative| {
+                let values = rationals(tag)?;
+                let value = values.iter().zip([1.0, 60.0, 3600.0]).map(|(v, d)| v / d).sum::<f64>();
+                Some(if string(ref_tag).as_deref() == Some(negative) { -value } else { value })
+            };
+            let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S");
+            let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W");
+            let below_sea_level = field(Tag::GPSAltitudeRef).and_then(|v| v.get_uint(0)) == Some(1);
+            let altitude = rational(Tag::GPSAltitude).map(|a| if below_sea_level { -a } else { a });
+            match (latitude, longitude) {
+                (Some(latitude), Some(longitude)) => {
+                    Some(GpsPosition { latitude, longitude, altitude })
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        ExifData {
+            make: string(Tag::Make),
+            model: string(Tag::Model),
+            lens: string(Tag::LensModel),
+            exposure_time,
+            f_number: rational(Tag::FNumber),
+            iso: field(Tag::PhotographicSensitivity).and_then(|v| v.get_uint(0)),
+            focal_length: rational(Tag::FocalLength),
+            date_taken,
+            gps,
+        }
+    }
+}
+
+/// The EXIF data of the source to write in a processed image. The thumbnail, the size and
+/// the orientation are left out since they don't match the processed image anymore.
+/// `None` if there is nothing left or if the data couldn't be written back.
+fn processed_exif(exif: &Exif, with_gps: bool) -> Option<Bytes> {
+    let fields: Vec<&Field> = exif
+        .fields()
+        .filter(|f| f.ifd_num == In::PRIMARY)
+        .filter(|f| {
+            !matches!(
+                f.tag,
+                Tag::Orientation
+                    | Tag::ImageWidth
+                    | Tag::ImageLength
+                    | Tag::PixelXDimension
+                    | Tag::PixelYDimension
+            )
+        })
+        .filter(|f| with_gps || f.tag.context() != exif::Context::Gps)
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+
+    let mut writer = Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+    let mut buffer = Cursor::new(Vec::new());
+    writer.write(&mut buffer, exif.little_endian()).ok()?;
+    Some(buffer.into_inner().into())
+}
+
+/// Copies the metadata of the source we want to keep into an encoded processed image.
+/// Only JPEG, PNG and WebP support it, other formats are returned as they are.
+pub(crate) fn copy_metadata(input_path: &Path, encoded: Vec<u8>, keep: KeepMetadata) -> Vec<u8> {
+    let encoded = Bytes::from(encoded);
+    let mut img = match DynImage::from_bytes(encoded.clone()) {
+        Ok(Some(img)) => img,
+        _ => return encoded.into(),
+    };
+
+    if keep.icc_profile {
+        let source = fs::read(input_path).ok().map(Bytes::from);
+        let source = source.and_then(|s| DynImage::from_bytes(s).ok().flatten());
+        img.set_icc_profile(source.and_then(|s| s.icc_profile()));
+    }
+    if keep.exif {
+        img.set_exif(read_exif(input_path).and_then(|exif| processed_exif(&exif, keep.gps)));
+    }
+
+    let mut output = Vec::with_capacity(encoded.len());
+    match img.encoder().write_to(&mut output) {
+        Ok(_) => output,
+        Err(_) => encoded.into(),
+    }
+}
+
+impl Processor {
+    /// The EXIF data of the given image, if it has any. The GPS position is only read
+    /// if `keep_gps` is enabled.
+    pub fn exif_data(&self, input_path: &Path) -> Option<ExifData> {
+        read_exif(input_path).map(|exif| ExifData::new(&exif, self.keep_metadata.gps))
+    }
+}
@@ -20,13 +20,75 @@ pub enum ResizeOperation {
     /// The part of the image that doesn't fit in the thumbnail due to differing
     /// aspect ratio will be cropped away, if any.
     Fill(u32, u32),
+    /// Same as `Fill` but the crop keeps the given point of the image in view
+    /// as much as possible rather than its center
+    FillAt(u32, u32, FocalPoint),
+    /// Crops the given rectangle (x, y, width, height) of the image, without resizing it
+    Crop(u32, u32, u32, u32),
+}
+
+/// A point of an image, in thousandths of its width and height from its top left corner.
+/// It is not stored as floats so that operations using it can be hashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FocalPoint {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl FocalPoint {
+    pub const CENTER: FocalPoint = FocalPoint { x: 500, y: 500 };
+
+    /// From the `focal_point` argument, fractions of the width and height
+    pub fn new(x: f64, y: f64) -> Result<Self> {
+        if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+            return Err(anyhow!("`focal_point` coordinates must be in range 0-1"));
+        }
+        Ok(FocalPoint { x: (x * 1000.0).round() as u16, y: (y * 1000.0).round() as u16 })
+    }
+
+    /// From the `gravity` argument, naming the side or corner to keep
+    pub fn from_gravity(gravity: &str) -> Result<Self> {
+        let (x, y) = match gravity {
+            "center" => (500, 500),
+            "north" => (500, 0),
+            "south" => (500, 1000),
+            "east" => (1000, 500),
+            "west" => (0, 500),
+            "north_east" => (1000, 0),
+            "north_west" => (0, 0),
+            "south_east" => (1000, 1000),
+            "south_west" => (0, 1000),
+            _ => return Err(anyhow!("Invalid gravity: {}", gravity)),
+        };
+        Ok(FocalPoint { x, y })
+    }
+
+    /// The offset of a `crop_len` long crop of a `len` long side, such that the crop
+    /// is centered on `pos` (in thousandths of `len`) without going past the image
+    fn offset(pos: u16, len: u32, crop_len: u32) -> u32 {
+        let center = len as u64 * pos as u64 / 1000;
+        let offset = center.saturating_sub(crop_len as u64 / 2) as u32;
+        offset.min(len - crop_len)
+    }
 }
 
 impl ResizeOperation {
-    pub fn from_args(op: &str, width: Option<u32>, height: Option<u32>) -> Result<Self> {
+    pub fn from_args(
+        op: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        focal_point: Option<FocalPoint>,
+        crop: Option<(u32, u32, u32, u32)>,
+    ) -> Result<Self> {
         use ResizeOperation::*;
 
         // Validate args:
+        if focal_point.is_some() && op != "fill" {
+            return Err(anyhow!("`focal_point` and `gravity` can only be used with op=\"fill\""));
+        }
+        if crop.is_some() != (op == "crop") {
+            return Err(anyhow!("op=\"crop\" requires a `crop` argument, and only that op does"));
+        }
         match op {
             "fit_width" => {
                 if width.is_none() {
@@ -43,6 +105,12 @@ impl ResizeOperation {
                     return Err(anyhow!("op={} requires a `width` and `height` argument", op));
                 }
             }
+            "crop" => {
+                let (_, _, w, h) = crop.unwrap();
+                if w == 0 || h == 0 {
+                    return Err(anyhow!("op=\"crop\" requires a non-empty rectangle"));
+                }
+            }
             _ => return Err(anyhow!("Invalid image resize operation: {}", op)),
         };
 
@@ -51,7 +119,15 @@ impl ResizeOperation {
             "fit_width" => FitWidth(width.unwrap()),
             "fit_height" => FitHeight(height.unwrap()),
             "fit" => Fit(width.unwrap(), height.unwrap()),
-            "fill" => Fill(width.unwrap(), height.unwrap()),
+            "fill" => match focal_point {
+                // Keeps the same filename as before focal points existed
+                None | Some(FocalPoint::CENTER) => Fill(width.unwrap(), height.unwrap()),
+                Some(focal_point) => FillAt(width.unwrap(), height.unwrap(), focal_point),
+            },
+            "crop" => {
+                let (x, y, w, h) = crop.unwrap();
+                Crop(x, y, w, h)
+            }
             _ => unreachable!(),
         })
     }
@@ -96,7 +172,8 @@ impl ResizeInstructions {
                     Self::new(FitHeight(h), (orig_w, orig_h))
                 }
             }
-            Fill(w, h) => {
+            Fill(w, h) => Self::new(FillAt(w, h, FocalPoint::CENTER), (orig_w, orig_h)),
+            FillAt(w, h, focal_point) => {
                 const RATIO_EPSILLION: f32 = 0.1;
 
                 let factor_w = orig_w as f32 / w as f32;
@@ -117,15 +194,26 @@ impl ResizeInstructions {
                         ((factor_h * w as f32).round() as u32, orig_h)
                     };
 
-                    let (offset_w, offset_h) = if factor_w < factor_h {
-                        (0, (orig_h - crop_h) / 2)
+                    // Rounding can make the crop a pixel larger than the image
+                    let (crop_w, crop_h) = (crop_w.min(orig_w), crop_h.min(orig_h));
+                    let (offset_w, offset_h) = if focal_point == FocalPoint::CENTER {
+                        ((orig_w - crop_w) / 2, (orig_h - crop_h) / 2)
                     } else {
-                        ((orig_w - crop_w) / 2, 0)
+                        (
+                            FocalPoint::offset(focal_point.x, orig_w, crop_w),
+                            FocalPoint::offset(focal_point.y, orig_h, crop_h),
+                        )
                     };
 
                     res.crop((offset_w, offset_h, crop_w, crop_h)).resize((w, h))
                 }
             }
+            Crop(x, y, w, h) => {
+                // Only keep the part of the rectangle inside the image
+                let x = x.min(orig_w);
+                let y = y.min(orig_h);
+                res.crop((x, y, w.min(orig_w - x), h.min(orig_h - y)))
+            }
         }
     }
 
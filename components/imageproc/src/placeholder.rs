@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use errors::{anyhow, Context, Result};
+use libs::ahash::HashMap;
+use libs::base64::engine::{general_purpose::STANDARD, Engine};
+use libs::image::codecs::jpeg::JpegEncoder;
+use libs::image::imageops::FilterType;
+use libs::image::{self, DynamicImage, ImageFormat};
+use libs::{blurhash, serde_json};
+use serde::{Deserialize, Serialize};
+
+use crate::fix_orientation;
+use crate::processor::Processor;
+
+/// The file, in the processed images directory, where placeholders are kept between builds
+pub static PLACEHOLDERS_FILENAME: &str = "placeholders.json";
+
+/// Largest side of the image the blurhash and the average color are computed from
+const THUMBNAIL_SIZE: u32 = 32;
+/// Largest side of the inline preview
+const PREVIEW_SIZE: u32 = 16;
+const PREVIEW_JPEG_QUALITY: u8 = 60;
+
+/// What can be shown while the actual image is loading
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Placeholder {
+    /// See <https://blurha.sh>
+    pub blurhash: String,
+    /// A tiny version of the image as a `data:` URI
+    pub preview: String,
+    /// The average color of the image, as `#rrggbb`
+    pub color: String,
+}
+
+impl Placeholder {
+    pub fn compute(input_path: &Path) -> Result<Self> {
+        let img = image::open(input_path)?;
+        let img = fix_orientation(&img, input_path).unwrap_or(img);
+
+        let thumbnail = img.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle).to_rgba8();
+        let (width, height) = thumbnail.dimensions();
+        // Keep a bit more details along the longest side
+        let (x_components, y_components) = if width >= height { (4, 3) } else { (3, 4) };
+        let blurhash =
+            blurhash::encode(x_components, y_components, width, height, thumbnail.as_raw())
+                .map_err(|e| anyhow!("Failed to compute the blurhash: {}", e))?;
+
+        let mut sums = [0u64; 3];
+        for pixel in thumbnail.pixels() {
+            for (sum, channel) in sums.iter_mut().zip(pixel.0) {
+                *sum += channel as u64;
+            }
+        }
+        let count = (width as u64 * height as u64).max(1);
+        let color =
+            format!("#{:02x}{:02x}{:02x}", sums[0] / count, sums[1] / count, sums[2] / count);
+
+        let preview = img.resize(PREVIEW_SIZE, PREVIEW_SIZE, FilterType::Triangle);
+        let mut buffer = Cursor::new(Vec::new());
+        // JPEG is smaller but would lose the transparency
+        let mime = if preview.color().has_alpha() {
+            preview.write_to(&mut buffer, ImageFormat::Png)?;
+            "image/png"
+        } else {
+            let preview = DynamicImage::ImageRgb8(preview.into_rgb8());
+            let mut encoder = JpegEncoder::new_with_quality(&mut buffer, PREVIEW_JPEG_QUALITY);
+            encoder.encode_image(&preview)?;
+            "image/jpeg"
+        };
+        let preview = format!("data:{};base64,{}", mime, STANDARD.encode(buffer.into_inner()));
+
+        Ok(Placeholder { blurhash, preview, color })
+    }
+}
+
+/// Reads the placeholders computed in a previous build, keyed by the hash of their source
+pub(crate) fn read_placeholders(path: &Path) -> HashMap<String, Placeholder> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+impl Processor {
+    /// The placeholder of the given image. It is only computed if the image content
+    /// changed since the last time it was asked for.
+    pub fn placeholder(&mut self, input_path: &Path) -> Result<Placeholder> {
+        let hash = self.source_hash(input_path)?;
+
+        if let Some(p) = self.used_placeholders.get(&hash) {
+            return Ok(p.clone());
+        }
+        let placeholder = match self.placeholders.get(&hash) {
+            Some(p) => p.clone(),
+            None => Placeholder::compute(input_path).with_context(|| {
+                format!("Failed to compute the placeholder of {}", input_path.display())
+            })?,
+        };
+        self.used_placeholders.insert(hash, placeholder.clone());
+        Ok(placeholder)
+    }
+
+    /// The placeholders that should be saved at the end of this build: the ones asked for, and
+    /// on partial builds the ones of the previous build since they might still be used
+    pub(crate) fn saved_placeholders(&self) -> BTreeMap<String, Placeholder> {
+        let mut placeholders = BTreeMap::new();
+        if self.partial_build {
+            placeholders.extend(self.placeholders.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        placeholders.extend(self.used_placeholders.iter().map(|(k, v)| (k.clone(), v.clone())));
+        placeholders
+    }
+}
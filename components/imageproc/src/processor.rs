@@ -1,22 +1,28 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{Cursor, Write};
 use std::path::{Path, PathBuf};
 
 use config::Config;
 use errors::{anyhow, Context, Result};
 use libs::ahash::{HashMap, HashSet};
+use libs::image::codecs::avif::AvifEncoder;
 use libs::image::codecs::jpeg::JpegEncoder;
 use libs::image::imageops::FilterType;
-use libs::image::{EncodableLayout, ImageFormat};
+use libs::image::{DynamicImage, EncodableLayout, Frame, ImageFormat};
 use libs::rayon::prelude::*;
-use libs::{image, webp};
+use libs::{image, serde_json, webp};
 use serde::{Deserialize, Serialize};
 use utils::fs as ufs;
 
+use crate::animation::{encode_gif, encode_webp, map_frames, read_frames};
 use crate::format::Format;
 use crate::helpers::get_processed_filename;
-use crate::{fix_orientation, ImageMeta, ResizeInstructions, ResizeOperation};
+use crate::manifest::{Manifest, ManifestEntry, MANIFEST_FILENAME};
+use crate::metadata::{copy_metadata, KeepMetadata};
+use crate::placeholder::{read_placeholders, Placeholder, PLACEHOLDERS_FILENAME};
+use crate::social_card::CardOp;
+use crate::{fix_orientation, Filter, ImageMeta, ResizeInstructions, ResizeOperation};
 
 pub static RESIZED_SUBDIR: &str = "processed_images";
 
@@ -26,7 +32,12 @@ pub struct ImageOp {
     input_path: PathBuf,
     output_path: PathBuf,
     instr: ResizeInstructions,
+    filters: Vec<Filter>,
     format: Format,
+    keep_metadata: KeepMetadata,
+    /// Whether every frame of an animated source is processed, rather than only the first one
+    animated: bool,
+    pub(crate) manifest_entry: ManifestEntry,
     /// Whether we actually want to perform that op.
     /// In practice we set it to true if the output file already
     /// exists and is not stale. We do need to keep the ImageOp around for pruning though.
@@ -34,47 +45,96 @@ pub struct ImageOp {
 }
 
 impl ImageOp {
-    fn perform(&self) -> Result<()> {
-        if self.ignore {
-            return Ok(());
-        }
-
-        let img = image::open(&self.input_path)?;
-        let mut img = fix_orientation(&img, &self.input_path).unwrap_or(img);
+    pub(crate) fn filename(&self) -> String {
+        self.output_path.file_name().unwrap().to_string_lossy().into_owned()
+    }
 
+    /// Crops, resizes and filters one image, or one frame of an animation
+    fn transform(&self, mut img: DynamicImage) -> DynamicImage {
         let img = match self.instr.crop_instruction {
             Some((x, y, w, h)) => img.crop(x, y, w, h),
             None => img,
         };
-        let img = match self.instr.resize_instruction {
+        let mut img = match self.instr.resize_instruction {
             Some((w, h)) => img.resize_exact(w, h, FilterType::Lanczos3),
             None => img,
         };
+        for filter in &self.filters {
+            img = filter.apply(img);
+        }
+        img
+    }
 
-        let f = File::create(&self.output_path)?;
-        let mut buffered_f = BufWriter::new(f);
+    fn perform(&self) -> Result<()> {
+        if self.ignore {
+            return Ok(());
+        }
 
-        match self.format {
-            Format::Png => {
-                img.write_to(&mut buffered_f, ImageFormat::Png)?;
-            }
-            Format::Jpeg(q) => {
-                let mut encoder = JpegEncoder::new_with_quality(&mut buffered_f, q);
-                encoder.encode_image(&img)?;
-            }
-            Format::WebP(q) => {
-                let encoder = webp::Encoder::from_image(&img)
-                    .map_err(|_| anyhow!("Unable to load this kind of image with webp"))?;
-                let memory = match q {
-                    Some(q) => encoder.encode(q as f32),
-                    None => encoder.encode_lossless(),
-                };
-                buffered_f.write_all(memory.as_bytes())?;
-            }
+        let mut encoded =
+            if self.animated { self.encode_animation()? } else { self.encode_still()? };
+        if self.keep_metadata.any() {
+            encoded = copy_metadata(&self.input_path, encoded, self.keep_metadata);
         }
+        fs::write(&self.output_path, encoded)?;
 
         Ok(())
     }
+
+    fn encode_animation(&self) -> Result<Vec<u8>> {
+        let frames = map_frames(read_frames(&self.input_path)?, |img| self.transform(img));
+        match self.format {
+            Format::WebP(q) => encode_webp(&frames, q),
+            Format::Gif => encode_gif(frames),
+            _ => unreachable!("only formats supporting animations are enqueued as animated"),
+        }
+    }
+
+    fn encode_still(&self) -> Result<Vec<u8>> {
+        let img = image::open(&self.input_path)?;
+        let img = fix_orientation(&img, &self.input_path).unwrap_or(img);
+        let img = self.transform(img);
+
+        encode_image(img, self.format)
+    }
+}
+
+/// Encodes a still image in the given format
+pub(crate) fn encode_image(img: DynamicImage, format: Format) -> Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+
+    match format {
+        Format::Png => {
+            img.write_to(&mut buffer, ImageFormat::Png)?;
+        }
+        Format::Jpeg(q) => {
+            let mut encoder = JpegEncoder::new_with_quality(&mut buffer, q);
+            encoder.encode_image(&img)?;
+        }
+        Format::WebP(q) => {
+            let encoder = webp::Encoder::from_image(&img)
+                .map_err(|_| anyhow!("Unable to load this kind of image with webp"))?;
+            let memory = match q {
+                Some(q) => encoder.encode(q as f32),
+                None => encoder.encode_lossless(),
+            };
+            buffer.write_all(memory.as_bytes())?;
+        }
+        Format::Avif(q, speed) => {
+            let encoder = AvifEncoder::new_with_speed_quality(&mut buffer, speed, q);
+            // The encoder only takes 8 bits images
+            let img = if img.color().has_alpha() {
+                DynamicImage::ImageRgba8(img.into_rgba8())
+            } else {
+                DynamicImage::ImageRgb8(img.into_rgb8())
+            };
+            img.write_with_encoder(encoder)?;
+        }
+        Format::Gif => {
+            buffer.write_all(&encode_gif(vec![Frame::new(img.into_rgba8())])?)?;
+        }
+    }
+
+    Ok(buffer.into_inner())
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -99,9 +159,19 @@ impl EnqueueResponse {
         static_path: PathBuf,
         meta: &ImageMeta,
         instr: &ResizeInstructions,
+        filters: &[Filter],
     ) -> Self {
         let static_path = static_path.to_string_lossy().into_owned();
-        let (width, height) = instr.resize_instruction.unwrap_or(meta.size);
+        let (width, height) = match (instr.resize_instruction, instr.crop_instruction) {
+            (Some(size), _) => size,
+            (None, Some((_, _, w, h))) => (w, h),
+            (None, None) => meta.size,
+        };
+        let (width, height) = if filters.iter().filter(|f| f.swaps_dimensions()).count() % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        };
         let (orig_width, orig_height) = meta.size;
 
         Self { url, static_path, width, height, orig_width, orig_height }
@@ -113,23 +183,55 @@ impl EnqueueResponse {
 /// taking care of file stale status based on timestamps
 #[derive(Debug)]
 pub struct Processor {
-    base_url: String,
-    output_dir: PathBuf,
-    img_ops: HashSet<ImageOp>,
+    pub(crate) base_path: PathBuf,
+    pub(crate) base_url: String,
+    pub(crate) output_dir: PathBuf,
+    pub(crate) img_ops: HashSet<ImageOp>,
+    pub(crate) card_ops: HashSet<CardOp>,
     /// We want to make sure we only ever get metadata for an image once
     meta_cache: HashMap<PathBuf, ImageMeta>,
+    /// Whether the `auto` format picks AVIF/WebP rather than JPEG/PNG
+    prefer_modern_formats: bool,
+    /// Which metadata of the source images is kept
+    pub(crate) keep_metadata: KeepMetadata,
+    /// The SHA256 of the images we processed or computed a placeholder for
+    pub(crate) source_hashes: HashMap<PathBuf, String>,
+    /// The images listed in the manifest of the previous build, if there is one
+    pub(crate) previous_images: Option<BTreeMap<String, ManifestEntry>>,
+    /// Whether only parts of the site are rendered, in which case the images of the previous
+    /// build that were not enqueued are kept since they might still be used
+    pub(crate) partial_build: bool,
+    /// The placeholders saved by the previous build, by hash of their source
+    pub(crate) placeholders: HashMap<String, Placeholder>,
+    /// The placeholders asked for in this build, which are saved along with the previous ones
+    /// on partial builds
+    pub(crate) used_placeholders: BTreeMap<String, Placeholder>,
 }
 
 impl Processor {
     pub fn new(base_path: PathBuf, config: &Config) -> Processor {
+        let output_dir = base_path.join("static").join(RESIZED_SUBDIR);
         Processor {
-            output_dir: base_path.join("static").join(RESIZED_SUBDIR),
+            placeholders: read_placeholders(&output_dir.join(PLACEHOLDERS_FILENAME)),
+            previous_images: Manifest::read(&output_dir.join(MANIFEST_FILENAME)).map(|m| m.images),
+            output_dir,
+            base_path,
             base_url: config.make_permalink(RESIZED_SUBDIR),
             img_ops: HashSet::default(),
+            card_ops: HashSet::default(),
             meta_cache: HashMap::default(),
+            prefer_modern_formats: config.image_processing.prefer_modern_formats,
+            keep_metadata: KeepMetadata::new(&config.image_processing),
+            source_hashes: HashMap::default(),
+            used_placeholders: BTreeMap::new(),
+            partial_build: false,
         }
     }
 
+    pub fn set_partial_build(&mut self, partial_build: bool) {
+        self.partial_build = partial_build;
+    }
+
     pub fn set_base_url(&mut self, config: &Config) {
         self.base_url = config.make_permalink(RESIZED_SUBDIR);
     }
@@ -138,37 +240,111 @@ impl Processor {
         self.img_ops.len()
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn enqueue(
         &mut self,
         op: ResizeOperation,
+        filters: Vec<Filter>,
         input_src: String,
         input_path: PathBuf,
         format: &str,
         quality: Option<u8>,
+        speed: Option<u8>,
+        first_frame: bool,
     ) -> Result<EnqueueResponse> {
-        // First we load metadata from the cache if possible, otherwise from the file itself
-        if !self.meta_cache.contains_key(&input_path) {
-            let meta = ImageMeta::read(&input_path)
+        let format = self.resolve_format(&input_path, format, quality, speed, first_frame)?;
+        self.enqueue_with_format(op, filters, input_src, input_path, format, first_frame)
+    }
+
+    /// The size of the given image, read only once per image
+    pub(crate) fn image_size(&mut self, input_path: &Path) -> Result<(u32, u32)> {
+        if !self.meta_cache.contains_key(input_path) {
+            let meta = ImageMeta::read(input_path)
                 .with_context(|| format!("Failed to read image: {}", input_path.display()))?;
-            self.meta_cache.insert(input_path.clone(), meta);
+            self.meta_cache.insert(input_path.to_path_buf(), meta);
         }
+        Ok(self.meta_cache[input_path].size)
+    }
+
+    /// Gets the output format of an image from the `format` argument of `resize_image`.
+    /// `auto` keeps animations animated: GIFs stay GIFs unless modern formats are preferred,
+    /// the rest become animated WebPs.
+    pub(crate) fn resolve_format(
+        &mut self,
+        input_path: &Path,
+        format: &str,
+        quality: Option<u8>,
+        speed: Option<u8>,
+        first_frame: bool,
+    ) -> Result<Format> {
+        self.image_size(input_path)?;
+        let meta = &self.meta_cache[input_path];
+        let format = match format {
+            "auto" if meta.is_animated() && !first_frame => {
+                if meta.format == Some(ImageFormat::Gif) && !self.prefer_modern_formats {
+                    "gif"
+                } else {
+                    "webp"
+                }
+            }
+            _ => format,
+        };
+        Format::from_args(meta.is_lossy(), format, quality, speed, self.prefer_modern_formats)
+    }
+
+    pub(crate) fn enqueue_with_format(
+        &mut self,
+        op: ResizeOperation,
+        filters: Vec<Filter>,
+        input_src: String,
+        input_path: PathBuf,
+        format: Format,
+        first_frame: bool,
+    ) -> Result<EnqueueResponse> {
+        // First we load metadata from the cache if possible, otherwise from the file itself
+        self.image_size(&input_path)?;
         // We will have inserted it just above
         let meta = &self.meta_cache[&input_path];
-        // We get the output format
-        let format = Format::from_args(meta.is_lossy(), format, quality)?;
+        // Formats that can't hold an animation only get the first frame
+        let animated = meta.is_animated() && !first_frame && format.supports_animation();
         // Now we have all the data we need to generate the output filename and the response
-        let filename = get_processed_filename(&input_path, &input_src, &op, &format);
+        let filename = get_processed_filename(
+            &input_path,
+            &input_src,
+            &op,
+            &filters,
+            &format,
+            &self.keep_metadata,
+            animated,
+        );
         let url = format!("{}{}", self.base_url, filename);
         let static_path = Path::new("static").join(RESIZED_SUBDIR).join(&filename);
         let output_path = self.output_dir.join(&filename);
         let instr = ResizeInstructions::new(op, meta.size);
-        let enqueue_response = EnqueueResponse::new(url, static_path, meta, &instr);
+        if let Some((_, _, 0, _) | (_, _, _, 0)) = instr.crop_instruction {
+            return Err(anyhow!(
+                "The crop rectangle is outside of the image: {}",
+                input_path.display()
+            ));
+        }
+        let enqueue_response = EnqueueResponse::new(url, static_path, meta, &instr, &filters);
+        let manifest_entry = self.manifest_entry(&input_path)?;
+        // The hash of the source is reliable even if the files were copied around,
+        // eg restored from a CI cache, unlike their modification times
+        let up_to_date = match self.previous_images.as_ref().and_then(|i| i.get(&filename)) {
+            Some(previous) => previous.source_hash == manifest_entry.source_hash,
+            None => !ufs::file_stale(&input_path, &output_path),
+        };
         let img_op = ImageOp {
-            ignore: output_path.exists() && !ufs::file_stale(&input_path, &output_path),
+            ignore: output_path.exists() && up_to_date,
+            manifest_entry,
             input_path,
             output_path,
             instr,
+            filters,
             format,
+            keep_metadata: self.keep_metadata,
+            animated,
         };
         self.img_ops.insert(img_op);
 
@@ -177,9 +353,19 @@ impl Processor {
 
     /// Run the enqueued image operations
     pub fn do_process(&mut self) -> Result<()> {
-        if !self.img_ops.is_empty() {
+        let placeholders = self.saved_placeholders();
+        if !self.img_ops.is_empty() || !self.card_ops.is_empty() || !placeholders.is_empty() {
             ufs::create_directory(&self.output_dir)?;
         }
+        let images = self.manifest_images();
+        if !images.is_empty() {
+            let manifest = serde_json::to_string(&Manifest::new(images))?;
+            ufs::create_file(&self.output_dir.join(MANIFEST_FILENAME), &manifest)?;
+        }
+        if !placeholders.is_empty() {
+            let placeholders = serde_json::to_string(&placeholders)?;
+            ufs::create_file(&self.output_dir.join(PLACEHOLDERS_FILENAME), &placeholders)?;
+        }
 
         self.img_ops
             .par_iter()
@@ -188,6 +374,13 @@ impl Processor {
                     format!("Failed to process image: {}", op.input_path.display())
                 })
             })
+            .collect::<Result<()>>()?;
+        self.card_ops
+            .par_iter()
+            .map(|op| {
+                op.perform()
+                    .with_context(|| format!("Failed to render social card: {}", op.filename()))
+            })
             .collect::<Result<()>>()
     }
 
@@ -199,17 +392,20 @@ impl Processor {
         }
 
         ufs::create_directory(&self.output_dir)?;
-        let output_paths: HashSet<_> = self
-            .img_ops
-            .iter()
-            .map(|o| o.output_path.file_name().unwrap().to_string_lossy())
-            .collect();
+        let images = self.manifest_images();
+        let keep_placeholders = !self.saved_placeholders().is_empty();
 
         for entry in fs::read_dir(&self.output_dir)? {
             let entry_path = entry?.path();
             if entry_path.is_file() {
                 let filename = entry_path.file_name().unwrap().to_string_lossy();
-                if !output_paths.contains(&filename) {
+                if keep_placeholders && filename == PLACEHOLDERS_FILENAME {
+                    continue;
+                }
+                if !images.is_empty() && filename == MANIFEST_FILENAME {
+                    continue;
+                }
+                if !images.contains_key(filename.as_ref()) {
                     fs::remove_file(&entry_path)?;
                 }
             }
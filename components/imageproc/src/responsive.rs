@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+
+use errors::{bail, Result};
+use libs::tera::escape_html;
+use serde::Serialize;
+
+use crate::processor::{EnqueueResponse, Processor};
+use crate::ResizeOperation;
+
+/// De-serialized & sanitized arguments of `responsive_image`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponsiveOptions {
+    /// The widths of the variants, the ones larger than the image are skipped
+    pub widths: Vec<u32>,
+    /// The formats as accepted by `resize_image`, the last one being the fallback of the `<img>`
+    pub formats: Vec<String>,
+    /// The `sizes` attribute, `100vw` if not set
+    pub sizes: Option<String>,
+    pub quality: Option<u8>,
+    pub speed: Option<u8>,
+}
+
+/// All the variants of one format
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ResponsiveSource {
+    /// The extension of the format, eg `webp`
+    pub format: &'static str,
+    pub mime: &'static str,
+    /// The `srcset` attribute for those variants
+    pub srcset: String,
+    pub images: Vec<EnqueueResponse>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ResponsiveImage {
+    /// The URL of the largest variant of the fallback format
+    pub src: String,
+    /// The size of the largest variant
+    pub width: u32,
+    pub height: u32,
+    pub orig_width: u32,
+    pub orig_height: u32,
+    pub sizes: String,
+    /// One entry per format, in the order they were asked for
+    pub sources: Vec<ResponsiveSource>,
+}
+
+impl ResponsiveImage {
+    /// A `<picture>` element with a `<source>` per format but the fallback one, which is used
+    /// by the `<img>`
+    pub fn to_html(&self, alt: &str) -> String {
+        let sizes = escape_html(&self.sizes);
+        let mut html = String::from("<picture>");
+        let (fallback, sources) = self.sources.split_last().expect("at least one format");
+        for source in sources {
+            html.push_str(&format!(
+                r#"<source type="{}" srcset="{}" sizes="{}">"#,
+                source.mime,
+                escape_html(&source.srcset),
+                sizes
+            ));
+        }
+        html.push_str(&format!(
+            r#"<img src="{}" srcset="{}" sizes="{}" width="{}" height="{}" alt="{}" loading="lazy" decoding="async">"#,
+            escape_html(&self.src),
+            escape_html(&fallback.srcset),
+            sizes,
+            self.width,
+            self.height,
+            escape_html(alt)
+        ));
+        html.push_str("</picture>");
+        html
+    }
+}
+
+impl Processor {
+    /// Enqueues a `fit_width` resize of the image for every width and format
+    pub fn enqueue_responsive(
+        &mut self,
+        input_src: String,
+        input_path: PathBuf,
+        options: &ResponsiveOptions,
+    ) -> Result<ResponsiveImage> {
+        if options.formats.is_empty() {
+            bail!("At least one format is required");
+        }
+        if options.widths.contains(&0) {
+            bail!("Widths must be greater than 0");
+        }
+
+        let (orig_width, orig_height) = self.image_size(&input_path)?;
+        // We never upscale images, the original size is used if they are all too large
+        let mut widths: Vec<_> =
+            options.widths.iter().copied().filter(|w| *w <= orig_width).collect();
+        if widths.is_empty() {
+            widths.push(orig_width);
+        }
+        widths.sort_unstable();
+        widths.dedup();
+
+        let mut sources = Vec::with_capacity(options.formats.len());
+        for format in &options.formats {
+            let format =
+                self.resolve_format(&input_path, format, options.quality, options.speed, false)?;
+            let mut images = Vec::with_capacity(widths.len());
+            for width in &widths {
+                let op = ResizeOperation::FitWidth(*width);
+                images.push(self.enqueue_with_format(
+                    op,
+                    Vec::new(),
+                    input_src.clone(),
+                    input_path.clone(),
+                    format,
+                    false,
+                )?);
+            }
+            let srcset = images
+                .iter()
+                .map(|i| format!("{} {}w", i.url, i.width))
+                .collect::<Vec<_>>()
+                .join(", ");
+            sources.push(ResponsiveSource {
+                format: format.extension(),
+                mime: format.mime_type(),
+                srcset,
+                images,
+            });
+        }
+
+        let largest = sources.last().and_then(|s| s.images.last()).unwrap();
+        Ok(ResponsiveImage {
+            src: largest.url.clone(),
+            width: largest.width,
+            height: largest.height,
+            orig_width,
+            orig_height,
+            sizes: options.sizes.clone().unwrap_or_else(|| "100vw".to_string()),
+            sources,
+        })
+    }
+}
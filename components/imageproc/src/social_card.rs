@@ -0,0 +1,279 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use config::SocialCardTemplate;
+use errors::{anyhow, Context, Result};
+use libs::ab_glyph::{point, Font, FontArc, GlyphId, PxScale, ScaleFont};
+use libs::image::imageops::FilterType;
+use libs::image::{self, ImageFormat, Rgba, RgbaImage};
+use serde::Serialize;
+
+use crate::fix_orientation;
+use crate::manifest::ManifestEntry;
+use crate::processor::{Processor, RESIZED_SUBDIR};
+
+/// Used when the template doesn't set a font
+static DEFAULT_FONT: &[u8] = include_bytes!("../assets/DejaVuSans-Bold.ttf");
+/// The title is made smaller until it fits, but not smaller than that
+const MIN_TITLE_SIZE: f32 = 16.0;
+
+/// What to write on a social card, with the background image and the font of its template
+/// already found
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SocialCard {
+    pub title: String,
+    /// One line each, at the bottom of the card, eg the date and the name of the site
+    pub metadata: Vec<String>,
+    pub template: SocialCardTemplate,
+    pub background_path: Option<PathBuf>,
+    pub font_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SocialCardResponse {
+    /// The final URL for that asset
+    pub url: String,
+    /// The path to the static asset generated
+    pub static_path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A social card to render in the processed images directory
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CardOp {
+    card: SocialCard,
+    output_path: PathBuf,
+    pub(crate) manifest_entry: ManifestEntry,
+    /// Whether the card is already there: its filename is a hash of everything it's made of
+    ignore: bool,
+}
+
+/// Parses a `#rrggbb` or `#rrggbbaa` color
+fn parse_color(color: &str) -> Result<Rgba<u8>> {
+    let hex = color.strip_prefix('#').filter(|h| matches!(h.len(), 6 | 8));
+    let channels = hex
+        .and_then(|hex| {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| anyhow!("Invalid color `{}`, it should be like `#rrggbb`", color))?;
+    Ok(Rgba([channels[0], channels[1], channels[2], channels.get(3).copied().unwrap_or(255)]))
+}
+
+fn load_font(font_path: Option<&Path>) -> Result<FontArc> {
+    match font_path {
+        Some(path) => {
+            let content = fs::read(path)
+                .with_context(|| format!("Failed to read font: {}", path.display()))?;
+            FontArc::try_from_vec(content)
+                .map_err(|_| anyhow!("Invalid TrueType or OpenType font: {}", path.display()))
+        }
+        None => Ok(FontArc::try_from_slice(DEFAULT_FONT).expect("the bundled font is valid")),
+    }
+}
+
+/// Lays out and draws lines of text with one font and one size
+struct TextStyle<'a> {
+    font: &'a FontArc,
+    scale: PxScale,
+    color: Rgba<u8>,
+}
+
+impl<'a> TextStyle<'a> {
+    fn line_height(&self) -> f32 {
+        let scaled = self.font.as_scaled(self.scale);
+        scaled.height() + scaled.line_gap()
+    }
+
+    /// Calls `f` with every glyph of the line and its position, starting from 0
+    fn layout(&self, text: &str, mut f: impl FnMut(GlyphId, f32)) -> f32 {
+        let scaled = self.font.as_scaled(self.scale);
+        let mut x = 0.0;
+        let mut previous = None;
+        for c in text.chars().filter(|c| !c.is_control()) {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                x += scaled.kern(previous, id);
+            }
+            f(id, x);
+            x += scaled.h_advance(id);
+            previous = Some(id);
+        }
+        x
+    }
+
+    fn width(&self, text: &str) -> f32 {
+        self.layout(text, |_, _| {})
+    }
+
+    /// Splits the text in lines fitting in the given width, a word too long to fit being
+    /// on its own line
+    fn wrap(&self, text: &str, max_width: f32) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut current = String::new();
+        for word in text.split_whitespace() {
+            let candidate =
+                if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+            if current.is_empty() || self.width(&candidate) <= max_width {
+                current = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut current, word.to_string()));
+            }
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+        lines
+    }
+
+    /// Draws the line with the top of its line box at `y`, clipping what's outside the canvas
+    fn draw(&self, canvas: &mut RgbaImage, text: &str, x: f32, y: f32) {
+        let baseline = y + self.font.as_scaled(self.scale).ascent();
+        self.layout(text, |id, offset| {
+            let glyph = id.with_scale_and_position(self.scale, point(x + offset, baseline));
+            let outlined = match self.font.outline_glyph(glyph) {
+                Some(outlined) => outlined,
+                None => return,
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + gx as i64;
+                let py = bounds.min.y as i64 + gy as i64;
+                if px < 0 || py < 0 || px >= canvas.width() as i64 || py >= canvas.height() as i64 {
+                    return;
+                }
+                let alpha = coverage.clamp(0.0, 1.0) * self.color[3] as f32 / 255.0;
+                let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+                for i in 0..3 {
+                    pixel[i] = (self.color[i] as f32 * alpha + pixel[i] as f32 * (1.0 - alpha))
+                        .round() as u8;
+                }
+                pixel[3] = pixel[3].max((alpha * 255.0).round() as u8);
+            });
+        });
+    }
+}
+
+impl SocialCard {
+    fn render(&self) -> Result<RgbaImage> {
+        let t = &self.template;
+        let mut canvas = match &self.background_path {
+            Some(path) => {
+                let img = image::open(path)?;
+                let img = fix_orientation(&img, path).unwrap_or(img);
+                img.resize_to_fill(t.width, t.height, FilterType::Lanczos3).into_rgba8()
+            }
+            None => RgbaImage::from_pixel(t.width, t.height, parse_color(&t.background_color)?),
+        };
+
+        let font = load_font(self.font_path.as_deref())?;
+        let color = parse_color(&t.text_color)?;
+        let padding = t.padding as f32;
+        let max_width = t.width as f32 - 2.0 * padding;
+
+        // The metadata goes at the bottom, from the last line up
+        let metadata =
+            TextStyle { font: &font, scale: PxScale::from(t.metadata_size as f32), color };
+        let mut bottom = t.height as f32 - padding;
+        for line in self.metadata.iter().rev() {
+            bottom -= metadata.line_height();
+            metadata.draw(&mut canvas, line, padding, bottom);
+        }
+        if !self.metadata.is_empty() {
+            bottom -= metadata.line_height() / 2.0;
+        }
+
+        // And the title at the top, made smaller until it fits above the metadata
+        let available_height = bottom - padding;
+        let mut size = t.title_size as f32;
+        let (title, lines) = loop {
+            let title = TextStyle { font: &font, scale: PxScale::from(size), color };
+            let lines = title.wrap(&self.title, max_width);
+            let fits = lines.len() as f32 * title.line_height() <= available_height
+                && lines.iter().all(|l| title.width(l) <= max_width);
+            if fits || size <= MIN_TITLE_SIZE {
+                break (title, lines);
+            }
+            size = (size * 0.9).max(MIN_TITLE_SIZE);
+        };
+        let mut top = padding;
+        for line in &lines {
+            title.draw(&mut canvas, line, padding, top);
+            top += title.line_height();
+        }
+
+        Ok(canvas)
+    }
+}
+
+impl CardOp {
+    pub(crate) fn filename(&self) -> String {
+        self.output_path.file_name().unwrap().to_string_lossy().into_owned()
+    }
+
+    pub(crate) fn perform(&self) -> Result<()> {
+        if self.ignore {
+            return Ok(());
+        }
+
+        let img = self.card.render()?;
+        let mut buffer = Cursor::new(Vec::new());
+        img.write_to(&mut buffer, ImageFormat::Png)?;
+        fs::write(&self.output_path, buffer.into_inner())?;
+        Ok(())
+    }
+}
+
+impl Processor {
+    /// Enqueues the rendering of a social card as a PNG. The colors and the font are checked
+    /// right away so errors point at the template calling `social_card`.
+    pub fn enqueue_social_card(&mut self, card: SocialCard) -> Result<SocialCardResponse> {
+        let t = &card.template;
+        if t.width == 0 || t.height == 0 || t.title_size == 0 || t.metadata_size == 0 {
+            return Err(anyhow!("The size of the card and of its text must be greater than 0"));
+        }
+        if t.padding * 2 >= t.width || t.padding * 2 >= t.height {
+            return Err(anyhow!("The padding of the card is larger than the card"));
+        }
+        parse_color(&t.background_color)?;
+        parse_color(&t.text_color)?;
+        load_font(card.font_path.as_deref())?;
+
+        // The sources are hashed by content so changing them changes the filename
+        let mut hasher = DefaultHasher::new();
+        card.title.hash(&mut hasher);
+        card.metadata.hash(&mut hasher);
+        card.template.hash(&mut hasher);
+        let manifest_entry = match &card.background_path {
+            Some(path) => self.manifest_entry(path)?,
+            None => ManifestEntry { source: None, source_hash: None },
+        };
+        manifest_entry.source_hash.hash(&mut hasher);
+        if let Some(font_path) = &card.font_path {
+            self.source_hash(font_path)?.hash(&mut hasher);
+        }
+        let filename = format!("social_card.{:016x}.png", hasher.finish());
+
+        let response = SocialCardResponse {
+            url: format!("{}{}", self.base_url, filename),
+            static_path: Path::new("static")
+                .join(RESIZED_SUBDIR)
+                .join(&filename)
+                .to_string_lossy()
+                .into_owned(),
+            width: t.width,
+            height: t.height,
+        };
+        let output_path = self.output_dir.join(&filename);
+        let card_op = CardOp { ignore: output_path.exists(), card, output_path, manifest_entry };
+        self.card_ops.insert(card_op);
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use config::{Config, StaticImages};
+use errors::{Context, Result};
+use libs::image::imageops::FilterType;
+use libs::image::{self, DynamicImage, GenericImageView, ImageFormat};
+use libs::rayon::prelude::*;
+use utils::fs::{copy_file_if_needed, create_directory};
+
+use crate::format::{Format, DEFAULT_Q_JPG};
+use crate::manifest::hash_file;
+use crate::metadata::{copy_metadata, KeepMetadata};
+use crate::processor::{encode_image, RESIZED_SUBDIR};
+use crate::{fix_orientation, ImageMeta};
+
+/// Re-encodes the images copied from `static` and the page assets according to the
+/// `[static_images]` section of the config.
+/// Every output is kept in a cache directory, keyed by the hash of its source and of the
+/// options, so an image is only encoded again when it or the options change.
+#[derive(Debug)]
+pub struct StaticImageOptimizer {
+    options: StaticImages,
+    cache_dir: PathBuf,
+    keep_metadata: KeepMetadata,
+    hard_link: bool,
+}
+
+/// An output of one image: the original or one of its conversions
+struct Target {
+    format: Format,
+    path: PathBuf,
+    is_original: bool,
+}
+
+impl StaticImageOptimizer {
+    /// `None` if the `[static_images]` section is not in the config
+    pub fn new(config: &Config, cache_dir: PathBuf) -> Option<Self> {
+        let options = config.static_images.clone()?;
+        Some(Self {
+            options,
+            cache_dir,
+            keep_metadata: KeepMetadata::new(&config.image_processing),
+            hard_link: config.hard_link_static,
+        })
+    }
+
+    /// Whether the file at that path, relative to `static` or `content`, is optimized.
+    /// The images of `resize_image` are already processed so they never are.
+    pub fn is_included(&self, relative_path: &Path) -> bool {
+        !relative_path.starts_with(RESIZED_SUBDIR) && self.options.is_included(relative_path)
+    }
+
+    /// Writes the optimized version of each image at its destination, along with its
+    /// conversions to the other formats of the config.
+    /// Returns the paths of the conversions, the destinations being written in any case.
+    pub fn optimize(&self, images: &[(PathBuf, PathBuf)]) -> Result<Vec<PathBuf>> {
+        let written = images
+            .par_iter()
+            .map(|(src, dest)| {
+                self.optimize_image(src, dest)
+                    .with_context(|| format!("Failed to optimize static image {}", src.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(written.into_iter().flatten().collect())
+    }
+
+    fn optimize_image(&self, src: &Path, dest: &Path) -> Result<Vec<PathBuf>> {
+        let targets = match ImageMeta::read(src) {
+            // Animations and formats we can't encode are copied as they are
+            Ok(meta) if !meta.is_animated() => self.targets(&meta, src, dest),
+            _ => Vec::new(),
+        };
+        if targets.is_empty() {
+            copy_file_if_needed(src, dest, self.hard_link)?;
+            return Ok(Vec::new());
+        }
+
+        let source_hash = hash_file(src)?;
+        // Only decoded if one of the outputs is not in the cache
+        let mut decoded = None;
+        let mut written = Vec::new();
+        for target in targets {
+            let cache_path = self.cache_dir.join(format!(
+                "{}.{:016x}.{}",
+                source_hash,
+                self.options_hash(target.format),
+                target.format.extension()
+            ));
+            if !cache_path.exists() {
+                if decoded.is_none() {
+                    decoded = Some(self.decode(src)?);
+                }
+                let (img, resized) = decoded.as_ref().unwrap();
+                let encoded = encode_image(img.clone(), target.format)?;
+                let encoded = copy_metadata(src, encoded, self.keep_metadata);
+                // Re-encoding an image that's already well compressed can make it bigger
+                let original_len = fs::metadata(src)?.len() as usize;
+                let encoded = if target.is_original && !resized && encoded.len() >= original_len {
+                    fs::read(src)?
+                } else {
+                    encoded
+                };
+                create_directory(&self.cache_dir)?;
+                fs::write(&cache_path, encoded)?;
+            }
+
+            copy_file_if_needed(&cache_path, &target.path, false)?;
+            if !target.is_original {
+                written.push(target.path);
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// The original in its own format and the conversions that don't overwrite another file.
+    /// Empty if we can't encode the format of the original.
+    fn targets(&self, meta: &ImageMeta, src: &Path, dest: &Path) -> Vec<Target> {
+        let quality = self.options.quality;
+        // Lossless WebP is a lot bigger than the lossy formats it would replace
+        let webp_quality = quality.or(meta.is_lossy().then_some(DEFAULT_Q_JPG));
+        let original = match meta.format {
+            Some(ImageFormat::Jpeg) => Format::Jpeg(quality.unwrap_or(DEFAULT_Q_JPG)),
+            Some(ImageFormat::Png) => Format::Png,
+            Some(ImageFormat::WebP) => Format::WebP(webp_quality),
+            _ => return Vec::new(),
+        };
+
+        let mut targets =
+            vec![Target { format: original, path: dest.to_path_buf(), is_original: true }];
+        for name in &self.options.formats {
+            let format = match name.as_str() {
+                "webp" => Format::WebP(webp_quality),
+                _ => match Format::from_args(meta.is_lossy(), name, quality, None, false) {
+                    Ok(format) => format,
+                    Err(_) => continue,
+                },
+            };
+            let path = dest.with_extension(format.extension());
+            if path == dest || src.with_extension(format.extension()).exists() {
+                continue;
+            }
+            targets.push(Target { format, path, is_original: false });
+        }
+        targets
+    }
+
+    /// Decodes the image turned according to its EXIF orientation and scaled down to fit in
+    /// the maximum dimensions, telling whether it was scaled
+    fn decode(&self, src: &Path) -> Result<(DynamicImage, bool)> {
+        let img = image::open(src)?;
+        let img = fix_orientation(&img, src).unwrap_or(img);
+        let (width, height) = img.dimensions();
+        let max_width = self.options.max_width.unwrap_or(width);
+        let max_height = self.options.max_height.unwrap_or(height);
+        if width <= max_width && height <= max_height {
+            return Ok((img, false));
+        }
+        Ok((img.resize(max_width, max_height, FilterType::Lanczos3), true))
+    }
+
+    fn options_hash(&self, format: Format) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.options.max_width.hash(&mut hasher);
+        self.options.max_height.hash(&mut hasher);
+        self.keep_metadata.hash(&mut hasher);
+        format.hash(&mut hasher);
+        hasher.finish()
+    }
+}
@@ -1,9 +1,17 @@
 use std::env;
-use std::path::{PathBuf, MAIN_SEPARATOR as SLASH};
+use std::fs::File;
+use std::io::Cursor;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR as SLASH};
 
 use config::Config;
-use imageproc::{fix_orientation, ImageMetaResponse, Processor, ResizeOperation};
-use libs::image::{self, DynamicImage, GenericImageView, Pixel};
+use exif::{Field, In, Rational, Tag, Value};
+use imageproc::{
+    fix_orientation, EnqueueResponse, Filter, FocalPoint, ImageMetaResponse, Processor,
+    ResizeInstructions, ResizeOperation,
+};
+use libs::image::{self, DynamicImage, GenericImageView, ImageFormat, Pixel};
+use libs::img_parts::jpeg::Jpeg;
+use libs::img_parts::{ImageEXIF, ImageICC};
 use libs::once_cell::sync::Lazy;
 
 /// Assert that `address` matches `prefix` + RESIZED_FILENAME regex + "." + `extension`,
@@ -48,9 +56,11 @@ fn image_op_test(
     let tmpdir = tempfile::tempdir().unwrap().into_path();
     let config = Config::parse(CONFIG).unwrap();
     let mut proc = Processor::new(tmpdir.clone(), &config);
-    let resize_op = ResizeOperation::from_args(op, width, height).unwrap();
+    let resize_op = ResizeOperation::from_args(op, width, height, None, None).unwrap();
 
-    let resp = proc.enqueue(resize_op, source_img.into(), source_path, format, None).unwrap();
+    let resp = proc
+        .enqueue(resize_op, vec![], source_img.into(), source_path, format, None, None, false)
+        .unwrap();
     assert_processed_path_matches(&resp.url, "https://example.com/processed_images/", expect_ext);
     assert_processed_path_matches(&resp.static_path, PROCESSED_PREFIX.as_str(), expect_ext);
     assert_eq!(resp.width, expect_width);
@@ -112,6 +122,197 @@ fn resize_image_fill2() {
     image_op_test("jpg.jpg", "fill", Some(200), Some(100), "auto", "jpg", 200, 100, 300, 380);
 }
 
+#[test]
+fn fill_crops_around_the_focal_point() {
+    let crop = |focal_point| {
+        let op = ResizeOperation::from_args("fill", Some(100), Some(100), focal_point, None);
+        ResizeInstructions::new(op.unwrap(), (300, 380)).crop_instruction.unwrap()
+    };
+
+    assert_eq!(crop(None), (0, 40, 300, 300));
+    assert_eq!(crop(Some(FocalPoint::from_gravity("north").unwrap())), (0, 0, 300, 300));
+    assert_eq!(crop(Some(FocalPoint::from_gravity("south").unwrap())), (0, 80, 300, 300));
+    assert_eq!(crop(Some(FocalPoint::new(0.0, 0.5).unwrap())), (0, 40, 300, 300));
+    assert_eq!(crop(Some(FocalPoint::new(0.2, 0.45).unwrap())), (0, 21, 300, 300));
+}
+
+#[test]
+fn fill_focal_point_is_part_of_the_filename() {
+    let filename = |focal_point| {
+        let tmpdir = tempfile::tempdir().unwrap().into_path();
+        let config = Config::parse(CONFIG).unwrap();
+        let mut proc = Processor::new(tmpdir, &config);
+        let op = ResizeOperation::from_args("fill", Some(100), Some(100), focal_point, None);
+        let source_path = TEST_IMGS.join("jpg.jpg");
+        proc.enqueue(op.unwrap(), vec![], "jpg.jpg".into(), source_path, "jpg", None, None, false)
+            .unwrap()
+            .url
+    };
+
+    // A centered focal point is the same as not having one
+    assert_eq!(filename(None), filename(Some(FocalPoint::CENTER)));
+    assert_ne!(filename(None), filename(Some(FocalPoint::from_gravity("north").unwrap())));
+}
+
+#[test]
+fn invalid_focal_points() {
+    assert!(FocalPoint::new(1.5, 0.5).is_err());
+    assert!(FocalPoint::from_gravity("up").is_err());
+    let north = FocalPoint::from_gravity("north").ok();
+    assert!(ResizeOperation::from_args("fit", Some(100), Some(100), north, None).is_err());
+}
+
+#[test]
+fn resize_image_crop() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let op = ResizeOperation::from_args("crop", None, None, None, Some((10, 20, 100, 50))).unwrap();
+    let source_path = TEST_IMGS.join("jpg.jpg");
+
+    let resp =
+        proc.enqueue(op, vec![], "jpg.jpg".into(), source_path, "auto", None, None, false).unwrap();
+    assert_eq!((resp.width, resp.height), (100, 50));
+    proc.do_process().unwrap();
+    let processed_size = imageproc::read_image_metadata(tmpdir.join(&resp.static_path))
+        .map(|meta| (meta.width, meta.height))
+        .unwrap();
+    assert_eq!(processed_size, (100, 50));
+
+    // The rectangle is cut to the image
+    let op = ResizeOperation::from_args("crop", None, None, None, Some((250, 0, 100, 50))).unwrap();
+    let resp = proc.enqueue(
+        op,
+        vec![],
+        "jpg.jpg".into(),
+        TEST_IMGS.join("jpg.jpg"),
+        "auto",
+        None,
+        None,
+        false,
+    );
+    assert_eq!(resp.map(|r| (r.width, r.height)).unwrap(), (50, 50));
+
+    let op = ResizeOperation::from_args("crop", None, None, None, Some((300, 0, 100, 50))).unwrap();
+    let resp = proc.enqueue(
+        op,
+        vec![],
+        "jpg.jpg".into(),
+        TEST_IMGS.join("jpg.jpg"),
+        "auto",
+        None,
+        None,
+        false,
+    );
+    assert!(resp.is_err());
+    assert!(ResizeOperation::from_args("crop", None, None, None, None).is_err());
+    assert!(ResizeOperation::from_args("fill", Some(1), Some(1), None, Some((0, 0, 1, 1))).is_err());
+}
+
+fn filtered_image(source_img: &str, filters: &[&str]) -> (EnqueueResponse, DynamicImage) {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let op = ResizeOperation::from_args("fit_width", Some(150), None, None, None).unwrap();
+    let filters = filters.iter().map(|f| Filter::from_arg(f).unwrap()).collect();
+
+    let resp = proc
+        .enqueue(
+            op,
+            filters,
+            source_img.into(),
+            TEST_IMGS.join(source_img),
+            "png",
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+    proc.do_process().unwrap();
+    let img = image::open(tmpdir.join(&resp.static_path)).unwrap();
+    (resp, img)
+}
+
+#[test]
+fn resize_image_rotate_filter() {
+    let (resp, img) = filtered_image("jpg.jpg", &["rotate(90)"]);
+    assert_eq!((resp.width, resp.height), (190, 150));
+    assert_eq!(img.dimensions(), (190, 150));
+
+    // Two quarter turns keep the dimensions
+    let (resp, img) = filtered_image("jpg.jpg", &["rotate(90)", "rotate(-90)", "rotate(180)"]);
+    assert_eq!((resp.width, resp.height), (150, 190));
+    assert_eq!(img.dimensions(), (150, 190));
+}
+
+#[test]
+fn resize_image_flip_filters() {
+    let (_, orig) = filtered_image("exif_0.jpg", &[]);
+    let (_, flipped) = filtered_image("exif_0.jpg", &["flip_horizontal"]);
+    let (w, h) = orig.dimensions();
+    assert_eq!(orig.get_pixel(0, 0), flipped.get_pixel(w - 1, 0));
+
+    let (_, flipped) = filtered_image("exif_0.jpg", &["flip_vertical"]);
+    assert_eq!(orig.get_pixel(0, 0), flipped.get_pixel(0, h - 1));
+}
+
+#[test]
+fn resize_image_grayscale_filter() {
+    let (_, img) = filtered_image("jpg.jpg", &["grayscale"]);
+    for (_, _, pixel) in img.pixels() {
+        let [r, g, b, _] = pixel.0;
+        assert!(r == g && g == b);
+    }
+}
+
+#[test]
+fn resize_image_brightness_filter() {
+    let (_, orig) = filtered_image("jpg.jpg", &[]);
+    let (_, bright) = filtered_image("jpg.jpg", &["brightness(40)"]);
+    let (_, dark) = filtered_image("jpg.jpg", &["brightness(-40)"]);
+    let luma = |img: &DynamicImage| img.to_luma8().pixels().map(|p| p.0[0] as u64).sum::<u64>();
+    assert!(luma(&bright) > luma(&orig));
+    assert!(luma(&dark) < luma(&orig));
+}
+
+#[test]
+fn resize_image_blur_and_sharpen_filters() {
+    // The sum of the differences between neighbouring pixels goes down with blur
+    fn sharpness(img: &DynamicImage) -> u64 {
+        let img = img.to_luma8();
+        img.pixels().zip(img.pixels().skip(1)).map(|(a, b)| a.0[0].abs_diff(b.0[0]) as u64).sum()
+    }
+    let (_, orig) = filtered_image("jpg.jpg", &[]);
+    let (_, blurred) = filtered_image("jpg.jpg", &["blur(3)"]);
+    let (_, sharpened) = filtered_image("jpg.jpg", &["sharpen(2)"]);
+    assert!(sharpness(&blurred) < sharpness(&orig));
+    assert!(sharpness(&sharpened) > sharpness(&orig));
+}
+
+#[test]
+fn filters_are_part_of_the_filename() {
+    let url = |filters: &[&str]| filtered_image("jpg.jpg", filters).0.url;
+
+    let plain = url(&[]);
+    let grayscale = url(&["grayscale"]);
+    assert_ne!(plain, grayscale);
+    assert_ne!(url(&["blur(1)"]), url(&["blur(2)"]));
+    assert_ne!(url(&["grayscale", "rotate(90)"]), url(&["rotate(90)", "grayscale"]));
+    assert_eq!(url(&["blur"]), url(&["blur(1)"]));
+}
+
+#[test]
+fn invalid_filters() {
+    assert!(Filter::from_arg("sepia").is_err());
+    assert!(Filter::from_arg("rotate").is_err());
+    assert!(Filter::from_arg("rotate(45)").is_err());
+    assert!(Filter::from_arg("blur(-1)").is_err());
+    assert!(Filter::from_arg("blur(2").is_err());
+    assert!(Filter::from_arg("brightness(300)").is_err());
+    assert!(Filter::from_arg("grayscale(1)").is_err());
+    assert_eq!(Filter::from_arg(" blur( 2.5 ) ").unwrap(), Filter::Blur(250));
+}
+
 #[test]
 fn resize_image_png_png() {
     image_op_test("png.png", "scale", Some(150), Some(150), "auto", "png", 150, 150, 300, 380);
@@ -132,6 +333,85 @@ fn resize_image_webp_jpg() {
     image_op_test("webp.webp", "scale", Some(150), Some(150), "auto", "jpg", 150, 150, 300, 380);
 }
 
+#[test]
+fn resize_image_jpg_avif() {
+    let (resp, processed) = avif_op_test("jpg.jpg", "avif", None);
+    assert_processed_path_matches(&resp.url, "https://example.com/processed_images/", "avif");
+    assert_eq!((resp.width, resp.height), (150, 190));
+    // An AVIF file is an ISO-BMFF container whose `ftyp` box has the `avif` brand
+    assert_eq!(&processed[4..12], b"ftypavif");
+}
+
+#[test]
+fn resize_image_auto_prefers_modern_formats() {
+    let (resp, _) = avif_op_test("jpg.jpg", "auto", Some(true));
+    assert!(resp.url.ends_with(".avif"));
+    let (resp, _) = avif_op_test("png.png", "auto", Some(true));
+    assert!(resp.url.ends_with(".webp"));
+    let (resp, _) = avif_op_test("png.png", "auto", Some(false));
+    assert!(resp.url.ends_with(".png"));
+}
+
+#[test]
+fn avif_quality_and_speed_are_part_of_the_filename() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir, &config);
+    let op = ResizeOperation::from_args("fit_width", Some(150), None, None, None).unwrap();
+    let source_path = TEST_IMGS.join("jpg.jpg");
+    let mut urls = std::collections::HashSet::new();
+    for (quality, speed) in [(None, None), (Some(50), None), (None, Some(10)), (Some(50), Some(10))]
+    {
+        let resp = proc
+            .enqueue(
+                op,
+                vec![],
+                "jpg.jpg".into(),
+                source_path.clone(),
+                "avif",
+                quality,
+                speed,
+                false,
+            )
+            .unwrap();
+        urls.insert(resp.url);
+    }
+    assert_eq!(urls.len(), 4);
+    assert!(proc
+        .enqueue(op, vec![], "jpg.jpg".into(), source_path, "avif", None, Some(11), false)
+        .is_err());
+}
+
+/// Resizes to a width of 150 with the given format, returning the bytes of the processed file
+fn avif_op_test(
+    source_img: &str,
+    format: &str,
+    prefer_modern_formats: Option<bool>,
+) -> (EnqueueResponse, Vec<u8>) {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let mut config = Config::parse(CONFIG).unwrap();
+    if let Some(prefer_modern_formats) = prefer_modern_formats {
+        config.image_processing.prefer_modern_formats = prefer_modern_formats;
+    }
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let op = ResizeOperation::from_args("fit_width", Some(150), None, None, None).unwrap();
+    let resp = proc
+        .enqueue(
+            op,
+            vec![],
+            source_img.into(),
+            TEST_IMGS.join(source_img),
+            format,
+            None,
+            Some(10),
+            false,
+        )
+        .unwrap();
+    proc.do_process().unwrap();
+    let processed = std::fs::read(tmpdir.join(&resp.static_path)).unwrap();
+    (resp, processed)
+}
+
 #[test]
 fn read_image_metadata_jpg() {
     assert_eq!(
@@ -140,7 +420,9 @@ fn read_image_metadata_jpg() {
             width: 300,
             height: 380,
             format: Some("jpg"),
-            mime: Some("image/jpeg")
+            mime: Some("image/jpeg"),
+            frames: 1,
+            animated: false,
         }
     );
 }
@@ -149,7 +431,14 @@ fn read_image_metadata_jpg() {
 fn read_image_metadata_png() {
     assert_eq!(
         image_meta_test("png.png"),
-        ImageMetaResponse { width: 300, height: 380, format: Some("png"), mime: Some("image/png") }
+        ImageMetaResponse {
+            width: 300,
+            height: 380,
+            format: Some("png"),
+            mime: Some("image/png"),
+            frames: 1,
+            animated: false,
+        }
     );
 }
 
@@ -161,7 +450,9 @@ fn read_image_metadata_svg() {
             width: 300,
             height: 300,
             format: Some("svg"),
-            mime: Some("text/svg+xml")
+            mime: Some("text/svg+xml"),
+            frames: 1,
+            animated: false,
         }
     );
 }
@@ -174,11 +465,67 @@ fn read_image_metadata_webp() {
             width: 300,
             height: 380,
             format: Some("webp"),
-            mime: Some("image/webp")
+            mime: Some("image/webp"),
+            frames: 1,
+            animated: false,
         }
     );
 }
 
+#[test]
+fn placeholder_jpg() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir, &config);
+
+    let placeholder = proc.placeholder(&TEST_IMGS.join("jpg.jpg")).unwrap();
+    // 3x4 components for a portrait image
+    assert_eq!(placeholder.blurhash.len(), 28);
+    assert!(placeholder.preview.starts_with("data:image/jpeg;base64,"));
+    assert_eq!(placeholder.color.len(), 7);
+    assert!(placeholder.color.starts_with('#'));
+}
+
+#[test]
+fn placeholder_svg_is_an_error() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir, &config);
+
+    assert!(proc.placeholder(&TEST_IMGS.join("svg.svg")).is_err());
+}
+
+#[test]
+fn placeholders_are_cached_by_source_hash() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let source_path = tmpdir.join("jpg.jpg");
+    std::fs::copy(TEST_IMGS.join("jpg.jpg"), &source_path).unwrap();
+    let cache_path = tmpdir.join("static").join("processed_images").join("placeholders.json");
+
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let placeholder = proc.placeholder(&source_path).unwrap();
+    proc.prune().unwrap();
+    proc.do_process().unwrap();
+    let cached = std::fs::read_to_string(&cache_path).unwrap();
+    assert!(cached.contains(&placeholder.blurhash));
+
+    // The next build reads it from the cache rather than computing it again
+    std::fs::write(&cache_path, cached.replace(&placeholder.color, "#123456")).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    assert_eq!(proc.placeholder(&source_path).unwrap().color, "#123456");
+
+    // Unless the image changed
+    std::fs::copy(TEST_IMGS.join("exif_1.jpg"), &source_path).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    assert_ne!(proc.placeholder(&source_path).unwrap().color, "#123456");
+
+    // And it is removed once no placeholders are used anymore
+    let proc = Processor::new(tmpdir, &config);
+    proc.prune().unwrap();
+    assert!(!cache_path.exists());
+}
+
 #[test]
 fn fix_orientation_test() {
     fn load_img_and_fix_orientation(img_name: &str) -> DynamicImage {
@@ -227,9 +574,11 @@ fn resize_and_check(source_img: &str) -> bool {
     let tmpdir = tempfile::tempdir().unwrap().into_path();
     let config = Config::parse(CONFIG).unwrap();
     let mut proc = Processor::new(tmpdir.clone(), &config);
-    let resize_op = ResizeOperation::from_args("scale", Some(16), Some(16)).unwrap();
+    let resize_op = ResizeOperation::from_args("scale", Some(16), Some(16), None, None).unwrap();
 
-    let resp = proc.enqueue(resize_op, source_img.into(), source_path, "jpg", None).unwrap();
+    let resp = proc
+        .enqueue(resize_op, vec![], source_img.into(), source_path, "jpg", None, None, false)
+        .unwrap();
 
     proc.do_process().unwrap();
     let processed_path = PathBuf::from(&resp.static_path);
@@ -270,3 +619,492 @@ fn asymmetric_resize_with_exif_orientations() {
     // 8: Rotate 270 CW
     image_op_test("exif_8.jpg", "scale", Some(16), Some(32), "auto", "jpg", 16, 32, 16, 16);
 }
+
+fn field(tag: Tag, value: Value) -> Field {
+    Field { tag, ifd_num: In::PRIMARY, value }
+}
+
+/// A black 20x10 JPEG with the given EXIF fields and ICC profile
+fn jpeg_with_metadata(dir: &Path, fields: &[Field], icc_profile: Option<&[u8]>) -> PathBuf {
+    let mut buffer = Cursor::new(Vec::new());
+    DynamicImage::new_rgb8(20, 10).write_to(&mut buffer, ImageFormat::Jpeg).unwrap();
+    let mut jpeg = Jpeg::from_bytes(buffer.into_inner().into()).unwrap();
+
+    let mut writer = exif::experimental::Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+    let mut exif = Cursor::new(Vec::new());
+    writer.write(&mut exif, false).unwrap();
+    jpeg.set_exif(Some(exif.into_inner().into()));
+    jpeg.set_icc_profile(icc_profile.map(|p| p.to_vec().into()));
+
+    let path = dir.join("metadata.jpg");
+    jpeg.encoder().write_to(File::create(&path).unwrap()).unwrap();
+    path
+}
+
+fn photo_fields() -> Vec<Field> {
+    vec![
+        field(Tag::Orientation, Value::Short(vec![6])),
+        field(Tag::Make, Value::Ascii(vec![b"Zola".to_vec()])),
+        field(Tag::Model, Value::Ascii(vec![b"Z1".to_vec()])),
+        field(Tag::ExposureTime, Value::Rational(vec![Rational { num: 10, denom: 2500 }])),
+        field(Tag::FNumber, Value::Rational(vec![Rational { num: 28, denom: 10 }])),
+        field(Tag::PhotographicSensitivity, Value::Short(vec![200])),
+        field(Tag::DateTimeOriginal, Value::Ascii(vec![b"2023:05:01 12:30:00".to_vec()])),
+        field(Tag::GPSLatitudeRef, Value::Ascii(vec![b"S".to_vec()])),
+        field(
+            Tag::GPSLatitude,
+            Value::Rational(vec![
+                Rational { num: 33, denom: 1 },
+                Rational { num: 52, denom: 1 },
+                Rational { num: 12, denom: 1 },
+            ]),
+        ),
+        field(Tag::GPSLongitudeRef, Value::Ascii(vec![b"E".to_vec()])),
+        field(
+            Tag::GPSLongitude,
+            Value::Rational(vec![
+                Rational { num: 151, denom: 1 },
+                Rational { num: 12, denom: 1 },
+                Rational { num: 36, denom: 1 },
+            ]),
+        ),
+    ]
+}
+
+fn config_with(image_processing: &str) -> Config {
+    Config::parse(&format!("{}\n[image_processing]\n{}", CONFIG, image_processing)).unwrap()
+}
+
+/// Resizes the image to 5 pixels wide, returning the path of the result
+fn process_to_jpg(config: &Config, tmpdir: &Path, source_path: PathBuf) -> PathBuf {
+    let mut proc = Processor::new(tmpdir.to_path_buf(), config);
+    let op = ResizeOperation::from_args("fit_width", Some(5), None, None, None).unwrap();
+    let resp =
+        proc.enqueue(op, vec![], "metadata.jpg".into(), source_path, "jpg", None, None, false);
+    proc.do_process().unwrap();
+    tmpdir.join(resp.unwrap().static_path)
+}
+
+fn read_exif(path: &Path) -> Option<exif::Exif> {
+    let mut reader = std::io::BufReader::new(File::open(path).unwrap());
+    exif::Reader::new().read_from_container(&mut reader).ok()
+}
+
+#[test]
+fn exif_orientation_swaps_the_dimensions() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let fields = [field(Tag::Orientation, Value::Short(vec![6]))];
+    let source_path = jpeg_with_metadata(&tmpdir, &fields, None);
+
+    let meta = imageproc::read_image_metadata(&source_path).unwrap();
+    assert_eq!((meta.width, meta.height), (10, 20));
+
+    let config = Config::parse(CONFIG).unwrap();
+    let processed_path = process_to_jpg(&config, &tmpdir, source_path);
+    let meta = imageproc::read_image_metadata(processed_path).unwrap();
+    assert_eq!((meta.width, meta.height), (5, 10));
+}
+
+#[test]
+fn metadata_is_stripped_by_default() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let source_path = jpeg_with_metadata(&tmpdir, &photo_fields(), Some(b"icc profile"));
+    let config = Config::parse(CONFIG).unwrap();
+
+    let processed_path = process_to_jpg(&config, &tmpdir, source_path);
+    assert!(read_exif(&processed_path).is_none());
+    let processed = Jpeg::from_bytes(std::fs::read(processed_path).unwrap().into()).unwrap();
+    assert!(processed.icc_profile().is_none());
+}
+
+#[test]
+fn can_keep_exif_and_icc_profile() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let source_path = jpeg_with_metadata(&tmpdir, &photo_fields(), Some(b"icc profile"));
+    let config = config_with("keep_exif = true\nkeep_icc_profile = true");
+
+    let processed_path = process_to_jpg(&config, &tmpdir, source_path.clone());
+    let exif = read_exif(&processed_path).unwrap();
+    assert_eq!(
+        exif.get_field(Tag::Make, In::PRIMARY).unwrap().display_value().to_string(),
+        "\"Zola\""
+    );
+    // It is already applied
+    assert!(exif.get_field(Tag::Orientation, In::PRIMARY).is_none());
+    assert!(exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_none());
+    let processed = Jpeg::from_bytes(std::fs::read(&processed_path).unwrap().into()).unwrap();
+    assert_eq!(processed.icc_profile().unwrap().as_ref(), b"icc profile");
+
+    let config = config_with("keep_exif = true\nkeep_gps = true");
+    let processed_path = process_to_jpg(&config, &tmpdir, source_path);
+    let exif = read_exif(&processed_path).unwrap();
+    assert!(exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_some());
+}
+
+#[test]
+fn kept_metadata_is_part_of_the_filename() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let source_path = jpeg_with_metadata(&tmpdir, &photo_fields(), None);
+
+    let stripped = process_to_jpg(&Config::parse(CONFIG).unwrap(), &tmpdir, source_path.clone());
+    let kept = process_to_jpg(&config_with("keep_exif = true"), &tmpdir, source_path);
+    assert_ne!(stripped, kept);
+}
+
+#[test]
+fn read_exif_data() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let source_path = jpeg_with_metadata(&tmpdir, &photo_fields(), None);
+
+    let proc = Processor::new(tmpdir.clone(), &Config::parse(CONFIG).unwrap());
+    let data = proc.exif_data(&source_path).unwrap();
+    assert_eq!(data.make.as_deref(), Some("Zola"));
+    assert_eq!(data.model.as_deref(), Some("Z1"));
+    assert_eq!(data.lens, None);
+    assert_eq!(data.exposure_time.as_deref(), Some("1/250"));
+    assert_eq!(data.f_number, Some(2.8));
+    assert_eq!(data.iso, Some(200));
+    assert_eq!(data.date_taken.as_deref(), Some("2023-05-01T12:30:00"));
+    assert_eq!(data.gps, None);
+    assert!(proc.exif_data(&TEST_IMGS.join("png.png")).is_none());
+
+    let proc = Processor::new(tmpdir, &config_with("keep_gps = true"));
+    let gps = proc.exif_data(&source_path).unwrap().gps.unwrap();
+    assert!((gps.latitude + 33.87).abs() < 0.001);
+    assert!((gps.longitude - 151.21).abs() < 0.001);
+    assert_eq!(gps.altitude, None);
+}
+
+#[test]
+fn partial_builds_keep_the_previous_placeholders() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let jpg = tmpdir.join("jpg.jpg");
+    let png = tmpdir.join("png.png");
+    std::fs::copy(TEST_IMGS.join("jpg.jpg"), &jpg).unwrap();
+    std::fs::copy(TEST_IMGS.join("png.png"), &png).unwrap();
+    let cache_path = tmpdir.join("static").join("processed_images").join("placeholders.json");
+
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let jpg_placeholder = proc.placeholder(&jpg).unwrap();
+    proc.prune().unwrap();
+    proc.do_process().unwrap();
+
+    // No placeholder is asked for by the pages rendered again
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    proc.set_partial_build(true);
+    proc.prune().unwrap();
+    proc.do_process().unwrap();
+    assert!(std::fs::read_to_string(&cache_path).unwrap().contains(&jpg_placeholder.blurhash));
+
+    // Or only other ones
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    proc.set_partial_build(true);
+    let png_placeholder = proc.placeholder(&png).unwrap();
+    proc.prune().unwrap();
+    proc.do_process().unwrap();
+    let cached = std::fs::read_to_string(&cache_path).unwrap();
+    assert!(cached.contains(&jpg_placeholder.blurhash));
+    assert!(cached.contains(&png_placeholder.blurhash));
+}
+
+/// Copies a test image in the site and resizes it, returning the path of the result
+fn enqueue_in_site(proc: &mut Processor, site: &Path, source_img: &str, width: u32) -> PathBuf {
+    let source_path = site.join(source_img);
+    if !source_path.exists() {
+        std::fs::copy(TEST_IMGS.join(source_img), &source_path).unwrap();
+    }
+    let op = ResizeOperation::from_args("fit_width", Some(width), None, None, None).unwrap();
+    let resp = proc.enqueue(op, vec![], source_img.into(), source_path, "jpg", None, None, false);
+    site.join(resp.unwrap().static_path)
+}
+
+#[test]
+fn images_are_listed_in_the_manifest() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let processed_path = enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    proc.prune().unwrap();
+    proc.do_process().unwrap();
+
+    let manifest_path = tmpdir.join(PROCESSED_PREFIX.as_str()).join(imageproc::MANIFEST_FILENAME);
+    let manifest: libs::serde_json::Value =
+        libs::serde_json::from_str(&std::fs::read_to_string(manifest_path).unwrap()).unwrap();
+    let filename = processed_path.file_name().unwrap().to_str().unwrap();
+    assert_eq!(manifest["images"][filename]["source"], "jpg.jpg");
+    assert_eq!(manifest["images"][filename]["source_hash"].as_str().unwrap().len(), 64);
+}
+
+#[test]
+fn images_are_processed_again_only_if_their_source_changed() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let processed_path = enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    proc.do_process().unwrap();
+
+    // The source being touched, eg by a checkout, doesn't matter
+    std::fs::write(&processed_path, "not processed again").unwrap();
+    let future = libs::filetime::FileTime::from_unix_time(4_000_000_000, 0);
+    libs::filetime::set_file_mtime(tmpdir.join("jpg.jpg"), future).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    proc.do_process().unwrap();
+    assert_eq!(std::fs::read_to_string(&processed_path).unwrap(), "not processed again");
+
+    // Its content does
+    std::fs::copy(TEST_IMGS.join("exif_0.jpg"), tmpdir.join("jpg.jpg")).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    proc.do_process().unwrap();
+    assert!(image::open(&processed_path).is_ok());
+}
+
+#[test]
+fn partial_builds_keep_the_previous_images() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let small = enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    let large = enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 20);
+    proc.do_process().unwrap();
+
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    proc.set_partial_build(true);
+    enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    proc.prune().unwrap();
+    proc.do_process().unwrap();
+    assert!(small.exists());
+    assert!(large.exists());
+
+    // Still listed in the manifest for the next full build to remove it
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    proc.prune().unwrap();
+    proc.do_process().unwrap();
+    assert!(small.exists());
+    assert!(!large.exists());
+}
+
+#[test]
+fn clean_removes_outdated_images() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let jpg = enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    let png = enqueue_in_site(&mut proc, &tmpdir, "png.png", 10);
+    let webp = enqueue_in_site(&mut proc, &tmpdir, "webp.webp", 10);
+    proc.do_process().unwrap();
+    let unknown = tmpdir.join(PROCESSED_PREFIX.as_str()).join("unknown.jpg");
+    std::fs::write(&unknown, "").unwrap();
+
+    std::fs::remove_file(tmpdir.join("png.png")).unwrap();
+    std::fs::copy(TEST_IMGS.join("exif_0.jpg"), tmpdir.join("webp.webp")).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    assert_eq!(proc.clean().unwrap(), 3);
+    assert!(jpg.exists());
+    assert!(!png.exists());
+    assert!(!webp.exists());
+    assert!(!unknown.exists());
+
+    // The manifest only lists what is left
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    assert_eq!(proc.clean().unwrap(), 0);
+    assert!(jpg.exists());
+}
+
+#[test]
+fn clean_keeps_every_image_without_a_manifest() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let jpg = enqueue_in_site(&mut proc, &tmpdir, "jpg.jpg", 10);
+    proc.do_process().unwrap();
+    let unknown = tmpdir.join(PROCESSED_PREFIX.as_str()).join("unknown.jpg");
+    std::fs::write(&unknown, "").unwrap();
+
+    // Eg a fresh clone where the manifest wasn't committed along with the images
+    std::fs::remove_file(tmpdir.join(PROCESSED_PREFIX.as_str()).join(imageproc::MANIFEST_FILENAME))
+        .unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    assert_eq!(proc.clean().unwrap(), 0);
+    assert!(jpg.exists());
+    assert!(unknown.exists());
+}
+
+#[test]
+fn processor_files_are_not_published() {
+    assert!(imageproc::is_processor_file(Path::new("processed_images/manifest.json")));
+    assert!(imageproc::is_processor_file(Path::new("processed_images/placeholders.json")));
+    assert!(!imageproc::is_processor_file(Path::new("manifest.json")));
+    assert!(!imageproc::is_processor_file(Path::new("processed_images/image.jpg")));
+}
+
+/// Writes a 40x20 GIF of 3 frames of different colors, staying 100, 200 and 300ms on screen
+fn write_animated_gif(path: &Path) {
+    use image::codecs::gif::{GifEncoder, Repeat};
+    use image::{Delay, Frame, Rgba, RgbaImage};
+
+    let frames = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]]
+        .iter()
+        .zip([100, 200, 300])
+        .map(|(color, ms)| {
+            let buffer = RgbaImage::from_pixel(40, 20, Rgba(*color));
+            Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(ms, 1))
+        });
+    let mut encoder = GifEncoder::new(File::create(path).unwrap());
+    encoder.set_repeat(Repeat::Infinite).unwrap();
+    encoder.encode_frames(frames).unwrap();
+}
+
+fn read_frames(path: &Path) -> Vec<image::Frame> {
+    use image::codecs::gif::GifDecoder;
+    use image::{AnimationDecoder, Delay, Frame, RgbaImage};
+
+    let reader = std::io::BufReader::new(File::open(path).unwrap());
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("gif") => GifDecoder::new(reader).unwrap().into_frames().collect_frames().unwrap(),
+        Some("webp") => {
+            // The animation decoder of `image` doesn't stop after the last frame
+            let mut decoder = libs::image_webp::WebPDecoder::new(reader).unwrap();
+            let (width, height) = decoder.dimensions();
+            (0..decoder.num_frames())
+                .map(|_| {
+                    let mut buffer = vec![0; decoder.output_buffer_size().unwrap()];
+                    let delay = decoder.read_frame(&mut buffer).unwrap();
+                    let img = if decoder.has_alpha() {
+                        RgbaImage::from_raw(width, height, buffer).unwrap()
+                    } else {
+                        DynamicImage::ImageRgb8(
+                            image::RgbImage::from_raw(width, height, buffer).unwrap(),
+                        )
+                        .into_rgba8()
+                    };
+                    Frame::from_parts(img, 0, 0, Delay::from_numer_denom_ms(delay, 1))
+                })
+                .collect()
+        }
+        _ => panic!("Not an animation: {}", path.display()),
+    }
+}
+
+fn delays_ms(frames: &[image::Frame]) -> Vec<u32> {
+    frames
+        .iter()
+        .map(|f| {
+            let (numer, denom) = f.delay().numer_denom_ms();
+            numer / denom
+        })
+        .collect()
+}
+
+/// Resizes the animation to 20 pixels wide, returning the path of the result
+fn process_animation(
+    config: &Config,
+    tmpdir: &Path,
+    format: &str,
+    first_frame: bool,
+) -> (EnqueueResponse, PathBuf) {
+    let source_path = tmpdir.join("animated.gif");
+    write_animated_gif(&source_path);
+    let mut proc = Processor::new(tmpdir.to_path_buf(), config);
+    let op = ResizeOperation::from_args("fit_width", Some(20), None, None, None).unwrap();
+    let resp = proc
+        .enqueue(op, vec![], "animated.gif".into(), source_path, format, None, None, first_frame)
+        .unwrap();
+    proc.do_process().unwrap();
+    let path = tmpdir.join(&resp.static_path);
+    (resp, path)
+}
+
+#[test]
+fn read_image_metadata_animated_gif() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let source_path = tmpdir.join("animated.gif");
+    write_animated_gif(&source_path);
+    assert_eq!(
+        imageproc::read_image_metadata(&source_path).unwrap(),
+        ImageMetaResponse {
+            width: 40,
+            height: 20,
+            format: Some("gif"),
+            mime: Some("image/gif"),
+            frames: 3,
+            animated: true,
+        }
+    );
+}
+
+#[test]
+fn read_image_metadata_truncated_gif() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let source_path = tmpdir.join("animated.gif");
+    write_animated_gif(&source_path);
+    let bytes = std::fs::read(&source_path).unwrap();
+    std::fs::write(&source_path, &bytes[..bytes.len() / 2]).unwrap();
+    let meta = imageproc::read_image_metadata(&source_path).unwrap();
+    assert_eq!((meta.width, meta.height, meta.frames, meta.animated), (40, 20, 1, false));
+}
+
+#[test]
+fn resize_animated_gif_keeps_every_frame() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (resp, path) = process_animation(&Config::parse(CONFIG).unwrap(), &tmpdir, "auto", false);
+    assert_processed_path_matches(&resp.static_path, PROCESSED_PREFIX.as_str(), "gif");
+    assert_eq!((resp.width, resp.height), (20, 10));
+
+    let frames = read_frames(&path);
+    assert_eq!(delays_ms(&frames), vec![100, 200, 300]);
+    assert!(frames.iter().all(|f| f.buffer().dimensions() == (20, 10)));
+    assert_eq!(frames[1].buffer().get_pixel(10, 5).0[..3], [0, 255, 0]);
+}
+
+#[test]
+fn resize_animated_gif_to_animated_webp() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (resp, path) = process_animation(&Config::parse(CONFIG).unwrap(), &tmpdir, "webp", false);
+    assert_processed_path_matches(&resp.static_path, PROCESSED_PREFIX.as_str(), "webp");
+
+    let frames = read_frames(&path);
+    assert_eq!(delays_ms(&frames), vec![100, 200, 300]);
+    assert!(frames.iter().all(|f| f.buffer().dimensions() == (20, 10)));
+    assert_eq!(frames[2].buffer().get_pixel(10, 5).0[..3], [0, 0, 255]);
+}
+
+#[test]
+fn resize_animated_gif_prefers_webp_with_modern_formats() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = config_with("prefer_modern_formats = true");
+    let (resp, path) = process_animation(&config, &tmpdir, "auto", false);
+    assert_processed_path_matches(&resp.static_path, PROCESSED_PREFIX.as_str(), "webp");
+    assert_eq!(read_frames(&path).len(), 3);
+}
+
+#[test]
+fn resize_animated_gif_first_frame() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let (resp, path) = process_animation(&config, &tmpdir, "auto", true);
+    // Like for any other GIF, the poster is a JPEG
+    assert_processed_path_matches(&resp.static_path, PROCESSED_PREFIX.as_str(), "jpg");
+    let img = image::open(&path).unwrap();
+    assert_eq!(img.dimensions(), (20, 10));
+    assert!(img.get_pixel(10, 5).to_rgb().0[0] > 200);
+
+    let (animated, _) = process_animation(&config, &tmpdir, "webp", false);
+    let (poster, path) = process_animation(&config, &tmpdir, "webp", true);
+    assert_ne!(animated.static_path, poster.static_path);
+    assert_eq!(imageproc::read_image_metadata(&path).unwrap().frames, 1);
+}
+
+#[test]
+fn resize_animated_gif_to_still_format() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (resp, path) = process_animation(&Config::parse(CONFIG).unwrap(), &tmpdir, "png", false);
+    assert_processed_path_matches(&resp.static_path, PROCESSED_PREFIX.as_str(), "png");
+    assert_eq!(image::open(&path).unwrap().dimensions(), (20, 10));
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use config::{Config, SocialCardTemplate};
+use imageproc::{Processor, SocialCard};
+use libs::image::{self, GenericImageView, Rgba};
+
+fn card(title: &str, template: SocialCardTemplate) -> SocialCard {
+    SocialCard {
+        title: title.to_string(),
+        metadata: vec!["January 1, 2024".to_string()],
+        template,
+        background_path: None,
+        font_path: None,
+    }
+}
+
+fn render(tmpdir: &std::path::Path, card: SocialCard) -> PathBuf {
+    let mut proc = Processor::new(tmpdir.to_path_buf(), &Config::default());
+    let resp = proc.enqueue_social_card(card).unwrap();
+    proc.do_process().unwrap();
+    tmpdir.join(resp.static_path)
+}
+
+#[test]
+fn can_render_social_card() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let template = SocialCardTemplate {
+        width: 400,
+        height: 200,
+        background_color: "#000000".to_string(),
+        text_color: "#ff0000".to_string(),
+        title_size: 40,
+        metadata_size: 20,
+        padding: 20,
+        ..Default::default()
+    };
+    let path = render(&tmpdir, card("Hello", template));
+    let img = image::open(path).unwrap();
+    assert_eq!(img.dimensions(), (400, 200));
+
+    let is_text = |p: Rgba<u8>| p[0] > 200 && p[1] == 0 && p[2] == 0;
+    let text_rows = |rows: std::ops::Range<u32>| {
+        rows.filter(|y| (0..400).any(|x| is_text(img.get_pixel(x, *y)))).count()
+    };
+    // The title at the top, the metadata at the bottom and nothing in the padding
+    assert!(text_rows(20..70) > 0);
+    assert!(text_rows(150..180) > 0);
+    assert_eq!(text_rows(0..15), 0);
+    assert_eq!(text_rows(185..200), 0);
+    assert!((0..400).all(|x| !is_text(img.get_pixel(x, 100))));
+}
+
+#[test]
+fn long_titles_are_wrapped_and_shrunk_to_fit() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let template = SocialCardTemplate {
+        width: 300,
+        height: 200,
+        background_color: "#ffffff".to_string(),
+        text_color: "#000000".to_string(),
+        padding: 10,
+        ..Default::default()
+    };
+    let title = "A rather long title that can't possibly fit on a single line of this card";
+    let img = image::open(render(&tmpdir, card(title, template))).unwrap();
+    // Nothing is drawn in the right padding
+    assert!((0..200).all(|y| (291..300).all(|x| img.get_pixel(x, y)[0] == 255)));
+}
+
+#[test]
+fn invalid_templates_are_errors() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let mut proc = Processor::new(tmpdir.clone(), &Config::default());
+    let template = SocialCardTemplate { text_color: "red".to_string(), ..Default::default() };
+    assert!(proc.enqueue_social_card(card("Hello", template)).is_err());
+    let template = SocialCardTemplate { padding: 700, ..Default::default() };
+    assert!(proc.enqueue_social_card(card("Hello", template)).is_err());
+    let mut missing_font = card("Hello", SocialCardTemplate::default());
+    missing_font.font_path = Some(tmpdir.join("missing.ttf"));
+    assert!(proc.enqueue_social_card(missing_font).is_err());
+}
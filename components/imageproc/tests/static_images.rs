@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use config::Config;
+use imageproc::StaticImageOptimizer;
+use libs::image::{self, GenericImageView, ImageFormat};
+
+static TEST_IMGS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test_imgs");
+
+fn optimizer(tmpdir: &Path, options: &str) -> StaticImageOptimizer {
+    let config_str = format!(
+        "title = \"My site\"\nbase_url = \"https://example.com\"\n[static_images]\n{}",
+        options
+    );
+    let config = Config::parse(&config_str).unwrap();
+    StaticImageOptimizer::new(&config, tmpdir.join("cache")).unwrap()
+}
+
+/// Copies a test image in a fresh `static` folder, returning it and its destination
+fn static_image(tmpdir: &Path, name: &str) -> (PathBuf, PathBuf) {
+    let src = tmpdir.join("static").join(name);
+    fs::create_dir_all(src.parent().unwrap()).unwrap();
+    fs::copy(Path::new(TEST_IMGS).join(name), &src).unwrap();
+    (src, tmpdir.join("public").join(name))
+}
+
+#[test]
+fn static_images_are_disabled_by_default() {
+    assert!(StaticImageOptimizer::new(&Config::default(), PathBuf::from("cache")).is_none());
+}
+
+#[test]
+fn can_select_static_images_with_globs() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let optimizer = optimizer(&tmpdir, "exclude = [\"icons/**\"]");
+    assert!(optimizer.is_included(Path::new("photo.jpg")));
+    assert!(optimizer.is_included(Path::new("blog/photo.PNG")));
+    assert!(!optimizer.is_included(Path::new("icons/logo.png")));
+    assert!(!optimizer.is_included(Path::new("style.css")));
+    assert!(!optimizer.is_included(Path::new("processed_images/photo.jpg")));
+}
+
+#[test]
+fn can_scale_static_images_down() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (src, dest) = static_image(&tmpdir, "jpg.jpg");
+    let optimizer = optimizer(&tmpdir, "max_width = 100\nmax_height = 1000");
+
+    let written = optimizer.optimize(&[(src.clone(), dest.clone())]).unwrap();
+    assert!(written.is_empty());
+    let (width, height) = image::open(&src).unwrap().dimensions();
+    let img = image::open(&dest).unwrap();
+    assert_eq!(img.dimensions(), (100, (height as f32 * 100.0 / width as f32).round() as u32));
+}
+
+#[test]
+fn keeps_static_images_that_would_grow() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (src, dest) = static_image(&tmpdir, "jpg.jpg");
+    let optimizer = optimizer(&tmpdir, "quality = 100");
+
+    optimizer.optimize(&[(src.clone(), dest.clone())]).unwrap();
+    assert_eq!(fs::read(&src).unwrap(), fs::read(&dest).unwrap());
+}
+
+#[test]
+fn can_convert_static_images_to_other_formats() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (src, dest) = static_image(&tmpdir, "png.png");
+    let optimizer = optimizer(&tmpdir, "formats = [\"webp\"]");
+
+    let written = optimizer.optimize(&[(src, dest.clone())]).unwrap();
+    let webp = tmpdir.join("public").join("png.webp");
+    assert_eq!(written, vec![webp.clone()]);
+    assert!(dest.exists());
+    assert_eq!(image::guess_format(&fs::read(webp).unwrap()).unwrap(), ImageFormat::WebP);
+}
+
+#[test]
+fn doesnt_overwrite_static_files_with_conversions() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (src, dest) = static_image(&tmpdir, "png.png");
+    fs::write(tmpdir.join("static").join("png.webp"), "").unwrap();
+    let optimizer = optimizer(&tmpdir, "formats = [\"webp\"]");
+
+    assert!(optimizer.optimize(&[(src, dest)]).unwrap().is_empty());
+    assert!(!tmpdir.join("public").join("png.webp").exists());
+}
+
+#[test]
+fn reuses_cached_static_images() {
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let (src, dest) = static_image(&tmpdir, "jpg.jpg");
+    let optimizer = optimizer(&tmpdir, "max_width = 100");
+    optimizer.optimize(&[(src.clone(), dest.clone())]).unwrap();
+
+    // Only the cached file is copied as long as the source and the options don't change
+    let cached = fs::read_dir(tmpdir.join("cache")).unwrap().next().unwrap().unwrap().path();
+    fs::write(&cached, "cached").unwrap();
+    fs::remove_file(&dest).unwrap();
+    optimizer.optimize(&[(src.clone(), dest.clone())]).unwrap();
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "cached");
+
+    let optimizer = self::optimizer(&tmpdir, "max_width = 50");
+    optimizer.optimize(&[(src, dest.clone())]).unwrap();
+    assert_eq!(image::open(&dest).unwrap().width(), 50);
+}
@@ -4,10 +4,12 @@
 //! to define features, it is done in a single place.
 //! It doesn't work for crates exporting macros like `serde` or dev deps but that's ok for most.
 
+pub use ab_glyph;
 pub use ahash;
 pub use ammonia;
 pub use atty;
 pub use base64;
+pub use blurhash;
 pub use csv;
 pub use elasticlunr;
 pub use filetime;
@@ -16,6 +18,8 @@ pub use glob;
 pub use globset;
 pub use grass;
 pub use image;
+pub use image_webp;
+pub use img_parts;
 pub use lexical_sort;
 pub use minify_html;
 pub use nom_bibtex;
@@ -24,6 +28,7 @@ pub use once_cell;
 pub use percent_encoding;
 pub use pulldown_cmark;
 pub use pulldown_cmark_escape;
+pub use pulldown_latex;
 pub use quickxml_to_serde;
 pub use rayon;
 pub use regex;
@@ -0,0 +1,243 @@
+//! Admonitions, also known as callouts: GitHub-style alerts like `> [!NOTE]` and `:::note`
+//! containers, rendered with the `markdown/admonition.html` template.
+use std::borrow::Cow;
+use std::ops::Range;
+
+use config::Admonitions;
+use libs::once_cell::sync::Lazy;
+use libs::pulldown_cmark::{BlockQuoteKind, CowStr, Event, Tag, TagEnd};
+use libs::regex::Regex;
+use libs::tera;
+
+use crate::context::RenderContext;
+use crate::markdown::get_text;
+use errors::{Context, Result};
+
+pub(crate) const ADMONITION_TEMPLATE: &str = "markdown/admonition.html";
+/// Take the place of the start and of the end of the admonitions in the events until
+/// they are rendered
+pub(crate) const ADMONITION_START: &str = "<!-- zola-admonition-start -->";
+pub(crate) const ADMONITION_END: &str = "<!-- zola-admonition-end -->";
+
+/// The first line of an alert, eg `[!NOTE]` or `[!TIP] Some title`
+static ALERT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[!([A-Za-z][\w-]*)\][ \t]*(.*)$").unwrap());
+/// The opening line of a container, eg `:::note` or `::: warning Some title`
+static CONTAINER_START_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^:{3,}[ \t]*([A-Za-z][\w-]*)[ \t]*(.*?)[ \t]*$").unwrap());
+static CONTAINER_END_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^:{3,}[ \t]*$").unwrap());
+/// What `convert_containers` turns the opening and closing lines of containers into
+static CONTAINER_COMMENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^<!--:::(?:([\w-]+) (.*))?-->\r?\n?$").unwrap());
+
+/// An admonition found in the events
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Admonition {
+    /// The type of the admonition, in lowercase
+    pub kind: String,
+    pub title: String,
+}
+
+impl Admonition {
+    fn new(kind: &str, title: Option<&str>, config: &Admonitions) -> Option<Self> {
+        let default_title = config.default_title(kind)?;
+        let title = title.map(str::trim).filter(|t| !t.is_empty()).unwrap_or(default_title);
+        Some(Admonition { kind: kind.to_ascii_lowercase(), title: title.to_owned() })
+    }
+
+    /// Renders the admonition template around the HTML of its content
+    pub(crate) fn render(&self, content: &str, context: &RenderContext) -> Result<String> {
+        let config = &context.config.markdown.admonitions;
+        let mut c = tera::Context::new();
+        c.insert("kind", &self.kind);
+        c.insert("title", &self.title);
+        c.insert("content", content);
+        c.insert("class", &config.class);
+        c.insert("title_class", &config.title_class);
+        c.insert("lang", &context.lang);
+
+        utils::templates::render_template(
+            ADMONITION_TEMPLATE,
+            &context.tera,
+            c,
+            &context.config.theme,
+        )
+        .with_context(|| format!("Failed to render the `{}` admonition", self.kind))
+    }
+}
+
+/// The character and the length of the code fence opened or closed by the line, if any
+fn code_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|x| *x == c).count();
+    (len >= 3).then_some((c, len))
+}
+
+/// Turns the opening and closing lines of the `:::type title` containers into HTML comments,
+/// which end paragraphs and lists like the fences would. They are found in the events
+/// by `mark_admonitions` afterwards.
+/// Only the lines not indented and outside of code blocks are containers, and only with a known
+/// type. Each line is replaced by another one so the line numbers don't change.
+pub(crate) fn convert_containers<'a>(content: &'a str, config: &Admonitions) -> Cow<'a, str> {
+    if !content.contains(":::") {
+        return Cow::Borrowed(content);
+    }
+
+    let mut output = String::with_capacity(content.len());
+    // The fence of the code block we are in
+    let mut code_block: Option<(char, usize)> = None;
+    let mut open_containers = 0;
+
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[text.len()..];
+
+        if let Some((c, len)) = code_fence(text) {
+            match code_block {
+                None => code_block = Some((c, len)),
+                Some((open_c, open_len)) => {
+                    let rest = text.trim_start_matches(' ')[len..].trim();
+                    if c == open_c && len >= open_len && rest.is_empty() {
+                        code_block = None;
+                    }
+                }
+            }
+        } else if code_block.is_none() {
+            if let Some(caps) = CONTAINER_START_RE.captures(text) {
+                if config.default_title(&caps[1]).is_some() && !caps[2].contains("-->") {
+                    output.push_str(&format!("<!--:::{} {}-->{}", &caps[1], &caps[2], ending));
+                    open_containers += 1;
+                    continue;
+                }
+            } else if open_containers > 0 && CONTAINER_END_RE.is_match(text) {
+                output.push_str("<!--:::-->");
+                output.push_str(ending);
+                open_containers -= 1;
+                continue;
+            }
+        }
+
+        output.push_str(line);
+    }
+
+    Cow::Owned(output)
+}
+
+/// If the events of a blockquote start with an alert marker like `[!NOTE] Some title`, its
+/// admonition and the range of the events of the marker to remove
+fn find_alert(events: &[Event], config: &Admonitions) -> Option<(Admonition, Range<usize>)> {
+    if !matches!(events.first(), Some(Event::Start(Tag::Paragraph))) {
+        return None;
+    }
+    if !matches!(events.get(1), Some(Event::Text(text)) if text.starts_with('[')) {
+        return None;
+    }
+
+    let line_end = events.iter().position(|e| {
+        matches!(e, Event::SoftBreak | Event::HardBreak | Event::End(TagEnd::Paragraph))
+    })?;
+    let line = get_text(&events[1..line_end]);
+    let caps = ALERT_RE.captures(&line)?;
+    let admonition = Admonition::new(&caps[1], Some(&caps[2]), config)?;
+
+    // The whole paragraph goes if there is nothing else in it
+    if matches!(events[line_end], Event::End(TagEnd::Paragraph)) {
+        Some((admonition, 0..line_end + 1))
+    } else {
+        Some((admonition, 1..line_end + 1))
+    }
+}
+
+/// The admonition type of a GitHub alert
+pub(crate) fn alert_kind(kind: BlockQuoteKind) -> &'static str {
+    match kind {
+        BlockQuoteKind::Note => "note",
+        BlockQuoteKind::Tip => "tip",
+        BlockQuoteKind::Important => "important",
+        BlockQuoteKind::Warning => "warning",
+        BlockQuoteKind::Caution => "caution",
+    }
+}
+
+/// Replaces the start and the end of the admonitions in the events by `ADMONITION_START` and
+/// `ADMONITION_END` so `render_elements` can render them once their content is ready.
+/// Returns the admonitions found, in order.
+pub(crate) fn mark_admonitions(events: &mut Vec<Event>, config: &Admonitions) -> Vec<Admonition> {
+    let mut admonitions = Vec::new();
+    let mut removed = vec![false; events.len()];
+    // Whether each blockquote we are in is an admonition
+    let mut blockquotes = Vec::new();
+    let mut open_containers = 0;
+
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::BlockQuote(kind)) if config.alerts => {
+                let found = match kind {
+                    // pulldown-cmark already removed the marker of the GitHub ones
+                    Some(kind) => {
+                        Admonition::new(alert_kind(*kind), None, config).map(|a| (a, 0..0))
+                    }
+                    None => find_alert(&events[i + 1..], config),
+                };
+                blockquotes.push(found.is_some());
+                if let Some((admonition, marker)) = found {
+                    admonitions.push(admonition);
+                    events[i] = Event::Html(CowStr::Borrowed(ADMONITION_START));
+                    for j in marker {
+                        removed[i + 1 + j] = true;
+                    }
+                }
+            }
+            Event::End(TagEnd::BlockQuote(_))
+                if config.alerts && blockquotes.pop() == Some(true) =>
+            {
+                events[i] = Event::Html(CowStr::Borrowed(ADMONITION_END));
+            }
+            Event::Html(text) if config.containers => {
+                let Some(caps) = CONTAINER_COMMENT_RE.captures(text) else {
+                    continue;
+                };
+                let marker = match (caps.get(1), caps.get(2)) {
+                    (Some(kind), title) => {
+                        let title = title.map(|t| t.as_str());
+                        match Admonition::new(kind.as_str(), title, config) {
+                            Some(admonition) => {
+                                admonitions.push(admonition);
+                                open_containers += 1;
+                                ADMONITION_START
+                            }
+                            None => continue,
+                        }
+                    }
+                    (None, _) if open_containers > 0 => {
+                        open_containers -= 1;
+                        ADMONITION_END
+                    }
+                    (None, _) => continue,
+                };
+                events[i] = Event::Html(CowStr::Borrowed(marker));
+                // The comment was the only thing in its HTML block
+                if i > 0 && matches!(events[i - 1], Event::Start(Tag::HtmlBlock)) {
+                    removed[i - 1] = true;
+                }
+                if matches!(events.get(i + 1), Some(Event::End(TagEnd::HtmlBlock))) {
+                    removed[i + 1] = true;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut removed = removed.into_iter();
+    events.retain(|_| !removed.next().unwrap());
+    // Containers left open end with the content
+    for _ in 0..open_containers {
+        events.push(Event::Html(CowStr::Borrowed(ADMONITION_END)));
+    }
+
+    admonitions
+}
@@ -0,0 +1,188 @@
+//! The `attributes` extension: `{#id .class key=value}` at the end of paragraphs, list items
+//! and definition list items. Headings have their own, parsed by pulldown-cmark.
+use libs::pulldown_cmark::{Event, Tag, TagEnd};
+use libs::pulldown_cmark_escape::escape_html;
+
+/// The attributes of an element, in the order they are written
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Attributes {
+    id: Option<String>,
+    classes: Vec<String>,
+    others: Vec<(String, String)>,
+}
+
+/// Splits the text at the first whitespace
+fn split_word(text: &str) -> (&str, &str) {
+    text.split_at(text.find(char::is_whitespace).unwrap_or(text.len()))
+}
+
+impl Attributes {
+    /// Parses what is between the braces, `None` if it isn't only attributes
+    fn parse(text: &str) -> Option<Self> {
+        let mut attributes = Attributes::default();
+        let mut rest = text.trim();
+        if rest.is_empty() {
+            return None;
+        }
+
+        while !rest.is_empty() {
+            let remaining = if let Some(id) = rest.strip_prefix('#') {
+                let (id, remaining) = split_word(id);
+                attributes.id = Some(id.to_owned()).filter(|id| !id.is_empty());
+                attributes.id.as_ref()?;
+                remaining
+            } else if let Some(class) = rest.strip_prefix('.') {
+                let (class, remaining) = split_word(class);
+                if class.is_empty() {
+                    return None;
+                }
+                attributes.classes.push(class.to_owned());
+                remaining
+            } else {
+                let (key, value) = rest.split_once('=')?;
+                let valid_key = !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || "-_:".contains(c));
+                if !valid_key {
+                    return None;
+                }
+                // Smart punctuation changes the quotes
+                let (value, remaining) = match value.strip_prefix(['"', '“']) {
+                    Some(quoted) => {
+                        let end = quoted.find(['"', '”'])?;
+                        let quote_len = quoted[end..].chars().next().unwrap().len_utf8();
+                        (&quoted[..end], &quoted[end + quote_len..])
+                    }
+                    None => split_word(value),
+                };
+                attributes.others.push((key.to_owned(), value.to_owned()));
+                remaining
+            };
+
+            if !remaining.is_empty() && !remaining.starts_with(char::is_whitespace) {
+                return None;
+            }
+            rest = remaining.trim_start();
+        }
+
+        Some(attributes)
+    }
+
+    fn to_html(&self) -> String {
+        let mut html = String::new();
+        let mut push = |name: &str, value: &str| {
+            html.push(' ');
+            html.push_str(name);
+            html.push_str("=\"");
+            escape_html(&mut html, value).unwrap();
+            html.push('"');
+        };
+
+        if let Some(id) = &self.id {
+            push("id", id);
+        }
+        if !self.classes.is_empty() {
+            push("class", &self.classes.join(" "));
+        }
+        for (name, value) in &self.others {
+            push(name, value);
+        }
+        html
+    }
+}
+
+/// Splits the text ending with attributes into the text before them and the attributes.
+/// There has to be whitespace between them.
+fn split_attributes(text: &str) -> Option<(&str, Attributes)> {
+    let inner = text.trim_end().strip_suffix('}')?;
+    let open = inner.rfind('{')?;
+    let before = &inner[..open];
+    if !before.ends_with(char::is_whitespace) {
+        return None;
+    }
+    Some((before.trim_end(), Attributes::parse(&inner[open + 1..])?))
+}
+
+/// The HTML element of the blocks that can have attributes
+fn element_name(tag: &Tag) -> Option<&'static str> {
+    match tag {
+        Tag::Paragraph => Some("p"),
+        Tag::Item => Some("li"),
+        Tag::DefinitionListTitle => Some("dt"),
+        Tag::DefinitionListDefinition => Some("dd"),
+        _ => None,
+    }
+}
+
+/// Applies the attributes written at the end of the text of the blocks that can have them,
+/// by replacing the start of these blocks with their HTML.
+/// Returns the ids set that way, so headings don't get the same.
+pub(crate) fn apply_attributes(events: &mut [Event]) -> Vec<String> {
+    let mut ids = Vec::new();
+    // The blocks that can have attributes we are in, with where they start
+    let mut blocks: Vec<(usize, TagEnd, &'static str)> = Vec::new();
+
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(tag) => {
+                if let Some(name) = element_name(tag) {
+                    blocks.push((i, tag.to_end(), name));
+                }
+            }
+            Event::End(end) if blocks.last().is_some_and(|(_, e, _)| e == end) => {
+                let (start, _, name) = blocks.pop().unwrap();
+                // The attributes are in the text at the end of the block
+                let mut first_text = i;
+                while first_text > start + 1 && matches!(events[first_text - 1], Event::Text(_)) {
+                    first_text -= 1;
+                }
+                let mut text = String::new();
+                for event in &events[first_text..i] {
+                    if let Event::Text(t) = event {
+                        text.push_str(t);
+                    }
+                }
+
+                let Some((before, attributes)) = split_attributes(&text) else {
+                    continue;
+                };
+                // A block with only attributes is left as it is
+                if before.is_empty() && first_text == start + 1 {
+                    continue;
+                }
+                events[first_text] = Event::Text(before.to_owned().into());
+                for event in &mut events[first_text + 1..i] {
+                    *event = Event::Text("".into());
+                }
+                events[start] = Event::Html(format!("<{}{}>", name, attributes.to_html()).into());
+                ids.extend(attributes.id);
+            }
+            _ => (),
+        }
+    }
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_attributes() {
+        let (before, attributes) =
+            split_attributes("Some text {#the-id .a .b data-x=1 title=\"A title\"}").unwrap();
+        assert_eq!(before, "Some text");
+        assert_eq!(
+            attributes.to_html(),
+            " id=\"the-id\" class=\"a b\" data-x=\"1\" title=\"A title\""
+        );
+    }
+
+    #[test]
+    fn ignores_text_that_is_not_attributes() {
+        assert!(split_attributes("no space{.a}").is_none());
+        assert!(split_attributes("a set {1, 2}").is_none());
+        assert!(split_attributes("an empty id {#}").is_none());
+        assert!(split_attributes("a function() {}").is_none());
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use config::Config;
 use libs::tera::{Context, Tera};
+use utils::site::WikilinkIndex;
 use utils::templates::ShortcodeDefinition;
 use utils::types::InsertAnchor;
 
@@ -18,6 +19,9 @@ pub struct RenderContext<'a> {
     pub insert_anchor: InsertAnchor,
     pub lang: &'a str,
     pub shortcode_definitions: Cow<'a, HashMap<String, ShortcodeDefinition>>,
+    pub wikilinks: Cow<'a, WikilinkIndex>,
+    /// How many lines of the file are before the content, for the positions in errors
+    pub line_offset: usize,
 }
 
 impl<'a> RenderContext<'a> {
@@ -43,6 +47,8 @@ impl<'a> RenderContext<'a> {
             config,
             lang,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            wikilinks: Cow::Owned(WikilinkIndex::default()),
+            line_offset: 0,
         }
     }
 
@@ -52,11 +58,21 @@ impl<'a> RenderContext<'a> {
         self.shortcode_definitions = Cow::Borrowed(def);
     }
 
+    /// Same as above
+    pub fn set_wikilinks(&mut self, wikilinks: &'a WikilinkIndex) {
+        self.wikilinks = Cow::Borrowed(wikilinks);
+    }
+
     /// Same as above
     pub fn set_current_page_path(&mut self, path: &'a str) {
         self.current_page_path = Some(path);
     }
 
+    /// Same as above
+    pub fn set_line_offset(&mut self, line_offset: usize) {
+        self.line_offset = line_offset;
+    }
+
     // In use in the markdown filter
     // NOTE: This RenderContext is not i18n-aware, see MarkdownFilter::filter for details
     // If this function is ever used outside of MarkdownFilter, take this into consideration
@@ -71,6 +87,8 @@ impl<'a> RenderContext<'a> {
             config,
             lang: &config.default_language,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            wikilinks: Cow::Owned(WikilinkIndex::default()),
+            line_offset: 0,
         }
     }
 }
@@ -0,0 +1,163 @@
+//! Render hooks: optional templates in `templates/markdown/` that replace the HTML we would
+//! output for some elements. They are given the attributes of the element and the HTML of
+//! its content.
+use libs::pulldown_cmark::{self as cmark, CowStr, Event, Tag};
+use libs::tera;
+use utils::templates::check_template_fallbacks;
+
+use crate::admonition::{alert_kind, Admonition, ADMONITION_END, ADMONITION_START};
+use crate::context::RenderContext;
+use crate::markdown::get_text;
+use errors::{Context, Result};
+
+pub(crate) const LINK_TEMPLATE: &str = "markdown/link.html";
+pub(crate) const IMAGE_TEMPLATE: &str = "markdown/image.html";
+pub(crate) const HEADING_TEMPLATE: &str = "markdown/heading.html";
+pub(crate) const CODEBLOCK_TEMPLATE: &str = "markdown/codeblock.html";
+pub(crate) const BLOCKQUOTE_TEMPLATE: &str = "markdown/blockquote.html";
+
+/// Which render hooks the site or its theme defines
+#[derive(Debug, Default)]
+pub(crate) struct RenderHooks {
+    pub link: bool,
+    pub image: bool,
+    pub heading: bool,
+    pub codeblock: bool,
+    pub blockquote: bool,
+}
+
+impl RenderHooks {
+    pub fn new(context: &RenderContext) -> Self {
+        let exists = |name| check_template_fallbacks(name, &context.tera, &context.config.theme);
+        RenderHooks {
+            link: exists(LINK_TEMPLATE).is_some(),
+            image: exists(IMAGE_TEMPLATE).is_some(),
+            heading: exists(HEADING_TEMPLATE).is_some(),
+            codeblock: exists(CODEBLOCK_TEMPLATE).is_some(),
+            blockquote: exists(BLOCKQUOTE_TEMPLATE).is_some(),
+        }
+    }
+
+    /// Whether some elements need `render_elements`. Images and code blocks are rendered while
+    /// going through the events since we need more than what the events tell about them.
+    pub fn wraps_elements(&self) -> bool {
+        self.link || self.heading || self.blockquote
+    }
+
+    fn wraps(&self, tag: &Tag) -> bool {
+        match tag {
+            Tag::Link { .. } => self.link,
+            Tag::Heading { .. } => self.heading,
+            Tag::BlockQuote(_) => self.blockquote,
+            _ => false,
+        }
+    }
+}
+
+/// Renders a hook template with the given variables, along with `lang`
+pub(crate) fn render_hook(
+    name: &str,
+    mut tera_context: tera::Context,
+    context: &RenderContext,
+) -> Result<String> {
+    tera_context.insert("lang", &context.lang);
+    utils::templates::render_template(name, &context.tera, tera_context, &context.config.theme)
+        .with_context(|| format!("Failed to render the `{}` render hook", name))
+}
+
+/// What `render_elements` replaces by the HTML of a template
+enum Element<'a> {
+    /// An element with a render hook, and whether it is an external link
+    Hooked(Tag<'a>, bool),
+    Admonition(Admonition),
+}
+
+/// Replaces every link, heading and blockquote that has a render hook, as well as every
+/// admonition, from its start to its end, by the HTML of its template. Elements inside others
+/// are rendered first so their HTML ends up in the content of their parent.
+/// The links have already been resolved so whether each of them was external is given in order.
+pub(crate) fn render_elements<'a>(
+    events: Vec<Event<'a>>,
+    hooks: &RenderHooks,
+    external_links: Vec<bool>,
+    admonitions: Vec<Admonition>,
+    context: &RenderContext,
+) -> Result<Vec<Event<'a>>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut external_links = external_links.into_iter();
+    let mut admonitions = admonitions.into_iter();
+    // The elements we are in, with the events of their content so far
+    let mut stack: Vec<(Element<'a>, Vec<Event<'a>>)> = Vec::new();
+
+    for event in events {
+        let ends_element = match (&event, stack.last()) {
+            (Event::End(end), Some((Element::Hooked(tag, _), _))) => tag.to_end() == *end,
+            (Event::Html(CowStr::Borrowed(ADMONITION_END)), Some((Element::Admonition(_), _))) => {
+                true
+            }
+            _ => false,
+        };
+        if ends_element {
+            let (element, content) = stack.pop().unwrap();
+            let html = render_element(element, content, context)?;
+            let parent = stack.last_mut().map_or(&mut output, |(_, events)| events);
+            parent.push(Event::Html(html.into()));
+            continue;
+        }
+
+        match event {
+            Event::Start(tag) if hooks.wraps(&tag) => {
+                let external =
+                    matches!(tag, Tag::Link { .. }) && external_links.next().unwrap_or(false);
+                stack.push((Element::Hooked(tag, external), Vec::new()));
+            }
+            Event::Html(CowStr::Borrowed(ADMONITION_START)) => {
+                let admonition = admonitions.next().expect("every admonition start is marked");
+                stack.push((Element::Admonition(admonition), Vec::new()));
+            }
+            _ => stack.last_mut().map_or(&mut output, |(_, events)| events).push(event),
+        }
+    }
+
+    Ok(output)
+}
+
+fn render_element(
+    element: Element,
+    content: Vec<Event>,
+    context: &RenderContext,
+) -> Result<String> {
+    let mut c = tera::Context::new();
+    let text = get_text(&content);
+    let mut html = String::new();
+    cmark::html::push_html(&mut html, content.into_iter());
+
+    let (tag, external) = match element {
+        Element::Hooked(tag, external) => (tag, external),
+        Element::Admonition(admonition) => return admonition.render(&html, context),
+    };
+    c.insert("content", &html);
+
+    let name = match tag {
+        Tag::Link { dest_url, title, .. } => {
+            c.insert("url", &dest_url.as_ref());
+            c.insert("title", &title.as_ref());
+            c.insert("external", &external);
+            LINK_TEMPLATE
+        }
+        Tag::Heading { level, id, classes, .. } => {
+            c.insert("level", &(level as u32));
+            c.insert("id", &id.as_deref());
+            c.insert("classes", &classes.iter().map(|c| c.as_ref()).collect::<Vec<_>>());
+            c.insert("title", &text);
+            HEADING_TEMPLATE
+        }
+        Tag::BlockQuote(kind) => {
+            c.insert("kind", &kind.map(alert_kind));
+            BLOCKQUOTE_TEMPLATE
+        }
+        _ => unreachable!("only the elements with a render hook are wrapped"),
+    };
+
+    render_hook(name, c, context)
+}
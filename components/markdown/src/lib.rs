@@ -1,8 +1,15 @@
+mod admonition;
+mod attributes;
 mod codeblock;
 mod context;
+mod hooks;
 mod markdown;
+mod math;
 mod shortcode;
 
+use std::borrow::Cow;
+
+use admonition::convert_containers;
 use shortcode::{extract_shortcodes, insert_md_shortcodes};
 
 use errors::Result;
@@ -12,6 +19,14 @@ pub use crate::markdown::Rendered;
 pub use context::RenderContext;
 
 pub fn render_content(content: &str, context: &RenderContext) -> Result<markdown::Rendered> {
+    // The containers have to be found before parsing the content
+    let content = if context.config.markdown.admonitions.containers {
+        convert_containers(content, &context.config.markdown.admonitions)
+    } else {
+        Cow::Borrowed(content)
+    };
+    let content = content.as_ref();
+
     // avoid parsing the content if needed
     if !content.contains("{{") && !content.contains("{%") {
         return markdown_to_html(content, context, Vec::new());
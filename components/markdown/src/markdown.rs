@@ -2,7 +2,8 @@ use std::collections::HashMap;
 use std::fmt::Write;
 
 use crate::markdown::cmark::CowStr;
-use errors::bail;
+use config::MathRendering;
+use errors::{anyhow, bail};
 use libs::gh_emoji::Replacer as EmojiReplacer;
 use libs::once_cell::sync::Lazy;
 use libs::pulldown_cmark as cmark;
@@ -14,17 +15,22 @@ use crate::context::RenderContext;
 use errors::{Context, Error, Result};
 use libs::pulldown_cmark_escape::escape_html;
 use libs::regex::{Regex, RegexBuilder};
-use utils::site::resolve_internal_link;
+use utils::site::{resolve_internal_link, ResolvedInternalLink};
 use utils::slugs::slugify_anchors;
 use utils::table_of_contents::{make_table_of_contents, Heading};
 use utils::types::InsertAnchor;
 
 use self::cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use crate::admonition::mark_admonitions;
+use crate::attributes::apply_attributes;
 use crate::codeblock::{CodeBlock, FenceSettings};
+use crate::hooks::{render_elements, render_hook, RenderHooks, CODEBLOCK_TEMPLATE, IMAGE_TEMPLATE};
+use crate::math::{line_and_column, render_math};
 use crate::shortcode::{Shortcode, SHORTCODE_PLACEHOLDER};
 
 const CONTINUE_READING: &str = "<span id=\"continue-reading\"></span>";
 const ANCHOR_LINK_TEMPLATE: &str = "anchor-link.html";
+const RESPONSIVE_IMAGE_TEMPLATE: &str = "markdown/responsive_image.html";
 static EMOJI_REPLACER: Lazy<EmojiReplacer> = Lazy::new(EmojiReplacer::new);
 
 /// Set as a regex to help match some extra cases. This way, spaces and case don't matter.
@@ -40,6 +46,10 @@ static FOOTNOTES_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"<sup class="footnote-reference"><a href=\s*.*?>\s*.*?</a></sup>"#).unwrap()
 });
 
+/// A `~sub~` or a `^sup^` without whitespace, the first group being the subscript
+static INTRAWORD_SCRIPT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"~([^~\s]+)~|\^([^\^\s]+)\^").unwrap());
+
 /// Although there exists [a list of registered URI schemes][uri-schemes], a link may use arbitrary,
 /// private schemes. This regex checks if the given string starts with something that just looks
 /// like a scheme, i.e., a case-insensitive identifier followed by a colon.
@@ -79,6 +89,61 @@ fn is_colocated_asset_link(link: &str) -> bool {
         && !STARTS_WITH_SCHEMA_RE.is_match(link)
 }
 
+/// Whether the image is a colocated one that `[markdown.images]` can resize
+fn is_responsive_image_link(link: &str) -> bool {
+    is_colocated_asset_link(link)
+        && link.rsplit_once('.').is_some_and(|(_, ext)| {
+            matches!(ext.to_ascii_lowercase().as_str(), "jpg" | "jpeg" | "png" | "webp")
+        })
+}
+
+/// An image rendered with the responsive image template or the `image.html` render hook,
+/// collecting its alt text
+#[derive(Debug, Default)]
+struct TemplatedImage {
+    src: String,
+    /// The final URL of the image, only used by the render hook
+    url: String,
+    title: String,
+    alt: String,
+    responsive: bool,
+}
+
+impl TemplatedImage {
+    fn render(&self, context: &RenderContext) -> Result<String> {
+        // The path of the image for `responsive_image`, relative to the site root
+        let path = match context.current_page_path.and_then(|p| p.rsplit_once('/')) {
+            Some((dir, _)) => format!("content/{}/{}", dir, self.src),
+            None => format!("content/{}", self.src),
+        };
+        let mut c = tera::Context::new();
+        c.insert("src", &self.src);
+        c.insert("alt", &self.alt);
+        c.insert("title", &self.title);
+
+        if !self.responsive {
+            c.insert("url", &self.url);
+            c.insert("path", &is_colocated_asset_link(&self.src).then_some(path));
+            return render_hook(IMAGE_TEMPLATE, c, context);
+        }
+        c.insert("path", &path);
+
+        let images = &context.config.markdown.images;
+        c.insert("widths", &images.widths);
+        c.insert("formats", &images.formats);
+        c.insert("sizes", &images.sizes);
+        c.insert("lang", &context.lang);
+
+        utils::templates::render_template(
+            RESPONSIVE_IMAGE_TEMPLATE,
+            &context.tera,
+            c,
+            &context.config.theme,
+        )
+        .with_context(|| format!("Failed to render the responsive image `{}`", self.src))
+    }
+}
+
 #[derive(Debug)]
 pub struct Rendered {
     pub body: String,
@@ -150,6 +215,44 @@ fn find_anchor(anchors: &[String], name: String, level: u16) -> String {
     find_anchor(anchors, name, level + 1)
 }
 
+/// Errors or warns about a broken internal link, depending on `link_checker.internal_level`.
+/// The link is kept as it is when it's only a warning.
+fn broken_internal_link(msg: String, link: &str, context: &RenderContext) -> Result<String> {
+    match context.config.link_checker.internal_level {
+        config::LinkCheckerLevel::Error => bail!(msg),
+        config::LinkCheckerLevel::Warn => {
+            console::warn(&msg);
+            Ok(link.to_string())
+        }
+    }
+}
+
+/// Resolves the target of a `[[wikilink]]`, like `Page Title`, `blog/hello#some-heading` or
+/// `#some-heading` for the current page. The anchor is slugified like the heading ids.
+fn resolve_wikilink(link: &str, context: &RenderContext) -> Result<ResolvedInternalLink> {
+    let (name, anchor) = match link.split_once('#') {
+        Some((name, anchor)) => (name, Some(anchor.trim()).filter(|a| !a.is_empty())),
+        None => (link, None),
+    };
+    let md_path = if name.trim().is_empty() {
+        context.current_page_path.ok_or_else(|| anyhow!("it isn't in a page or a section"))?
+    } else {
+        context.wikilinks.resolve(name, context.lang)?
+    };
+    let permalink =
+        context.permalinks.get(md_path).ok_or_else(|| anyhow!("`{}` has no permalink", md_path))?;
+    let anchor = anchor.map(|a| slugify_anchors(a, context.config.slugify.anchors));
+
+    Ok(ResolvedInternalLink {
+        permalink: match anchor {
+            Some(ref anchor) => format!("{}#{}", permalink, anchor),
+            None => permalink.to_owned(),
+        },
+        md_path: md_path.to_owned(),
+        anchor,
+    })
+}
+
 fn fix_link(
     link_type: LinkType,
     link: &str,
@@ -162,10 +265,27 @@ fn fix_link(
     }
 
     // A few situations here:
+    // - it could be a wikilink (`[[Page Title]]`)
     // - it could be a relative link (starting with `@/`)
     // - it could be a link to a co-located asset
     // - it could be a normal link
-    let result = if link.starts_with("@/") {
+    let result = if let LinkType::WikiLink { .. } = link_type {
+        match resolve_wikilink(link, context) {
+            Ok(resolved) => {
+                internal_links.push((resolved.md_path, resolved.anchor));
+                resolved.permalink
+            }
+            Err(e) => {
+                let msg = format!(
+                    "Broken wikilink `[[{}]]` in {}: {}",
+                    link,
+                    context.current_page_path.unwrap_or("unknown"),
+                    e
+                );
+                broken_internal_link(msg, link, context)?
+            }
+        }
+    } else if link.starts_with("@/") {
         match resolve_internal_link(link, &context.permalinks) {
             Ok(resolved) => {
                 internal_links.push((resolved.md_path, resolved.anchor));
@@ -177,13 +297,7 @@ fn fix_link(
                     link,
                     context.current_page_path.unwrap_or("unknown"),
                 );
-                match context.config.link_checker.internal_level {
-                    config::LinkCheckerLevel::Error => bail!(msg),
-                    config::LinkCheckerLevel::Warn => {
-                        console::warn(&msg);
-                        link.to_string()
-                    }
-                }
+                broken_internal_link(msg, link, context)?
             }
         }
     } else if is_colocated_asset_link(link) {
@@ -209,7 +323,7 @@ fn fix_link(
 }
 
 /// get only text in a slice of events
-fn get_text(parser_slice: &[Event]) -> String {
+pub(crate) fn get_text(parser_slice: &[Event]) -> String {
     let mut title = String::new();
 
     for event in parser_slice.iter() {
@@ -222,6 +336,62 @@ fn get_text(parser_slice: &[Event]) -> String {
     title
 }
 
+/// Renders the `~sub~` and `^sup^` of consecutive text events into the output, or pushes the
+/// events as they are if there aren't any
+fn push_intraword_scripts<'a>(
+    texts: &mut Vec<Event<'a>>,
+    output: &mut Vec<Event<'a>>,
+    superscript: bool,
+    subscript: bool,
+) {
+    let text = get_text(texts);
+    let mut last = 0;
+    for caps in INTRAWORD_SCRIPT_RE.captures_iter(&text) {
+        let (tag, inner) = match (caps.get(1), caps.get(2)) {
+            (Some(inner), _) if subscript => ("sub", inner),
+            (_, Some(inner)) if superscript => ("sup", inner),
+            _ => continue,
+        };
+        let whole = caps.get(0).unwrap();
+        if last < whole.start() {
+            output.push(Event::Text(text[last..whole.start()].to_owned().into()));
+        }
+        output.push(Event::Html(format!("<{}>", tag).into()));
+        output.push(Event::Text(inner.as_str().to_owned().into()));
+        output.push(Event::Html(format!("</{}>", tag).into()));
+        last = whole.end();
+    }
+
+    if last == 0 {
+        output.append(texts);
+    } else {
+        if last < text.len() {
+            output.push(Event::Text(text[last..].to_owned().into()));
+        }
+        texts.clear();
+    }
+}
+
+/// pulldown-cmark only finds `^sup^` and `~sub~` at the boundaries of words, like emphasis,
+/// so the ones inside words like `H~2~O` are still in the text. Adjacent text events are joined
+/// since it splits the text around the markers.
+fn render_intraword_scripts(events: Vec<Event>, superscript: bool, subscript: bool) -> Vec<Event> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut texts = Vec::new();
+
+    for event in events {
+        if matches!(event, Event::Text(_)) {
+            texts.push(event);
+        } else {
+            push_intraword_scripts(&mut texts, &mut output, superscript, subscript);
+            output.push(event);
+        }
+    }
+    push_intraword_scripts(&mut texts, &mut output, superscript, subscript);
+
+    output
+}
+
 fn get_heading_refs(events: &[Event]) -> Vec<HeadingRef> {
     let mut heading_refs = vec![];
 
@@ -414,6 +584,8 @@ pub fn markdown_to_html(
     let mut error = None;
 
     let mut code_block: Option<CodeBlock> = None;
+    // Where the code block starts in the events and its language, if it has a render hook
+    let mut hooked_code_block: Option<(usize, Option<String>)> = None;
     // Indicates whether we're in the middle of parsing a text node which will be placed in an HTML
     // attribute, and which hence has to be escaped using escape_html rather than push_html's
     // default HTML body escaping for text nodes.
@@ -426,18 +598,42 @@ pub fn markdown_to_html(
     let mut stop_next_end_p = false;
 
     let lazy_async_image = context.config.markdown.lazy_async_image;
+    let responsive_images =
+        context.config.markdown.images.responsive && context.current_page_path.is_some();
+    // Set while going through the events of an image rendered with a template
+    let mut templated_image: Option<TemplatedImage> = None;
+    let hooks = RenderHooks::new(context);
+    // Whether each link given to the link render hook is external, in order
+    let mut hooked_links_external = Vec::new();
 
     let mut opts = Options::empty();
     let mut has_summary = false;
-    opts.insert(Options::ENABLE_TABLES);
-    opts.insert(Options::ENABLE_FOOTNOTES);
-    opts.insert(Options::ENABLE_STRIKETHROUGH);
-    opts.insert(Options::ENABLE_TASKLISTS);
-    opts.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    let extensions = &context.config.markdown.extensions;
+    for (enabled, option) in [
+        (extensions.tables, Options::ENABLE_TABLES),
+        (extensions.footnotes, Options::ENABLE_FOOTNOTES),
+        (extensions.strikethrough, Options::ENABLE_STRIKETHROUGH),
+        (extensions.tasklists, Options::ENABLE_TASKLISTS),
+        (extensions.heading_attributes, Options::ENABLE_HEADING_ATTRIBUTES),
+        (extensions.definition_lists, Options::ENABLE_DEFINITION_LIST),
+        (extensions.superscript, Options::ENABLE_SUPERSCRIPT),
+        (extensions.subscript, Options::ENABLE_SUBSCRIPT),
+        (extensions.wikilinks, Options::ENABLE_WIKILINKS),
+    ] {
+        opts.set(option, enabled);
+    }
+    let intraword_scripts = extensions.superscript || extensions.subscript;
 
     if context.config.markdown.smart_punctuation {
         opts.insert(Options::ENABLE_SMART_PUNCTUATION);
     }
+    if context.config.markdown.math == MathRendering::MathMl {
+        opts.insert(Options::ENABLE_MATH);
+    }
+    // Gives the type of the GitHub alerts, to the admonitions or to the blockquote hook
+    if context.config.markdown.admonitions.alerts || hooks.blockquote {
+        opts.insert(Options::ENABLE_GFM);
+    }
 
     // we reverse their order so we can pop them easily in order
     let mut html_shortcodes: Vec<_> = html_shortcodes.into_iter().rev().collect();
@@ -506,8 +702,22 @@ pub fn markdown_to_html(
 
         let mut accumulated_block = String::new();
         for (event, mut range) in Parser::new_ext(content, opts).into_offset_iter() {
+            if let Some(image) = templated_image.as_mut() {
+                match event {
+                    Event::Text(text) | Event::Code(text) => image.alt.push_str(&text),
+                    Event::End(TagEnd::Image) => {
+                        match templated_image.take().unwrap().render(context) {
+                            Ok(html) => events.push(Event::Html(html.into())),
+                            Err(e) => error = Some(e),
+                        }
+                    }
+                    _ => (),
+                }
+                continue;
+            }
+
             match event {
-                Event::Text(text) => {
+                Event::Text(mut text) => {
                     if let Some(ref mut _code_block) = code_block {
                         if contains_shortcode(text.as_ref()) {
                             // mark the start of the code block events
@@ -534,6 +744,18 @@ pub fn markdown_to_html(
                             accumulated_block += &text;
                         }
                     } else {
+                        // An escaped `\~` or `\^` starts a text event, we keep it away from
+                        // `render_intraword_scripts`
+                        if intraword_scripts
+                            && text.starts_with(['~', '^'])
+                            && content[..range.start].ends_with('\\')
+                            && !inside_attribute
+                        {
+                            let (escaped, rest) = text.split_at(1);
+                            events.push(Event::Html(escaped.to_owned().into()));
+                            text = rest.to_owned().into();
+                            range.start += 1;
+                        }
                         let text = if context.config.markdown.render_emoji {
                             EMOJI_REPLACER.replace_all(&text).to_string().into()
                         } else {
@@ -559,20 +781,64 @@ pub fn markdown_to_html(
                         cmark::CodeBlockKind::Fenced(fence_info) => FenceSettings::new(fence_info),
                         _ => FenceSettings::new(""),
                     };
+                    if hooks.codeblock {
+                        let language = fence.language.map(|l| l.to_string());
+                        hooked_code_block = Some((events.len(), language));
+                    }
                     let (block, begin) = CodeBlock::new(fence, context.config, path);
                     code_block = Some(block);
                     events.push(Event::Html(begin.into()));
                 }
                 Event::End(TagEnd::CodeBlock { .. }) => {
+                    let mut html = String::new();
                     if let Some(ref mut code_block) = code_block {
-                        let html = code_block.highlight(&accumulated_block);
-                        events.push(Event::Html(html.into()));
-                        accumulated_block.clear();
+                        html = code_block.highlight(&accumulated_block);
                     }
 
                     // reset highlight and close the code block
                     code_block = None;
-                    events.push(Event::Html("</code></pre>\n".into()));
+                    if let Some((start, language)) = hooked_code_block.take() {
+                        // The hook is given the HTML we would have rendered
+                        let mut content = String::new();
+                        for event in events.drain(start..) {
+                            if let Event::Html(begin) = event {
+                                content.push_str(&begin);
+                            }
+                        }
+                        content.push_str(&html);
+                        content.push_str("</code></pre>\n");
+
+                        let mut c = tera::Context::new();
+                        c.insert("language", &language);
+                        c.insert("code", &accumulated_block);
+                        c.insert("content", &content);
+                        match render_hook(CODEBLOCK_TEMPLATE, c, context) {
+                            Ok(html) => events.push(Event::Html(html.into())),
+                            Err(e) => error = Some(e),
+                        }
+                    } else {
+                        events.push(Event::Html(html.into()));
+                        events.push(Event::Html("</code></pre>\n".into()));
+                    }
+                    accumulated_block.clear();
+                }
+                Event::Start(Tag::Image { dest_url, title, .. })
+                    if hooks.image
+                        || (responsive_images && is_responsive_image_link(&dest_url)) =>
+                {
+                    let responsive = responsive_images && is_responsive_image_link(&dest_url);
+                    let url = if is_colocated_asset_link(&dest_url) {
+                        format!("{}{}", context.current_page_permalink, &*dest_url)
+                    } else {
+                        dest_url.to_string()
+                    };
+                    templated_image = Some(TemplatedImage {
+                        src: dest_url.to_string(),
+                        url,
+                        title: title.to_string(),
+                        responsive,
+                        ..Default::default()
+                    });
                 }
                 Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
                     let link = if is_colocated_asset_link(&dest_url) {
@@ -610,6 +876,7 @@ pub fn markdown_to_html(
                     if dest_url.is_empty() =>
                 {
                     error = Some(Error::msg("There is a link that is missing a URL"));
+                    hooked_links_external.push(false);
                     events.push(Event::Start(Tag::Link {
                         link_type,
                         dest_url: "#".into(),
@@ -633,8 +900,11 @@ pub fn markdown_to_html(
                         }
                     };
 
+                    hooked_links_external.push(is_external_link(&dest_url));
                     events.push(
-                        if is_external_link(&dest_url)
+                        // The render hook decides what to do with external links
+                        if !hooks.link
+                            && is_external_link(&dest_url)
                             && context.config.markdown.has_external_link_tweaks()
                         {
                             let mut escaped = String::new();
@@ -684,6 +954,22 @@ pub fn markdown_to_html(
                         event
                     });
                 }
+                Event::InlineMath(ref tex) | Event::DisplayMath(ref tex) => {
+                    let display = matches!(event, Event::DisplayMath(_));
+                    match render_math(tex, display) {
+                        Ok(html) => events.push(Event::InlineHtml(html.into())),
+                        Err(e) => {
+                            let (line, column) = line_and_column(content, range.start);
+                            error = Some(anyhow!(
+                                "Invalid TeX math at line {}, column {} of {}: {}",
+                                line + context.line_offset,
+                                column,
+                                context.current_page_path.unwrap_or("unknown"),
+                                e
+                            ));
+                        }
+                    }
+                }
                 Event::Html(text) if !has_summary && MORE_DIVIDER_RE.is_match(text.as_ref()) => {
                     has_summary = true;
                     events.push(Event::Html(CONTINUE_READING.into()));
@@ -697,6 +983,14 @@ pub fn markdown_to_html(
             }
         }
 
+        if intraword_scripts {
+            events = render_intraword_scripts(events, extensions.superscript, extensions.subscript);
+        }
+
+        // Ids set with attributes, which the headings can't have
+        let attribute_ids =
+            if extensions.attributes { apply_attributes(&mut events) } else { Vec::new() };
+
         // We remove all the empty things we might have pushed before so we don't get some random \n
         events.retain(|e| match e {
             Event::Text(text) | Event::Html(text) => !text.is_empty(),
@@ -706,7 +1000,7 @@ pub fn markdown_to_html(
         let heading_refs = get_heading_refs(&events);
 
         let mut anchors_to_insert = vec![];
-        let mut inserted_anchors = vec![];
+        let mut inserted_anchors = attribute_ids;
         for heading in &heading_refs {
             if let Some(s) = &heading.id {
                 inserted_anchors.push(s.to_owned());
@@ -730,8 +1024,16 @@ pub fn markdown_to_html(
             inserted_anchors.push(heading_ref.id.clone().unwrap());
             let id = inserted_anchors.last().unwrap();
 
-            let html = heading_ref.to_html(id);
-            events[start_idx] = Event::Html(html.into());
+            if hooks.heading {
+                // The heading is rendered by its hook once we have its content
+                if let Event::Start(Tag::Heading { id: ref mut heading_id, .. }) = events[start_idx]
+                {
+                    *heading_id = Some(id.clone().into());
+                }
+            } else {
+                let html = heading_ref.to_html(id);
+                events[start_idx] = Event::Html(html.into());
+            }
 
             // generate anchors and places to insert them
             if context.insert_anchor != InsertAnchor::None {
@@ -782,6 +1084,16 @@ pub fn markdown_to_html(
             convert_footnotes_to_github_style(&mut events);
         }
 
+        let admonitions = if context.config.markdown.admonitions.is_enabled() {
+            mark_admonitions(&mut events, &context.config.markdown.admonitions)
+        } else {
+            Vec::new()
+        };
+
+        if hooks.wraps_elements() || !admonitions.is_empty() {
+            events = render_elements(events, &hooks, hooked_links_external, admonitions, context)?;
+        }
+
         let continue_reading = events
             .iter()
             .position(|e| matches!(e, Event::Html(CowStr::Borrowed(CONTINUE_READING))))
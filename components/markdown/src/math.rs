@@ -0,0 +1,35 @@
+use libs::pulldown_cmark_escape::escape_html;
+use libs::pulldown_latex::config::DisplayMode;
+use libs::pulldown_latex::{push_mathml, Parser, RenderConfig, Storage};
+
+use errors::{anyhow, Result};
+
+/// Converts TeX math to MathML, keeping the source as an annotation.
+/// `display` is for `$$...$$` blocks, which are rendered on their own line.
+pub(crate) fn render_math(tex: &str, display: bool) -> Result<String> {
+    let storage = Storage::new();
+    let events: Vec<_> = Parser::new(tex, &storage).collect();
+    if let Some(Err(e)) = events.iter().find(|e| e.is_err()) {
+        return Err(anyhow!("{}", e));
+    }
+
+    // The annotation is written as it is
+    let mut annotation = String::new();
+    escape_html(&mut annotation, tex)?;
+    let config = RenderConfig {
+        display_mode: if display { DisplayMode::Block } else { DisplayMode::Inline },
+        annotation: Some(&annotation),
+        ..RenderConfig::default()
+    };
+    let mut html = String::new();
+    push_mathml(&mut html, events.into_iter(), config)?;
+    Ok(html)
+}
+
+/// The line and column, starting from 1, of the given byte offset in the content
+pub(crate) fn line_and_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or(before).chars().count() + 1;
+    (line, column)
+}
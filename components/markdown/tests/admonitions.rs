@@ -0,0 +1,111 @@
+mod common;
+use config::Config;
+use utils::types::InsertAnchor;
+
+fn config() -> Config {
+    let mut config = Config::default_for_test();
+    config.markdown.admonitions.alerts = true;
+    config.markdown.admonitions.containers = true;
+    config
+}
+
+#[test]
+fn leaves_alerts_and_containers_as_is_by_default() {
+    let content = "> [!NOTE]\n> Hello\n\n:::tip\nWorld\n:::";
+    let body = common::render(content).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_render_github_alerts() {
+    let content = r#"
+> [!NOTE]
+> Useful information.
+
+> [!tip]
+>
+> Helpful advice, with a [link](https://example.com).
+
+> [!WARNING] Mind the gap
+> Urgent info.
+
+> [!NOPE]
+> Not an admonition.
+
+> Just a quote.
+"#;
+    let body = common::render_with_config(content, config()).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_render_containers() {
+    let content = r#"
+Some text
+:::note
+Inside a note:
+
+- a list
+:::
+
+::: warning Be careful
+## A heading
+
+:::tip Nested
+Yes
+:::
+:::
+
+```md
+:::note
+Not in code blocks
+:::
+```
+
+:::unknown
+Not an admonition
+:::
+
+:::caution
+Left open
+"#;
+    let rendered = common::render_with_config(content, config()).unwrap();
+    assert_eq!(rendered.toc[0].id, "a-heading");
+    insta::assert_snapshot!(rendered.body);
+}
+
+#[test]
+fn can_declare_admonition_types_and_classes() {
+    let mut config = config();
+    config.markdown.admonitions.class = "callout".to_owned();
+    config.markdown.admonitions.title_class = "callout-title".to_owned();
+    config.markdown.admonitions.types.insert("danger".to_owned(), "Danger".to_owned());
+    config.markdown.admonitions.types.insert("note".to_owned(), "Remarque".to_owned());
+    let content = "> [!DANGER]\n> Do not.\n\n:::danger Really\nDo not.\n:::\n\n> [!NOTE]\n> Ceci.";
+    let body = common::render_with_config(content, config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_override_admonition_template() {
+    let templates = [
+        (
+            "markdown/admonition.html",
+            r#"<div data-kind="{{ kind }}" data-lang="{{ lang }}"><b>{{ title }}</b>{{ content | safe }}</div>"#,
+        ),
+        ("markdown/link.html", r#"<a href="{{ url }}" data-hooked>{{ content | safe }}</a>"#),
+    ];
+    let content = "> [!IMPORTANT]\n> Read [this](https://example.com) first.";
+    let body =
+        common::render_with_config_and_templates(content, config(), &templates).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn blockquote_hook_gets_alert_kind() {
+    let templates =
+        [("markdown/blockquote.html", "<div data-kind=\"{{ kind }}\">{{ content | safe }}</div>")];
+    let content = "> [!CAUTION]\n> Hot.";
+    let body = common::render_with_templates(content, InsertAnchor::None, &templates).unwrap().body;
+    assert_eq!(body, "<div data-kind=\"caution\"><p>Hot.</p>\n</div>");
+}
@@ -8,15 +8,20 @@ use config::Config;
 use errors::Result;
 use markdown::{render_content, RenderContext, Rendered};
 use templates::ZOLA_TERA;
+use utils::site::WikilinkIndex;
 use utils::types::InsertAnchor;
 
 fn configurable_render(
     content: &str,
     config: Config,
     insert_anchor: InsertAnchor,
+    templates: &[(&str, &str)],
 ) -> Result<Rendered> {
     let mut tera = Tera::default();
     tera.extend(&ZOLA_TERA).unwrap();
+    for (name, template) in templates {
+        tera.add_raw_template(name, template).unwrap();
+    }
 
     // out_put_id looks like a markdown string
     tera.add_raw_template("shortcodes/out_put_id.html", "{{id}}").unwrap();
@@ -51,9 +56,23 @@ fn configurable_render(
     )
     .unwrap();
     tera.add_raw_template("shortcodes/md_passthrough.md", "{{body}}").unwrap();
+    // The built-in one calls `responsive_image`, which needs a site
+    tera.add_raw_template(
+        "markdown/responsive_image.html",
+        r#"<picture data-path="{{ path }}" data-widths="{{ widths | join(sep=",") }}">{{ alt }}|{{ title }}</picture>"#,
+    )
+    .unwrap();
 
     let mut permalinks = HashMap::new();
     permalinks.insert("pages/about.md".to_owned(), "https://getzola.org/about/".to_owned());
+    permalinks.insert("my_page.md".to_owned(), "https://www.getzola.org/test/".to_owned());
+    permalinks.insert("blog/setup.md".to_owned(), "https://getzola.org/blog/setup/".to_owned());
+    permalinks.insert("docs/setup.md".to_owned(), "https://getzola.org/docs/setup/".to_owned());
+    let mut wikilinks = WikilinkIndex::default();
+    wikilinks.insert("pages/about.md", "en", "pages/about", &["About us", "about"]);
+    wikilinks.insert("my_page.md", "en", "my_page", &["My page", "my_page"]);
+    wikilinks.insert("blog/setup.md", "en", "blog/setup", &["Setup", "setup"]);
+    wikilinks.insert("docs/setup.md", "en", "docs/setup", &["Setup", "setup"]);
 
     tera.register_filter(
         "markdown",
@@ -69,19 +88,38 @@ fn configurable_render(
     );
     let shortcode_def = utils::templates::get_shortcodes(&tera);
     context.set_shortcode_definitions(&shortcode_def);
+    context.set_wikilinks(&wikilinks);
     context.set_current_page_path("my_page.md");
 
     render_content(content, &context)
 }
 
 pub fn render(content: &str) -> Result<Rendered> {
-    configurable_render(content, Config::default_for_test(), InsertAnchor::None)
+    configurable_render(content, Config::default_for_test(), InsertAnchor::None, &[])
 }
 
 pub fn render_with_config(content: &str, config: Config) -> Result<Rendered> {
-    configurable_render(content, config, InsertAnchor::None)
+    configurable_render(content, config, InsertAnchor::None, &[])
 }
 
 pub fn render_with_insert_anchor(content: &str, insert_anchor: InsertAnchor) -> Result<Rendered> {
-    configurable_render(content, Config::default_for_test(), insert_anchor)
+    configurable_render(content, Config::default_for_test(), insert_anchor, &[])
+}
+
+/// Renders with the given extra templates, eg render hooks
+pub fn render_with_templates(
+    content: &str,
+    insert_anchor: InsertAnchor,
+    templates: &[(&str, &str)],
+) -> Result<Rendered> {
+    configurable_render(content, Config::default_for_test(), insert_anchor, templates)
+}
+
+/// Renders with the given config and extra templates
+pub fn render_with_config_and_templates(
+    content: &str,
+    config: Config,
+    templates: &[(&str, &str)],
+) -> Result<Rendered> {
+    configurable_render(content, config, InsertAnchor::None, templates)
 }
@@ -0,0 +1,81 @@
+mod common;
+use config::Config;
+
+#[test]
+fn default_extensions_are_enabled() {
+    let content = "| a | b |\n|---|---|\n| 1 | 2 |\n\n~~gone~~ and a note[^1]\n\n- [x] done\n\n[^1]: The note.";
+    let body = common::render(content).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_disable_extensions() {
+    let mut config = Config::default_for_test();
+    config.markdown.extensions.tables = false;
+    config.markdown.extensions.strikethrough = false;
+    config.markdown.extensions.heading_attributes = false;
+    let content = "# Title {#custom}\n\n| a | b |\n|---|---|\n\n~~kept~~";
+    let rendered = common::render_with_config(content, config).unwrap();
+    assert_eq!(rendered.toc[0].id, "title-custom");
+    insta::assert_snapshot!(rendered.body);
+}
+
+#[test]
+fn can_render_definition_lists() {
+    let mut config = Config::default_for_test();
+    config.markdown.extensions.definition_lists = true;
+    let content = r#"
+## Glossary
+
+Zola
+: A static site generator, see [about](@/pages/about.md).
+: Also a [cola](https://example.com).
+
+Tera
+: The template engine.
+"#;
+    let rendered = common::render_with_config(content, config).unwrap();
+    assert_eq!(rendered.toc[0].id, "glossary");
+    assert_eq!(rendered.internal_links, vec![("pages/about.md".to_owned(), None)]);
+    insta::assert_snapshot!(rendered.body);
+}
+
+#[test]
+fn can_render_superscript_and_subscript() {
+    let mut config = Config::default_for_test();
+    config.markdown.extensions.superscript = true;
+    config.markdown.extensions.subscript = true;
+    let content = "H~2~O, CO~2~ and E = mc^2^, a ^word^ and ~another~, but ~~not this~~, \\~this\\~ or ~this one~.";
+    let body = common::render_with_config(content, config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_render_attributes() {
+    let mut config = Config::default_for_test();
+    config.markdown.extensions.attributes = true;
+    config.markdown.extensions.definition_lists = true;
+    let content = r#"
+# Term
+
+A paragraph with a [link](#term) {#intro .lead data-x="some value"}
+
+- An item {.first}
+- A set {1, 2}
+
+Term {#term}
+: Its definition {.definition}
+
+Not {attributes}
+"#;
+    let rendered = common::render_with_config(content, config).unwrap();
+    // The heading doesn't get an id set with attributes
+    assert_eq!(rendered.toc[0].id, "term-1");
+    insta::assert_snapshot!(rendered.body);
+}
+
+#[test]
+fn attributes_are_disabled_by_default() {
+    let body = common::render("Some text {#id .class}").unwrap().body;
+    assert_eq!(body, "<p>Some text {#id .class}</p>\n");
+}
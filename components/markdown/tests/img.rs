@@ -31,3 +31,20 @@ fn can_add_lazy_loading_and_async_decoding() {
     let body = common::render_with_config(&cases.join("\n"), config).unwrap().body;
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn can_render_colocated_images_with_the_responsive_template() {
+    let cases = vec![
+        "![A *nice* `cat`](cat.jpg \"The cat\")",
+        "![remote](https://example.com/abc.jpg)",
+        "![svg](diagram.svg)",
+        "![nested](gallery/dog.PNG)",
+    ];
+
+    let mut config = Config::default_for_test();
+    config.markdown.images.responsive = true;
+    config.markdown.images.widths = vec![320, 640];
+
+    let body = common::render_with_config(&cases.join("\n\n"), config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
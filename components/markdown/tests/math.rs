@@ -0,0 +1,45 @@
+mod common;
+use config::{Config, MathRendering};
+
+fn config() -> Config {
+    let mut config = Config::default_for_test();
+    config.markdown.math = MathRendering::MathMl;
+    config
+}
+
+#[test]
+fn leaves_math_as_is_by_default() {
+    let body = common::render("Costs $5 and $x^2$").unwrap().body;
+    assert_eq!(body, "<p>Costs $5 and $x^2$</p>\n");
+}
+
+#[test]
+fn can_render_inline_math() {
+    let body =
+        common::render_with_config("The area is $\\pi r^2$, *roughly*, and $a < b$.", config())
+            .unwrap()
+            .body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_render_display_math() {
+    let content =
+        "Euler's identity:\n\n$$\ne^{i\\pi} + 1 = 0\n$$\n\nAnd a fraction: $\\frac{a}{b}$";
+    let body = common::render_with_config(content, config()).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn doesnt_render_math_in_code() {
+    let body = common::render_with_config("`$x$` and\n\n```\n$$y$$\n```", config()).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn reports_invalid_tex_with_its_location() {
+    let content = "Some text\n\nand then $x + \\frac{1}$ here";
+    let err = common::render_with_config(content, config()).unwrap_err();
+    let msg = format!("{}", err);
+    assert!(msg.starts_with("Invalid TeX math at line 3, column 10 of my_page.md"));
+}
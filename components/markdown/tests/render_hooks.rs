@@ -0,0 +1,83 @@
+mod common;
+use utils::types::InsertAnchor;
+
+#[test]
+fn can_render_links_with_a_hook() {
+    let template = r#"<a href="{{ url }}"{% if external %} rel="external"{% endif %}>{{ content | safe }}</a>"#;
+    let content = "[An *internal* link](@/pages/about.md) and [an external one](https://example.com \"Title\")";
+    let rendered = common::render_with_templates(
+        content,
+        InsertAnchor::None,
+        &[("markdown/link.html", template)],
+    )
+    .unwrap();
+    insta::assert_snapshot!(rendered.body);
+    // The links are still collected for the link checker
+    assert_eq!(rendered.internal_links, vec![("pages/about.md".to_string(), None)]);
+    assert_eq!(rendered.external_links, vec!["https://example.com".to_string()]);
+}
+
+#[test]
+fn can_render_images_with_a_hook() {
+    let template = r#"<figure data-src="{{ src }}" data-path="{{ path }}"><img src="{{ url }}" alt="{{ alt }}"><figcaption>{{ title }}</figcaption></figure>"#;
+    let content = "![A *nice* cat](cat.jpg \"The cat\")\n\n![remote](https://example.com/abc.jpg)";
+    let body = common::render_with_templates(
+        content,
+        InsertAnchor::None,
+        &[("markdown/image.html", template)],
+    )
+    .unwrap()
+    .body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_render_headings_with_a_hook() {
+    let template = r#"<h{{ level }} id="{{ id }}" class="{{ classes | join(sep=" ") }}" data-title="{{ title }}">{{ content | safe }}</h{{ level }}>"#;
+    let content = "# Hello *world*\n\n## Another {.big #custom}";
+    let rendered = common::render_with_templates(
+        content,
+        InsertAnchor::Left,
+        &[("markdown/heading.html", template)],
+    )
+    .unwrap();
+    insta::assert_snapshot!(rendered.body);
+    assert_eq!(rendered.toc.len(), 1);
+    assert_eq!(rendered.toc[0].children[0].id, "custom");
+}
+
+#[test]
+fn can_render_code_blocks_with_a_hook() {
+    let template = r#"<div class="code" data-lang="{{ language }}" data-code="{{ code }}">{{ content | safe }}</div>"#;
+    let content = "```rust\nlet a = 1;\n```\n\n```\nplain\n```";
+    let body = common::render_with_templates(
+        content,
+        InsertAnchor::None,
+        &[("markdown/codeblock.html", template)],
+    )
+    .unwrap()
+    .body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_render_nested_blockquotes_with_a_hook() {
+    let templates = [
+        ("markdown/blockquote.html", "<aside>{{ content | safe }}</aside>"),
+        ("markdown/link.html", "<a class=\"hooked\" href=\"{{ url }}\">{{ content | safe }}</a>"),
+    ];
+    let content = "> A quote with [a link](https://example.com)\n>\n> > And a nested one";
+    let body = common::render_with_templates(content, InsertAnchor::None, &templates).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn reports_errors_of_render_hooks() {
+    let res = common::render_with_templates(
+        "[link](https://example.com)",
+        InsertAnchor::None,
+        &[("markdown/link.html", "{{ nope }}")],
+    );
+    let err = format!("{:?}", res.unwrap_err());
+    assert!(err.contains("Failed to render the `markdown/link.html` render hook"));
+}
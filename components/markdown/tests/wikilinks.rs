@@ -0,0 +1,62 @@
+mod common;
+use config::{Config, LinkCheckerLevel};
+
+fn wikilinks_config() -> Config {
+    let mut config = Config::default_for_test();
+    config.markdown.extensions.wikilinks = true;
+    config
+}
+
+#[test]
+fn wikilinks_are_disabled_by_default() {
+    let body = common::render("See [[About us]]").unwrap().body;
+    assert_eq!(body, "<p>See [[About us]]</p>\n");
+}
+
+#[test]
+fn can_render_wikilinks() {
+    let content = r#"
+- By title: [[About us]]
+- By slug, with a label: [[about|our *team*]]
+- By path, to a heading: [[blog/setup#Getting Started]]
+- On the same page: [[#Some heading]]
+"#;
+    let rendered = common::render_with_config(content, wikilinks_config()).unwrap();
+    assert_eq!(
+        rendered.internal_links,
+        vec![
+            ("pages/about.md".to_owned(), None),
+            ("pages/about.md".to_owned(), None),
+            ("blog/setup.md".to_owned(), Some("getting-started".to_owned())),
+            ("my_page.md".to_owned(), Some("some-heading".to_owned())),
+        ]
+    );
+    insta::assert_snapshot!(rendered.body);
+}
+
+#[test]
+fn errors_on_missing_wikilinks() {
+    let res = common::render_with_config("[[Nowhere]]", wikilinks_config());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Broken wikilink `[[Nowhere]]` in my_page.md: no page or section has this title, slug or path"
+    );
+}
+
+#[test]
+fn errors_on_ambiguous_wikilinks() {
+    let res = common::render_with_config("[[Setup]]", wikilinks_config());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Broken wikilink `[[Setup]]` in my_page.md: it could be any of `blog/setup.md`, `docs/setup.md`"
+    );
+}
+
+#[test]
+fn can_only_warn_about_broken_wikilinks() {
+    let mut config = wikilinks_config();
+    config.link_checker.internal_level = LinkCheckerLevel::Warn;
+    let rendered = common::render_with_config("[[Nowhere]]", config).unwrap();
+    assert_eq!(rendered.body, "<p><a href=\"Nowhere\">Nowhere</a></p>\n");
+    assert!(rendered.internal_links.is_empty());
+}
@@ -0,0 +1,396 @@
+//! On-disk cache used by `zola build` to avoid re-rendering and re-writing outputs
+//! whose inputs did not change since the previous build.
+//!
+//! The cache records:
+//!
+//! - a global hash of the inputs every output depends on: config, templates and data files
+//! - the hash of the render inputs of every page
+//! - the hash of every file written in the output directory
+//!
+//! On an incremental build, pages whose inputs hash is unchanged are not rendered at all unless
+//! the global hash changed, and any output whose content is identical to the previous build is not written again.
+//! Outputs of the previous build that were not produced anymore are deleted.
+use std::collections::HashMap;
+use std::fs::{read_dir, remove_dir, remove_file};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use libs::globset::GlobSet;
+use libs::serde_json::{self, Value};
+use libs::sha2::{Digest, Sha256};
+use libs::walkdir::WalkDir;
+use serde::{Deserialize, Serialize};
+
+use errors::{Context, Result};
+use utils::fs::{create_file, is_dotfile};
+
+/// The default directory, relative to the site root, where the cache is stored
+pub const CACHE_DIR: &str = ".zola-cache";
+const MANIFEST_FILENAME: &str = "build.json";
+/// Bump whenever the manifest format or the way hashes are computed changes
+const MANIFEST_VERSION: u32 = 1;
+
+/// Extensions of the files that can be loaded with `load_data`
+const DATA_EXTENSIONS: [&str; 8] = ["toml", "json", "csv", "bib", "bibtex", "xml", "yaml", "yml"];
+
+/// Template functions that give a template access to content other than the one being rendered.
+/// If any template uses them, every page depends on the whole content folder.
+const CONTENT_LOOKUP_FNS: [&str; 4] =
+    ["get_page(", "get_section(", "get_taxonomy(", "get_taxonomy_term("];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    output_path: PathBuf,
+    global: String,
+    /// Hash of the inputs of each page, keyed by the relative path of its markdown file
+    pages: HashMap<String, String>,
+    /// Hash of each file in the output directory, keyed by its path relative to it.
+    /// Files copied rather than rendered (static files, assets) have an empty hash.
+    outputs: HashMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    current: Manifest,
+    files_written: usize,
+}
+
+#[derive(Debug)]
+pub struct BuildCache {
+    path: PathBuf,
+    /// The manifest of the previous build, only set on incremental builds
+    previous: Option<Manifest>,
+    state: Mutex<CacheState>,
+}
+
+impl BuildCache {
+    /// Creates a cache stored in the given directory.
+    /// The previous manifest is only read if `incremental` is set.
+    pub fn new(path: PathBuf, incremental: bool) -> Self {
+        let previous = if incremental { read_manifest(&path) } else { None };
+        BuildCache { path, previous, state: Mutex::new(CacheState::default()) }
+    }
+
+    /// Resets the state for a new build and returns whether the previous build can be
+    /// reused. If it can't, the caller needs to clean the output directory.
+    pub fn start(&self, output_path: &Path, global: String) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.current = Manifest {
+            version: MANIFEST_VERSION,
+            output_path: output_path.to_path_buf(),
+            global,
+            ..Default::default()
+        };
+        state.files_written = 0;
+
+        match self.previous {
+            Some(ref p) => p.output_path == output_path,
+            None => false,
+        }
+    }
+
+    /// How many files were written in the output directory during the current build
+    pub fn files_written(&self) -> usize {
+        self.state.lock().unwrap().files_written
+    }
+
+    /// Records the inputs hash of a page and returns whether it is identical to the one of the
+    /// previous build, along with the global inputs hash, and all the given outputs from that
+    /// build are still there.
+    /// When that's the case, the outputs are marked as produced by the current build.
+    pub fn is_page_fresh(
+        &self,
+        relative_path: &str,
+        inputs_hash: String,
+        output_path: &Path,
+        outputs: &[String],
+    ) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let fresh = match self.previous {
+            Some(ref p) => {
+                p.global == state.current.global
+                    && p.pages.get(relative_path) == Some(&inputs_hash)
+                    && outputs
+                        .iter()
+                        .all(|o| p.outputs.contains_key(o) && output_path.join(o).exists())
+            }
+            None => false,
+        };
+
+        state.current.pages.insert(relative_path.to_string(), inputs_hash);
+        if fresh {
+            let previous = self.previous.as_ref().unwrap();
+            for o in outputs {
+                state.current.outputs.insert(o.clone(), previous.outputs[o].clone());
+            }
+        }
+        fresh
+    }
+
+    /// Writes the file at `relative_path` in the output directory unless it already has the
+    /// exact same content, either from the previous build or from earlier in the current one.
+    pub fn write_file(&self, output_path: &Path, relative_path: &str, content: &str) -> Result<()> {
+        let hash = hash_bytes(content.as_bytes());
+        let full_path = output_path.join(relative_path);
+
+        let unchanged = {
+            let mut state = self.state.lock().unwrap();
+            // Some outputs are written several times in a single build, eg aliases that
+            // are overwritten by actual content: what's on disk is then the last write
+            let on_disk = match state.current.outputs.get(relative_path) {
+                Some(h) => Some(h),
+                None => self.previous.as_ref().and_then(|p| p.outputs.get(relative_path)),
+            };
+            let unchanged = on_disk == Some(&hash) && full_path.exists();
+            if !unchanged {
+                state.files_written += 1;
+            }
+            state.current.outputs.insert(relative_path.to_string(), hash);
+            unchanged
+        };
+
+        if !unchanged {
+            create_file(&full_path, content)?;
+        }
+        Ok(())
+    }
+
+    /// Records files copied as-is from `src` into the output directory
+    pub fn record_copied_directory(&self, src: &Path, ignore_globset: Option<&GlobSet>) {
+        let mut state = self.state.lock().unwrap();
+        for entry in WalkDir::new(src).follow_links(true).into_iter().filter_map(|e| e.ok()) {
+            if entry.path().is_dir() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(src).unwrap();
+            if let Some(gs) = ignore_globset {
+                if gs.is_match(relative) {
+                    continue;
+                }
+            }
+            if imageproc::is_processor_file(relative) {
+                continue;
+            }
+            state.current.outputs.entry(to_slash(relative)).or_default();
+        }
+    }
+
+    /// Records a single file copied into the output directory
+    pub fn record_copied_file(&self, relative_path: &Path) {
+        let mut state = self.state.lock().unwrap();
+        state.current.outputs.entry(to_slash(relative_path)).or_default();
+    }
+
+    /// Deletes the outputs of the previous build that were not produced by the current one
+    /// and saves the manifest for the next build.
+    pub fn finish(&self, output_path: &Path) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        if let Some(ref previous) = self.previous {
+            for stale in previous.outputs.keys().filter(|k| !state.current.outputs.contains_key(*k))
+            {
+                let path = output_path.join(stale);
+                if path.is_file() {
+                    remove_file(&path).with_context(|| {
+                        format!("Failed to remove stale output {}", path.display())
+                    })?;
+                }
+                remove_empty_parents(&path, output_path);
+            }
+        }
+
+        let manifest =
+            serde_json::to_string(&state.current).context("Failed to serialize the build cache")?;
+        create_file(&self.path.join(MANIFEST_FILENAME), manifest)
+    }
+}
+
+fn read_manifest(path: &Path) -> Option<Manifest> {
+    let content = std::fs::read_to_string(path.join(MANIFEST_FILENAME)).ok()?;
+    let manifest: Manifest = serde_json::from_str(&content).ok()?;
+    if manifest.version != MANIFEST_VERSION {
+        return None;
+    }
+    Some(manifest)
+}
+
+/// Removes the now empty directories left behind after deleting a stale output
+fn remove_empty_parents(path: &Path, output_path: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == output_path || !dir.starts_with(output_path) {
+            break;
+        }
+        let is_empty = read_dir(dir).map(|mut entries| entries.next().is_none()).unwrap_or(false);
+        if !is_empty || remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+fn to_slash(path: &Path) -> String {
+    path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+pub fn hash_bytes(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Hashes a serializable value in a deterministic way: object keys are sorted since
+/// some of the values we hash come from `HashMap`s
+pub fn hash_serializable<T: Serialize>(value: &T) -> String {
+    fn update(hasher: &mut Sha256, value: &Value) {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<_> = map.keys().collect();
+                keys.sort();
+                hasher.update(b"{");
+                for k in keys {
+                    hasher.update(k.as_bytes());
+                    hasher.update(b":");
+                    update(hasher, &map[k]);
+                }
+                hasher.update(b"}");
+            }
+            Value::Array(items) => {
+                hasher.update(b"[");
+                for item in items {
+                    update(hasher, item);
+                    hasher.update(b",");
+                }
+                hasher.update(b"]");
+            }
+            _ => hasher.update(value.to_string().as_bytes()),
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    update(&mut hasher, &serde_json::to_value(value).unwrap_or(Value::Null));
+    format!("{:x}", hasher.finalize())
+}
+
+/// Computes the hash of every input that all outputs depend on: the config files, the templates,
+/// and the data files that could be loaded with `load_data` from the content, static and data
+/// folders.
+/// If a template can fetch arbitrary content, all the markdown files are included as well.
+pub fn hash_global_inputs(
+    base_path: &Path,
+    config_file: &Path,
+    extra: &[&str],
+    ignored: &[&Path],
+) -> Result<String> {
+    let mut files = vec![config_file.to_path_buf()];
+    let mut templates = Vec::new();
+
+    for (dir, is_template_dir) in [
+        ("templates", true),
+        ("themes", false),
+        ("content", false),
+        ("static", false),
+        ("data", false),
+    ] {
+        let walker = WalkDir::new(base_path.join(dir))
+            .follow_links(true)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !is_dotfile(e.path()) && !ignored.iter().any(|i| e.path() == *i));
+        for entry in walker.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                continue;
+            }
+            let in_templates =
+                is_template_dir || path.components().any(|c| c.as_os_str() == "templates");
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            if in_templates {
+                templates.push(path.to_path_buf());
+            } else if DATA_EXTENSIONS.contains(&extension) {
+                files.push(path.to_path_buf());
+            }
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    for e in extra {
+        hasher.update(e.as_bytes());
+    }
+
+    let mut uses_content_lookups = false;
+    for path in &templates {
+        let content = std::fs::read(path)
+            .with_context(|| format!("Failed to read template {}", path.display()))?;
+        if !uses_content_lookups {
+            let text = String::from_utf8_lossy(&content);
+            uses_content_lookups = CONTENT_LOOKUP_FNS.iter().any(|f| text.contains(f));
+        }
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update(&content);
+    }
+
+    if uses_content_lookups {
+        for entry in WalkDir::new(base_path.join("content"))
+            .follow_links(true)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if entry.path().extension().map(|e| e == "md").unwrap_or(false) {
+                files.push(entry.path().to_path_buf());
+            }
+        }
+    }
+
+    for path in &files {
+        if let Ok(content) = std::fs::read(path) {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update(&content);
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn hash_serializable_is_independent_of_map_order() {
+        let mut a = HashMap::new();
+        let mut b = HashMap::new();
+        for i in 0..20 {
+            a.insert(format!("key{}", i), i);
+        }
+        for i in (0..20).rev() {
+            b.insert(format!("key{}", i), i);
+        }
+        assert_eq!(hash_serializable(&a), hash_serializable(&b));
+    }
+
+    #[test]
+    fn write_file_skips_identical_content() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache_path = tmp_dir.path().join("cache");
+        let output_path = tmp_dir.path().join("public");
+
+        let cache = BuildCache::new(cache_path.clone(), true);
+        cache.start(&output_path, String::new());
+        cache.write_file(&output_path, "a/index.html", "hello").unwrap();
+        cache.write_file(&output_path, "b/index.html", "world").unwrap();
+        assert_eq!(cache.files_written(), 2);
+        cache.finish(&output_path).unwrap();
+
+        let cache = BuildCache::new(cache_path, true);
+        assert!(cache.start(&output_path, String::new()));
+        cache.write_file(&output_path, "a/index.html", "hello").unwrap();
+        assert_eq!(cache.files_written(), 0);
+        cache.finish(&output_path).unwrap();
+        assert!(output_path.join("a").join("index.html").exists());
+        // Stale outputs are removed along with their now empty folder
+        assert!(!output_path.join("b").exists());
+    }
+}
@@ -1,6 +1,8 @@
+pub mod cache;
 pub mod feeds;
 pub mod link_checking;
 mod minify;
+pub mod rebuild;
 pub mod sass;
 pub mod sitemap;
 pub mod tpls;
@@ -16,16 +18,20 @@ use libs::tera::{Context, Tera};
 use libs::walkdir::{DirEntry, WalkDir};
 
 use config::{get_config, Config, IndexFormat};
-use content::{Library, Page, Paginator, Section, Taxonomy};
+use content::{is_terms_directory, Library, Page, Paginator, Section, Taxonomy};
 use errors::{anyhow, bail, Result};
 use libs::relative_path::RelativePathBuf;
+use rebuild::{DependencyGraph, Output};
 use std::time::Instant;
-use templates::{load_tera, render_redirect_template};
+use templates::{load_tera, reload_tera, render_redirect_template};
+use utils::dependencies;
 use utils::fs::{
-    clean_site_output_folder, copy_directory, copy_file_if_needed, create_directory, create_file,
+    clean_site_output_folder, copy_directory_except, copy_file, copy_file_if_needed,
+    create_directory, create_file, is_dotfile,
 };
 use utils::net::{get_available_port, is_external_link};
-use utils::templates::{render_template, ShortcodeDefinition};
+use utils::site::WikilinkIndex;
+use utils::templates::{find_dependent_templates, render_template, ShortcodeDefinition};
 use utils::types::InsertAnchor;
 
 pub static SITE_CONTENT: Lazy<Arc<RwLock<HashMap<RelativePathBuf, String>>>> =
@@ -40,6 +46,17 @@ pub enum BuildMode {
     Memory,
 }
 
+/// Why a page was not loaded
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SkipReason {
+    /// It is a draft and drafts are not included
+    Draft,
+    /// Its `publish_date` is in the future and `--future` is not set
+    Scheduled,
+    /// Its `expiry_date` is in the past and `--expired` is not set
+    Expired,
+}
+
 #[derive(Debug)]
 pub struct Site {
     /// The base path of the zola site
@@ -48,6 +65,8 @@ pub struct Site {
     pub config: Config,
     pub tera: Tera,
     imageproc: Arc<Mutex<imageproc::Processor>>,
+    /// Only set if the config has a `[static_images]` section
+    static_images: Option<imageproc::StaticImageOptimizer>,
     // the live reload port to be used if there is one
     pub live_reload: Option<u16>,
     pub output_path: PathBuf,
@@ -59,21 +78,48 @@ pub struct Site {
     /// A map of all .md files (section and pages) and their permalink
     /// We need that if there are relative links in the content that need to be resolved
     pub permalinks: HashMap<String, String>,
+    /// The pages and sections by their title, slug and path, to resolve `[[wikilinks]]`
+    pub wikilinks: WikilinkIndex,
     /// Contains all pages and sections of the site
     pub library: Arc<RwLock<Library>>,
     /// Whether to load draft pages
     include_drafts: bool,
+    /// Whether to load pages with a `publish_date` in the future
+    include_future: bool,
+    /// Whether to load pages with an `expiry_date` in the past
+    include_expired: bool,
+    /// The pages that were not loaded and why
+    pub skipped_pages: Vec<(PathBuf, SkipReason)>,
+    /// The files of the terms directories that don't describe any term, eg because of a typo
+    /// in their name, and are therefore not loaded
+    pub unknown_term_files: Vec<PathBuf>,
     build_mode: BuildMode,
     shortcode_definitions: HashMap<String, ShortcodeDefinition>,
+    /// The path to the config file, used to know whether it changed for the build cache
+    config_file: PathBuf,
+    /// The on-disk build cache, only used by `zola build`
+    build_cache: Option<cache::BuildCache>,
+    /// What each output used while being rendered, only filled in `zola serve`
+    dependencies: DependencyGraph,
 }
 
 impl Site {
     /// Parse a site at the given path. Defaults to the current dir
     /// Passing in a path is used in tests and when --root argument is passed
     pub fn new<P: AsRef<Path>, P2: AsRef<Path>>(path: P, config_file: P2) -> Result<Site> {
+        Site::new_with_env(path, config_file, None)
+    }
+
+    /// Same as `Site::new` but merges the config of the given environment, eg `production`,
+    /// over the config file
+    pub fn new_with_env<P: AsRef<Path>, P2: AsRef<Path>>(
+        path: P,
+        config_file: P2,
+        env: Option<&str>,
+    ) -> Result<Site> {
         let path = path.as_ref();
         let config_file = config_file.as_ref();
-        let mut config = get_config(&path.join(config_file))?;
+        let mut config = get_config(&path.join(config_file), env)?;
 
         if let Some(theme) = config.theme.clone() {
             // Grab data from the extra section of the theme
@@ -88,6 +134,10 @@ impl Site {
         let static_path = path.join("static");
         let templates_path = path.join("templates");
         let imageproc = imageproc::Processor::new(path.to_path_buf(), &config);
+        let static_images = imageproc::StaticImageOptimizer::new(
+            &config,
+            path.join(cache::CACHE_DIR).join("static_images"),
+        );
         let output_path = path.join(config.output_dir.clone());
 
         let site = Site {
@@ -95,6 +145,7 @@ impl Site {
             config,
             tera,
             imageproc: Arc::new(Mutex::new(imageproc)),
+            static_images,
             live_reload: None,
             output_path,
             content_path,
@@ -103,11 +154,19 @@ impl Site {
             templates_path,
             taxonomies: Vec::new(),
             permalinks: HashMap::new(),
+            wikilinks: WikilinkIndex::default(),
             include_drafts: false,
+            include_future: false,
+            include_expired: false,
+            skipped_pages: Vec::new(),
+            unknown_term_files: Vec::new(),
             // We will allocate it properly later on
             library: Arc::new(RwLock::new(Library::default())),
             build_mode: BuildMode::Disk,
             shortcode_definitions,
+            config_file: path.join(config_file),
+            build_cache: None,
+            dependencies: DependencyGraph::default(),
         };
 
         Ok(site)
@@ -126,6 +185,42 @@ impl Site {
         self.include_drafts = true;
     }
 
+    /// Set the site to load the pages with a `publish_date` in the future.
+    /// Needs to be called before loading it
+    pub fn include_future(&mut self) {
+        self.include_future = true;
+    }
+
+    /// Set the site to load the pages with an `expiry_date` in the past.
+    /// Needs to be called before loading it
+    pub fn include_expired(&mut self) {
+        self.include_expired = true;
+    }
+
+    /// Why the page should not be loaded, if it should not
+    fn skip_reason(&self, page: &Page) -> Option<SkipReason> {
+        if page.meta.draft && !self.include_drafts {
+            Some(SkipReason::Draft)
+        } else if page.meta.is_scheduled() && !self.include_future {
+            Some(SkipReason::Scheduled)
+        } else if page.meta.is_expired() && !self.include_expired {
+            Some(SkipReason::Expired)
+        } else {
+            None
+        }
+    }
+
+    /// Enable the on-disk build cache, stored in the given directory.
+    /// If `incremental` is set, the cache of the previous build is used to skip rendering and
+    /// writing outputs that did not change. Otherwise the cache is only written.
+    pub fn enable_build_cache<P: AsRef<Path>>(&mut self, path: P, incremental: bool) {
+        self.build_cache = Some(cache::BuildCache::new(path.as_ref().to_path_buf(), incremental));
+    }
+
+    pub fn build_cache(&self) -> Option<&cache::BuildCache> {
+        self.build_cache.as_ref()
+    }
+
     /// The index sections are ALWAYS at those paths
     /// There are one index section for the default language + 1 per language
     fn index_section_paths(&self) -> Vec<(PathBuf, Option<&str>)> {
@@ -148,11 +243,259 @@ impl Site {
         self.live_reload = Some(live_reload_port);
     }
 
-    /// Reloads the templates and rebuild the site without re-markdown the Markdown.
-    pub fn reload_templates(&mut self) -> Result<()> {
-        self.tera.full_reload()?;
-        // TODO: be smarter than that, no need to recompile sass for example
-        self.build()
+    /// Reloads the templates and only re-renders the outputs that used one of the templates at
+    /// the given paths, directly or through inheritance, includes and macro imports.
+    /// Returns the outputs re-rendered or `None` if templates were added or removed, in which case
+    /// the site needs to be rebuilt from scratch.
+    pub fn rebuild_after_template_changes(
+        &mut self,
+        paths: &[&Path],
+    ) -> Result<Option<Vec<String>>> {
+        let previous_names: HashSet<String> = self.tera.templates.keys().cloned().collect();
+        reload_tera(&mut self.tera, &self.base_path)?;
+        if self.tera.templates.len() != previous_names.len()
+            || self.tera.templates.keys().any(|name| !previous_names.contains(name))
+        {
+            return Ok(None);
+        }
+
+        let changed_paths: HashSet<PathBuf> = paths.iter().map(canonicalize).collect();
+        let changed: HashSet<String> = self
+            .tera
+            .templates
+            .iter()
+            .filter(|(_, tpl)| {
+                tpl.path.as_ref().is_some_and(|p| changed_paths.contains(&canonicalize(p)))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        let affected = find_dependent_templates(&self.tera, &changed);
+
+        self.shortcode_definitions = utils::templates::get_shortcodes(&self.tera);
+        // The markdown filter holds a copy of the templates
+        tpls::register_early_global_fns(self)?;
+        let outputs = self.dependencies.outputs_using_templates(&affected);
+        self.rebuild_outputs(outputs).map(Some)
+    }
+
+    /// Reloads the content file at the given path and only re-renders the outputs using it.
+    /// Returns the outputs re-rendered or `None` if the change requires rebuilding the site
+    /// from scratch, eg a page changing path or a new file.
+    pub fn rebuild_after_content_change(&mut self, path: &Path) -> Result<Option<Vec<String>>> {
+        if path.extension().map_or(true, |ext| ext != "md") {
+            // Data files loaded with `load_data`, anything else could be an asset
+            let outputs = self.dependencies.outputs_using_file(path);
+            if outputs.is_empty() {
+                return Ok(None);
+            }
+            return self.rebuild_outputs(outputs).map(Some);
+        }
+
+        let is_section =
+            path.file_name().is_some_and(|f| f.to_string_lossy().starts_with("_index."));
+        // What used the previous version, eg its neighbours if the page moved in its section
+        let mut outputs = HashSet::new();
+        self.dependencies.forget(&Output::Markdown(path.to_path_buf()));
+        let wikilinks = self.config.markdown.extensions.wikilinks;
+        if is_section {
+            let section = Section::from_file(path, &self.config, &self.base_path)?;
+            match self.library.read().unwrap().sections.get(path) {
+                Some(previous) if has_same_structure_as_section(previous, &section, wikilinks) => {}
+                _ => return Ok(None),
+            }
+            self.add_section(section, true)?;
+            self.populate_sections();
+        } else {
+            let mut page = Page::from_file(path, &self.config, &self.base_path)?;
+            self.apply_permalink_pattern(&mut page)?;
+            let library = self.library.read().unwrap();
+            match library.pages.get(path) {
+                Some(previous) if has_same_structure_as_page(previous, &page, wikilinks) => {
+                    outputs.extend(
+                        previous.lower.iter().chain(&previous.higher).cloned().map(Output::Page),
+                    );
+                    outputs.extend(self.linked_content(&library, &previous.internal_links));
+                }
+                _ => return Ok(None),
+            }
+            drop(library);
+            self.add_page(page, true)?;
+            self.populate_sections();
+            self.populate_taxonomies()?;
+            self.render_terms_markdown()?;
+        }
+        self.library.write().unwrap().fill_backlinks();
+        tpls::register_early_global_fns(self)?;
+        tpls::register_tera_global_fns(self);
+
+        outputs.extend(self.content_dependents(path));
+        self.rebuild_outputs(outputs).map(Some)
+    }
+
+    /// Runs the given render, recording what it used for the given output in `zola serve`
+    fn track<T>(&self, output: Output, render: impl FnOnce() -> Result<T>) -> Result<T> {
+        if self.build_mode != BuildMode::Memory {
+            return render();
+        }
+        let (res, deps) = dependencies::track(render);
+        self.dependencies.record(output, deps);
+        res
+    }
+
+    /// The pages and sections targeted by the given internal links, as they show backlinks
+    fn linked_content(
+        &self,
+        library: &Library,
+        internal_links: &[(String, Option<String>)],
+    ) -> HashSet<Output> {
+        internal_links
+            .iter()
+            .filter_map(|(link, _)| {
+                let path = self.content_path.join(link);
+                if library.pages.contains_key(&path) {
+                    Some(Output::Page(path))
+                } else if library.sections.contains_key(&path) {
+                    Some(Output::Section(path))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Every output showing something from the page or section at the given path
+    fn content_dependents(&self, path: &Path) -> HashSet<Output> {
+        let library = self.library.read().unwrap();
+        let mut outputs = HashSet::new();
+        let (ancestors, internal_links) = if let Some(page) = library.pages.get(path) {
+            outputs.insert(Output::Page(path.to_path_buf()));
+            outputs.extend(page.lower.iter().chain(&page.higher).cloned().map(Output::Page));
+            let taxonomies: HashSet<String> =
+                page.meta.taxonomies.keys().map(|name| format!("{}-{}", name, page.lang)).collect();
+            outputs.extend(taxonomies.iter().cloned().map(Output::Taxonomy));
+            outputs.extend(self.dependencies.outputs_using_taxonomies(&taxonomies));
+            (&page.ancestors, &page.internal_links)
+        } else if let Some(section) = library.sections.get(path) {
+            outputs.insert(Output::Section(path.to_path_buf()));
+            (&section.ancestors, &section.internal_links)
+        } else {
+            return outputs;
+        };
+
+        // Sections list their pages and subsections
+        let mut paths = HashSet::from([path.to_path_buf()]);
+        for ancestor in ancestors {
+            let ancestor_path = self.content_path.join(ancestor);
+            outputs.insert(Output::Section(ancestor_path.clone()));
+            paths.insert(ancestor_path);
+        }
+        outputs.extend(self.linked_content(&library, internal_links));
+        outputs.extend(self.dependencies.outputs_using_content(&paths));
+        outputs
+    }
+
+    /// Renders the markdown of the page or section at the given path again
+    fn rerender_markdown(&self, path: &Path) -> Result<()> {
+        let output = Output::Markdown(path.to_path_buf());
+        self.dependencies.forget(&output);
+        let parent = self
+            .library
+            .read()
+            .unwrap()
+            .pages
+            .get(path)
+            .map(|p| (p.file.parent.clone(), p.lang.clone()));
+        let insert_anchor = match parent {
+            Some((parent, lang)) => self.find_parent_section_insert_anchor(&parent, &lang),
+            None => InsertAnchor::None,
+        };
+
+        let mut library = self.library.write().expect("Get lock for rerender_markdown");
+        if let Some(page) = library.pages.get_mut(path) {
+            self.track(output, || {
+                page.render_markdown(
+                    &self.permalinks,
+                    &self.wikilinks,
+                    &self.tera,
+                    &self.config,
+                    insert_anchor,
+                    &self.shortcode_definitions,
+                )
+            })?;
+        } else if let Some(section) = library.sections.get_mut(path) {
+            self.track(output, || {
+                section.render_markdown(
+                    &self.permalinks,
+                    &self.wikilinks,
+                    &self.tera,
+                    &self.config,
+                    &self.shortcode_definitions,
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Renders the given outputs again along with the site-wide ones, like the sitemap.
+    /// Returns the path of the pages, sections and taxonomies rendered.
+    fn rebuild_outputs(&mut self, mut outputs: HashSet<Output>) -> Result<Vec<String>> {
+        // Markdown first since pages and sections show it
+        let markdown: Vec<PathBuf> = outputs
+            .iter()
+            .filter_map(|o| match o {
+                Output::Markdown(p) => Some(p.clone()),
+                _ => None,
+            })
+            .collect();
+        for path in &markdown {
+            outputs.remove(&Output::Markdown(path.clone()));
+            self.rerender_markdown(path)?;
+            outputs.extend(self.content_dependents(path));
+        }
+        if !markdown.is_empty() {
+            self.library.write().unwrap().fill_backlinks();
+        }
+
+        let library = self.library.read().unwrap();
+        let mut rendered = Vec::new();
+        for output in &outputs {
+            self.dependencies.forget(output);
+            match output {
+                Output::Page(path) if library.pages.contains_key(path) => {
+                    let page = &library.pages[path];
+                    self.render_page(page)?;
+                    rendered.push(page.path.clone());
+                }
+                Output::Section(path) if library.sections.contains_key(path) => {
+                    let section = &library.sections[path];
+                    self.render_section(section, false)?;
+                    rendered.push(section.path.clone());
+                }
+                Output::Taxonomy(key) => {
+                    let taxonomy = self
+                        .taxonomies
+                        .iter()
+                        .find(|t| t.kind.render && &format!("{}-{}", t.kind.name, t.lang) == key);
+                    if let Some(taxonomy) = taxonomy {
+                        self.render_taxonomy(taxonomy)?;
+                        rendered.push(taxonomy.path.clone());
+                    }
+                }
+                // Markdown was handled above, the rest was removed
+                _ => (),
+            }
+        }
+        drop(library);
+
+        self.render_site_wide_outputs()?;
+        // Only the outputs rendered again enqueued their images, the others still use theirs
+        self.imageproc
+            .lock()
+            .expect("Couldn't lock imageproc (set_partial_build)")
+            .set_partial_build(true);
+        self.process_images()?;
+        rendered.sort();
+        Ok(rendered)
     }
 
     pub fn set_base_url(&mut self, base_url: String) {
@@ -169,6 +512,8 @@ impl Site {
     /// out of them
     pub fn load(&mut self) -> Result<()> {
         self.library = Arc::new(RwLock::new(Library::new(&self.config)));
+        self.dependencies.clear();
+        self.skipped_pages.clear();
         let mut pages_insert_anchors = HashMap::new();
 
         // not the most elegant loop, but this is necessary to use skip_current_dir
@@ -183,6 +528,7 @@ impl Site {
             .map(|code| format!("_index.{}.md", code))
             .collect();
         allowed_index_filenames.push("_index.md".to_string());
+        let taxonomy_dirs = self.taxonomy_dirs();
 
         // We will insert colocated pages (those with a index.md filename)
         // at the end to detect pages that are actually errors:
@@ -225,6 +571,11 @@ impl Site {
                 continue;
             }
 
+            if path.is_dir() && taxonomy_dirs.contains(path) && is_terms_directory(path) {
+                dir_walker.skip_current_dir();
+                continue;
+            }
+
             // is it a section or not?
             if path.is_dir() {
                 // if we are processing a section we have to collect
@@ -271,8 +622,9 @@ impl Site {
         self.create_default_index_sections()?;
 
         for page in pages {
-            // should we skip drafts?
-            if page.meta.draft && !self.include_drafts {
+            // should we skip drafts, scheduled or expired pages?
+            if let Some(reason) = self.skip_reason(&page) {
+                self.skipped_pages.push((page.file.path.clone(), reason));
                 continue;
             }
 
@@ -420,6 +772,7 @@ impl Site {
         // Another silly thing needed to not borrow &self in parallel and
         // make the borrow checker happy
         let permalinks = &self.permalinks;
+        let wikilinks = &self.wikilinks;
         let tera = &self.tera;
         let config = &self.config;
 
@@ -440,13 +793,16 @@ impl Site {
             .par_iter_mut()
             .map(|page| {
                 let insert_anchor = pages_insert_anchors[&page.file.path];
-                page.render_markdown(
-                    permalinks,
-                    tera,
-                    config,
-                    insert_anchor,
-                    &self.shortcode_definitions,
-                )
+                self.track(Output::Markdown(page.file.path.clone()), || {
+                    page.render_markdown(
+                        permalinks,
+                        wikilinks,
+                        tera,
+                        config,
+                        insert_anchor,
+                        &self.shortcode_definitions,
+                    )
+                })
             })
             .collect::<Result<()>>()?;
 
@@ -456,10 +812,36 @@ impl Site {
             .collect::<Vec<_>>()
             .par_iter_mut()
             .map(|section| {
-                section.render_markdown(permalinks, tera, config, &self.shortcode_definitions)
+                self.track(Output::Markdown(section.file.path.clone()), || {
+                    section.render_markdown(
+                        permalinks,
+                        wikilinks,
+                        tera,
+                        config,
+                        &self.shortcode_definitions,
+                    )
+                })
             })
             .collect::<Result<()>>()?;
+        drop(library);
+
+        self.render_terms_markdown()
+    }
 
+    /// Renders the content of the files describing taxonomy terms
+    fn render_terms_markdown(&mut self) -> Result<()> {
+        for taxonomy in self.taxonomies.iter_mut() {
+            for item in taxonomy.items.iter_mut() {
+                item.render_markdown(
+                    &taxonomy.lang,
+                    &self.permalinks,
+                    &self.wikilinks,
+                    &self.tera,
+                    &self.config,
+                    &self.shortcode_definitions,
+                )?;
+            }
+        }
         Ok(())
     }
 
@@ -476,17 +858,28 @@ impl Site {
             }
         }
 
+        self.apply_permalink_pattern(&mut page)?;
         self.permalinks.insert(page.file.relative.clone(), page.permalink.clone());
+        let path = match page.file.colocated_path {
+            Some(ref colocated_path) => colocated_path.trim_end_matches('/').to_owned(),
+            None if page.file.components.is_empty() => page.file.name.clone(),
+            None => format!("{}/{}", page.file.components.join("/"), page.file.name),
+        };
+        let title = page.meta.title.as_deref().unwrap_or_default();
+        self.wikilinks.insert(&page.file.relative, &page.lang, &path, &[title, &page.slug]);
         if render_md {
             let insert_anchor =
                 self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
-            page.render_markdown(
-                &self.permalinks,
-                &self.tera,
-                &self.config,
-                insert_anchor,
-                &self.shortcode_definitions,
-            )?;
+            self.track(Output::Markdown(page.file.path.clone()), || {
+                page.render_markdown(
+                    &self.permalinks,
+                    &self.wikilinks,
+                    &self.tera,
+                    &self.config,
+                    insert_anchor,
+                    &self.shortcode_definitions,
+                )
+            })?;
         }
 
         let mut library = self.library.write().expect("Get lock for add_page");
@@ -503,6 +896,7 @@ impl Site {
         self.add_page(page, true)?;
         self.populate_sections();
         self.populate_taxonomies()?;
+        self.render_terms_markdown()?;
         let library = self.library.read().unwrap();
         let page = library.pages.get(path).unwrap();
         self.render_page(page)
@@ -512,13 +906,23 @@ impl Site {
     /// The `render` parameter is used in the serve command with --fast, when rebuilding a page.
     pub fn add_section(&mut self, mut section: Section, render_md: bool) -> Result<()> {
         self.permalinks.insert(section.file.relative.clone(), section.permalink.clone());
+        let title = section.meta.title.as_deref().unwrap_or_default();
+        self.wikilinks.insert(
+            &section.file.relative,
+            &section.lang,
+            &section.file.components.join("/"),
+            &[title],
+        );
         if render_md {
-            section.render_markdown(
-                &self.permalinks,
-                &self.tera,
-                &self.config,
-                &self.shortcode_definitions,
-            )?;
+            self.track(Output::Markdown(section.file.path.clone()), || {
+                section.render_markdown(
+                    &self.permalinks,
+                    &self.wikilinks,
+                    &self.tera,
+                    &self.config,
+                    &self.shortcode_definitions,
+                )
+            })?;
         }
         let mut library = self.library.write().expect("Get lock for add_section");
         library.sections.remove(&section.file.path);
@@ -556,6 +960,36 @@ impl Site {
         }
     }
 
+    /// Sets the path of the page from the permalink pattern of its section, set either in the
+    /// section front matter or in the config
+    fn apply_permalink_pattern(&self, page: &mut Page) -> Result<()> {
+        let index = if page.lang != self.config.default_language {
+            format!("_index.{}.md", page.lang)
+        } else {
+            "_index.md".to_string()
+        };
+        let section_pattern = self
+            .library
+            .read()
+            .unwrap()
+            .sections
+            .get(&page.file.parent.join(index))
+            .and_then(|s| s.meta.permalink.clone());
+        let section_path = page.file.components.join("/");
+        let pattern = section_pattern.or_else(|| {
+            self.config
+                .permalinks
+                .iter()
+                .find(|(path, _)| path.trim_matches('/') == section_path)
+                .map(|(_, pattern)| pattern.clone())
+        });
+
+        match pattern {
+            Some(pattern) => page.apply_permalink_pattern(&pattern, &self.config),
+            None => Ok(()),
+        }
+    }
+
     /// Find out the direct subsections of each subsection if there are some
     /// as well as the pages for each section
     pub fn populate_sections(&mut self) {
@@ -563,9 +997,38 @@ impl Site {
         library.populate_sections(&self.config, &self.content_path);
     }
 
+    /// The directories named after a taxonomy, which contain the files describing its terms
+    /// unless they are sections
+    fn taxonomy_dirs(&self) -> HashSet<PathBuf> {
+        self.config
+            .languages
+            .values()
+            .flat_map(|l| l.taxonomies.iter())
+            .map(|t| self.base_path.join("content").join(&t.slug))
+            .collect()
+    }
+
     /// Find all the tags and categories if it's asked in the config
     pub fn populate_taxonomies(&mut self) -> Result<()> {
         self.taxonomies = self.library.read().unwrap().find_taxonomies(&self.config);
+        let mut term_files = HashSet::new();
+        for taxonomy in self.taxonomies.iter_mut() {
+            term_files.extend(taxonomy.load_terms_metadata(&self.base_path, &self.config)?);
+        }
+
+        self.unknown_term_files = self
+            .taxonomy_dirs()
+            .into_iter()
+            .filter(|dir| is_terms_directory(dir))
+            .flat_map(|dir| std::fs::read_dir(dir).into_iter().flatten().filter_map(|e| e.ok()))
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension().is_some_and(|ext| ext == "md")
+                    && !is_dotfile(p)
+                    && !term_files.contains(p)
+            })
+            .collect();
+        self.unknown_term_files.sort();
         Ok(())
     }
 
@@ -588,32 +1051,99 @@ impl Site {
     pub fn copy_static_directories(&self) -> Result<()> {
         // The user files will overwrite the theme files
         if let Some(ref theme) = self.config.theme {
-            copy_directory(
+            self.copy_static_directory(
                 &self.base_path.join("themes").join(theme).join("static"),
-                &self.output_path,
                 false,
                 None,
             )?;
         }
         // We're fine with missing static folders
-        if self.static_path.exists() {
-            if let Some(gs) = &self.config.ignored_static_globset {
-                copy_directory(
-                    &self.static_path,
-                    &self.output_path,
-                    self.config.hard_link_static,
-                    Some(gs),
-                )?;
-            } else {
-                copy_directory(
-                    &self.static_path,
-                    &self.output_path,
-                    self.config.hard_link_static,
+        if let Some(ref cache) = self.build_cache {
+            if let Some(ref theme) = self.config.theme {
+                cache.record_copied_directory(
+                    &self.base_path.join("themes").join(theme).join("static"),
                     None,
-                )?;
+                );
+            }
+            if self.static_path.exists() {
+                cache.record_copied_directory(
+                    &self.static_path,
+                    self.config.ignored_static_globset.as_ref(),
+                );
+            }
+        }
+        if self.static_path.exists() {
+            self.copy_static_directory(
+                &self.static_path,
+                self.config.hard_link_static,
+                self.config.ignored_static_globset.as_ref(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies a static folder to the output directory, optimizing its images if
+    /// `[static_images]` is set
+    fn copy_static_directory(
+        &self,
+        src: &Path,
+        hard_link: bool,
+        ignore_globset: Option<&libs::globset::GlobSet>,
+    ) -> Result<()> {
+        let mut images = Vec::new();
+        copy_directory_except(src, &self.output_path, hard_link, ignore_globset, |relative| {
+            // The manifest of the processed images is only there for the next builds
+            if imageproc::is_processor_file(relative) {
+                return true;
+            }
+            match self.static_images {
+                Some(ref optimizer) if optimizer.is_included(relative) => {
+                    images.push((src.join(relative), self.output_path.join(relative)));
+                    true
+                }
+                _ => false,
+            }
+        })?;
+        match self.static_images {
+            Some(ref optimizer) => self.optimize_static_images(optimizer, &images),
+            None => Ok(()),
+        }
+    }
+
+    /// Copies a single file of the `static` folder, used by `zola serve` when it changes
+    pub fn copy_static_file(&self, path: &Path) -> Result<()> {
+        if let Ok(relative) = path.strip_prefix(&self.static_path) {
+            if imageproc::is_processor_file(relative) {
+                return Ok(());
             }
         }
+        if let Some(ref optimizer) = self.static_images {
+            if let Ok(relative) = path.strip_prefix(&self.static_path) {
+                if optimizer.is_included(relative) {
+                    let image = (path.to_path_buf(), self.output_path.join(relative));
+                    return self.optimize_static_images(optimizer, &[image]);
+                }
+            }
+        }
+        copy_file(path, &self.output_path, &self.static_path, self.config.hard_link_static)
+    }
 
+    /// Writes the optimized images and records their conversions in the build cache so they
+    /// are not deleted as stale outputs
+    fn optimize_static_images(
+        &self,
+        optimizer: &imageproc::StaticImageOptimizer,
+        images: &[(PathBuf, PathBuf)],
+    ) -> Result<()> {
+        let written = optimizer.optimize(images)?;
+        if let Some(ref cache) = self.build_cache {
+            for path in written {
+                if let Ok(relative) = path.strip_prefix(&self.output_path) {
+                    cache.record_copied_file(relative);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -635,6 +1165,14 @@ impl Site {
         clean_site_output_folder(&self.output_path, self.config.preserve_dotfiles_in_output)
     }
 
+    /// Removes the processed images that are outdated without building the site,
+    /// returning how many were removed
+    pub fn clean_processed_images(&self) -> Result<usize> {
+        let mut imageproc =
+            self.imageproc.lock().expect("Couldn't lock imageproc (clean_processed_images)");
+        imageproc.clean()
+    }
+
     /// Handles whether to write to disk or to memory
     pub fn write_content(
         &self,
@@ -661,8 +1199,10 @@ impl Site {
 
         match self.build_mode {
             BuildMode::Disk => {
-                let end_path = current_path.join(filename);
-                create_file(&end_path, &final_content)?;
+                let mut relative_path: Vec<&str> =
+                    components.iter().copied().filter(|c| !c.is_empty()).collect();
+                relative_path.push(filename);
+                self.write_file(&relative_path.join("/"), &final_content)?;
             }
             BuildMode::Memory => {
                 let site_path =
@@ -675,19 +1215,63 @@ impl Site {
         Ok(current_path)
     }
 
+    /// Writes a file at the given path relative to the output directory, going through the
+    /// build cache if there is one
+    fn write_file(&self, relative_path: &str, content: &str) -> Result<()> {
+        match self.build_cache {
+            Some(ref cache) => cache.write_file(&self.output_path, relative_path, content),
+            None => create_file(&self.output_path.join(relative_path), content),
+        }
+    }
+
     fn copy_assets(&self, parent: &Path, assets: &[impl AsRef<Path>], dest: &Path) -> Result<()> {
+        let mut images = Vec::new();
         for asset in assets {
             let asset_path = asset.as_ref();
-            copy_file_if_needed(
-                asset_path,
-                &dest.join(
-                    asset_path.strip_prefix(parent).expect("Couldn't get filename from page asset"),
-                ),
-                self.config.hard_link_static,
-            )?;
+            let dest_path = dest.join(
+                asset_path.strip_prefix(parent).expect("Couldn't get filename from page asset"),
+            );
+            if let Some(ref cache) = self.build_cache {
+                if let Ok(relative) = dest_path.strip_prefix(&self.output_path) {
+                    cache.record_copied_file(relative);
+                }
+            }
+            // The patterns of the images to optimize are relative to the content folder
+            let is_image = match (&self.static_images, asset_path.strip_prefix(&self.content_path))
+            {
+                (Some(optimizer), Ok(relative)) => optimizer.is_included(relative),
+                _ => false,
+            };
+            if is_image {
+                images.push((asset_path.to_path_buf(), dest_path));
+            } else {
+                copy_file_if_needed(asset_path, &dest_path, self.config.hard_link_static)?;
+            }
         }
 
-        Ok(())
+        match self.static_images {
+            Some(ref optimizer) if !images.is_empty() => {
+                self.optimize_static_images(optimizer, &images)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the page was rendered by the previous build with the exact same inputs, in which
+    /// case there is no need to render it again
+    fn is_page_fresh(&self, page: &Page, library: &Library) -> bool {
+        let cache = match self.build_cache {
+            Some(ref c) => c,
+            None => return false,
+        };
+        let inputs_hash = cache::hash_serializable(&(
+            page.meta.template.as_deref(),
+            &page.lang,
+            page.serialize(library),
+        ));
+        let mut output = page.path.trim_start_matches('/').to_string();
+        output.push_str("index.html");
+        cache.is_page_fresh(&page.file.relative, inputs_hash, &self.output_path, &[output])
     }
 
     /// Renders a single content page
@@ -696,10 +1280,17 @@ impl Site {
             return Ok(());
         }
 
-        let output = page.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
-        let content = self.inject_livereload(output);
         let components: Vec<&str> = page.path.split('/').collect();
-        let current_path = self.write_content(&components, "index.html", content)?;
+        let library = self.library.read().unwrap();
+        let current_path = if self.is_page_fresh(page, &library) {
+            components.iter().fold(self.output_path.clone(), |p, c| p.join(c))
+        } else {
+            let output = self.track(Output::Page(page.file.path.clone()), || {
+                page.render_html(&self.tera, &self.config, &library)
+            })?;
+            let content = self.inject_livereload(output);
+            self.write_content(&components, "index.html", content)?
+        };
 
         // Copy any asset we found previously into the same directory as the index.html
         self.copy_assets(page.file.path.parent().unwrap(), &page.assets, &current_path)?;
@@ -710,8 +1301,40 @@ impl Site {
     /// Deletes the `public` directory (only for `zola build`) and builds the site
     pub fn build(&self) -> Result<()> {
         let mut start = Instant::now();
-        // Do not clean on `zola serve` otherwise we end up copying assets all the time
-        if self.build_mode == BuildMode::Disk {
+        // Do not clean on `zola serve` otherwise we end up copying assets all the time.
+        // Incremental builds only clean if the previous build can't be reused.
+        let mut needs_cleaning = self.build_mode == BuildMode::Disk;
+        if let Some(ref cache) = self.build_cache {
+            // The environment config file and the env vars overrides are part of the config
+            let env_config = match self.config.environment {
+                Some(ref env) => {
+                    std::fs::read_to_string(config::env_config_path(&self.config_file, env))
+                        .unwrap_or_default()
+                }
+                None => String::new(),
+            };
+            let global = cache::hash_global_inputs(
+                &self.base_path,
+                &self.config_file,
+                &[
+                    &self.config.base_url,
+                    &format!("{:?}", self.config.mode),
+                    &format!("{:?}", self.config.environment),
+                    &env_config,
+                    &format!("{:?}", config::env_overrides()),
+                ],
+                &[&self.output_path, &self.base_path.join(cache::CACHE_DIR)],
+            )?;
+            let reused = cache.start(&self.output_path, global);
+            needs_cleaning &= !reused;
+            // Pages that are not rendered again don't enqueue their images
+            self.imageproc
+                .lock()
+                .expect("Couldn't lock imageproc (set_partial_build)")
+                .set_partial_build(reused);
+            start = log_time(start, "Hashed build cache inputs");
+        }
+        if needs_cleaning {
             self.clean()?;
         }
         start = log_time(start, "Cleaned folder");
@@ -720,13 +1343,13 @@ impl Site {
         if let Some(ref theme) = self.config.theme {
             let theme_path = self.base_path.join("themes").join(theme);
             if theme_path.join("sass").exists() {
-                sass::compile_sass(&theme_path, &self.output_path)?;
+                self.compile_sass(&theme_path)?;
                 start = log_time(start, "Compiled theme Sass");
             }
         }
 
         if self.config.compile_sass {
-            sass::compile_sass(&self.base_path, &self.output_path)?;
+            self.compile_sass(&self.base_path)?;
             start = log_time(start, "Compiled own Sass");
         }
 
@@ -746,7 +1369,53 @@ impl Site {
             self.render_sitemap()?;
             start = log_time(start, "Rendered sitemap");
         }
+        self.render_site_feeds()?;
+        start = log_time(start, "Generated feeds");
+        self.render_themes_css()?;
+        start = log_time(start, "Rendered themes css");
+        self.render_404()?;
+        start = log_time(start, "Rendered 404");
+        if self.config.generate_robots_txt {
+            self.render_robots()?;
+            start = log_time(start, "Rendered robots.txt");
+        }
+        self.render_taxonomies()?;
+        start = log_time(start, "Rendered taxonomies");
+        // We process images at the end as we might have picked up images to process from markdown
+        // or from templates
+        self.process_images()?;
+        start = log_time(start, "Processed images");
+        // Processed images will be in static so the last step is to copy it
+        self.copy_static_directories()?;
+        start = log_time(start, "Copied static dir");
+
+        if let Some(ref cache) = self.build_cache {
+            if self.build_mode == BuildMode::Disk {
+                cache.finish(&self.output_path)?;
+                log_time(start, "Saved build cache");
+            }
+        }
 
+        Ok(())
+    }
+
+    /// Renders the outputs using all the content or that we don't track: aliases, sitemap,
+    /// site feeds, 404 and robots.txt
+    fn render_site_wide_outputs(&self) -> Result<()> {
+        self.render_aliases()?;
+        if self.config.generate_sitemap {
+            self.render_sitemap()?;
+        }
+        self.render_site_feeds()?;
+        self.render_404()?;
+        if self.config.generate_robots_txt {
+            self.render_robots()?;
+        }
+        Ok(())
+    }
+
+    /// Renders the feeds of the whole site, for each language
+    fn render_site_feeds(&self) -> Result<()> {
         let library = self.library.read().unwrap();
         if self.config.generate_feeds {
             let is_multilingual = self.config.is_multilingual();
@@ -756,7 +1425,6 @@ impl Site {
                 library.pages.values().collect()
             };
             self.render_feeds(pages, None, &self.config.default_language, |c| c)?;
-            start = log_time(start, "Generated feed in default language");
         }
 
         for (code, language) in &self.config.other_languages() {
@@ -765,26 +1433,17 @@ impl Site {
             }
             let pages: Vec<_> = library.pages.values().filter(|p| &p.lang == code).collect();
             self.render_feeds(pages, Some(&PathBuf::from(code)), code, |c| c)?;
-            start = log_time(start, "Generated feed in other language");
         }
-        self.render_themes_css()?;
-        start = log_time(start, "Rendered themes css");
-        self.render_404()?;
-        start = log_time(start, "Rendered 404");
-        if self.config.generate_robots_txt {
-            self.render_robots()?;
-            start = log_time(start, "Rendered robots.txt");
-        }
-        self.render_taxonomies()?;
-        start = log_time(start, "Rendered taxonomies");
-        // We process images at the end as we might have picked up images to process from markdown
-        // or from templates
-        self.process_images()?;
-        start = log_time(start, "Processed images");
-        // Processed images will be in static so the last step is to copy it
-        self.copy_static_directories()?;
-        log_time(start, "Copied static dir");
+        Ok(())
+    }
 
+    fn compile_sass(&self, base_path: &Path) -> Result<()> {
+        create_directory(&self.output_path)?;
+        for (path, css) in sass::compile_sass_files(base_path)? {
+            let relative = RelativePathBuf::from_path(&path)
+                .map_err(|e| anyhow!("Invalid Sass output path {}: {}", path.display(), e))?;
+            self.write_file(relative.as_str(), &css)?;
+        }
         Ok(())
     }
 
@@ -807,7 +1466,7 @@ impl Site {
     }
 
     fn index_for_lang(&self, lang: &str) -> Result<()> {
-        let path = &self.output_path.join(self.config.search.index_format.filename(lang));
+        let filename = self.config.search.index_format.filename(lang);
         let library = self.library.read().unwrap();
         let content = match &self.config.search.index_format {
             IndexFormat::ElasticlunrJavascript | IndexFormat::ElasticlunrJson => {
@@ -818,9 +1477,9 @@ impl Site {
             }
         };
         drop(library); // no need to hold on to this guard while writing
-        create_file(
-            path,
-            match self.config.search.index_format {
+        self.write_file(
+            &filename,
+            &match self.config.search.index_format {
                 IndexFormat::ElasticlunrJson | IndexFormat::FuseJson => content,
                 IndexFormat::ElasticlunrJavascript | IndexFormat::FuseJavascript => {
                     format!("window.searchIndex = {}", content)
@@ -845,7 +1504,7 @@ impl Site {
         match self.config.search.index_format {
             IndexFormat::ElasticlunrJavascript | IndexFormat::ElasticlunrJson => {
                 // then elasticlunr.min.js
-                create_file(&self.output_path.join("elasticlunr.min.js"), search::ELASTICLUNR_JS)?;
+                self.write_file("elasticlunr.min.js", search::ELASTICLUNR_JS)?;
             }
             _ => {}
         }
@@ -932,8 +1591,10 @@ impl Site {
 
         components.push(taxonomy.slug.as_ref());
 
-        let list_output =
-            taxonomy.render_all_terms(&self.tera, &self.config, &self.library.read().unwrap())?;
+        let output = Output::Taxonomy(format!("{}-{}", taxonomy.kind.name, taxonomy.lang));
+        let list_output = self.track(output.clone(), || {
+            taxonomy.render_all_terms(&self.tera, &self.config, &self.library.read().unwrap())
+        })?;
         let content = self.inject_livereload(list_output);
         self.write_content(&components, "index.html", content)?;
 
@@ -942,49 +1603,54 @@ impl Site {
             .items
             .par_iter()
             .map(|item| {
-                let mut comp = components.clone();
-                comp.push(&item.slug);
-
-                if taxonomy.kind.is_paginated() {
-                    self.render_paginated(
-                        comp.clone(),
-                        &Paginator::from_taxonomy(
-                            taxonomy,
-                            item,
-                            &library,
-                            &self.tera,
-                            &self.config.theme,
-                        ),
-                    )?;
-                } else {
-                    let single_output =
-                        taxonomy.render_term(item, &self.tera, &self.config, &library)?;
-                    let content = self.inject_livereload(single_output);
-                    self.write_content(&comp, "index.html", content)?;
-                }
+                self.track(output.clone(), || {
+                    let mut comp = components.clone();
+                    comp.push(&item.slug);
+
+                    if taxonomy.kind.is_paginated() {
+                        self.render_paginated(
+                            comp.clone(),
+                            &Paginator::from_taxonomy(
+                                taxonomy,
+                                item,
+                                &library,
+                                &self.tera,
+                                &self.config.theme,
+                            ),
+                        )?;
+                    } else {
+                        let single_output =
+                            taxonomy.render_term(item, &self.tera, &self.config, &library)?;
+                        let content = self.inject_livereload(single_output);
+                        self.write_content(&comp, "index.html", content)?;
+                    }
 
-                if taxonomy.kind.feed {
-                    let tax_path = if taxonomy.lang == self.config.default_language {
-                        PathBuf::from(format!("{}/{}", taxonomy.slug, item.slug))
+                    if taxonomy.kind.feed {
+                        let tax_path = if taxonomy.lang == self.config.default_language {
+                            PathBuf::from(format!("{}/{}", taxonomy.slug, item.slug))
+                        } else {
+                            PathBuf::from(format!(
+                                "{}/{}/{}",
+                                taxonomy.lang, taxonomy.slug, item.slug
+                            ))
+                        };
+                        self.render_feeds(
+                            item.pages.iter().map(|p| library.pages.get(p).unwrap()).collect(),
+                            Some(&tax_path),
+                            &taxonomy.lang,
+                            |mut context: Context| {
+                                context.insert("taxonomy", &taxonomy.kind);
+                                context.insert(
+                                    "term",
+                                    &feeds::SerializedFeedTaxonomyItem::from_item(item),
+                                );
+                                context
+                            },
+                        )
                     } else {
-                        PathBuf::from(format!("{}/{}/{}", taxonomy.lang, taxonomy.slug, item.slug))
-                    };
-                    self.render_feeds(
-                        item.pages.iter().map(|p| library.pages.get(p).unwrap()).collect(),
-                        Some(&tax_path),
-                        &taxonomy.lang,
-                        |mut context: Context| {
-                            context.insert("taxonomy", &taxonomy.kind);
-                            context.insert(
-                                "term",
-                                &feeds::SerializedFeedTaxonomyItem::from_item(item),
-                            );
-                            context
-                        },
-                    )
-                } else {
-                    Ok(())
-                }
+                        Ok(())
+                    }
+                })
             })
             .collect::<Result<()>>()
     }
@@ -1089,15 +1755,17 @@ impl Site {
         if section.meta.generate_feeds {
             let library = &self.library.read().unwrap();
             let pages = section.pages.iter().map(|k| library.pages.get(k).unwrap()).collect();
-            self.render_feeds(
-                pages,
-                Some(&PathBuf::from(&section.path[1..])),
-                &section.lang,
-                |mut context: Context| {
-                    context.insert("section", &section.serialize(library));
-                    context
-                },
-            )?;
+            self.track(Output::Section(section.file.path.clone()), || {
+                self.render_feeds(
+                    pages,
+                    Some(&PathBuf::from(&section.path[1..])),
+                    &section.lang,
+                    |mut context: Context| {
+                        context.insert("section", &section.serialize(library));
+                        context
+                    },
+                )
+            })?;
         }
 
         // Copy any asset we found previously into the same directory as the index.html
@@ -1130,17 +1798,20 @@ impl Site {
             return Ok(());
         }
 
-        if section.meta.is_paginated() {
-            self.render_paginated(
-                components,
-                &Paginator::from_section(section, &self.library.read().unwrap()),
-            )?;
-        } else {
-            let output =
-                section.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
-            let content = self.inject_livereload(output);
-            self.write_content(&components, "index.html", content)?;
-        }
+        self.track(Output::Section(section.file.path.clone()), || {
+            if section.meta.is_paginated() {
+                self.render_paginated(
+                    components,
+                    &Paginator::from_section(section, &self.library.read().unwrap()),
+                )
+            } else {
+                let output =
+                    section.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
+                let content = self.inject_livereload(output);
+                self.write_content(&components, "index.html", content)?;
+                Ok(())
+            }
+        })?;
 
         Ok(())
     }
@@ -1174,39 +1845,83 @@ impl Site {
     ) -> Result<()> {
         let index_components = components.clone();
 
-        paginator
+        // Pagers are rendered in parallel so we need to forward what they used to the caller
+        let rendered: Vec<_> = paginator
             .pagers
             .par_iter()
             .map(|pager| {
-                let mut pager_components = index_components.clone();
-                pager_components.push(&paginator.paginate_path);
-                let pager_path = format!("{}", pager.index);
-                pager_components.push(&pager_path);
-                let output = paginator.render_pager(
-                    pager,
-                    &self.config,
-                    &self.tera,
-                    &self.library.read().unwrap(),
-                )?;
-                let content = self.inject_livereload(output);
-
-                if pager.index > 1 {
-                    self.write_content(&pager_components, "index.html", content)?;
-                } else {
-                    self.write_content(&index_components, "index.html", content)?;
-                    self.write_content(
-                        &pager_components,
-                        "index.html",
-                        render_redirect_template(&paginator.permalink, &self.tera)?,
+                dependencies::track(|| -> Result<()> {
+                    let mut pager_components = index_components.clone();
+                    pager_components.push(&paginator.paginate_path);
+                    let pager_path = format!("{}", pager.index);
+                    pager_components.push(&pager_path);
+                    let output = paginator.render_pager(
+                        pager,
+                        &self.config,
+                        &self.tera,
+                        &self.library.read().unwrap(),
                     )?;
-                }
+                    let content = self.inject_livereload(output);
 
-                Ok(())
+                    if pager.index > 1 {
+                        self.write_content(&pager_components, "index.html", content)?;
+                    } else {
+                        self.write_content(&index_components, "index.html", content)?;
+                        self.write_content(
+                            &pager_components,
+                            "index.html",
+                            render_redirect_template(&paginator.permalink, &self.tera)?,
+                        )?;
+                    }
+
+                    Ok(())
+                })
             })
-            .collect::<Result<()>>()
+            .collect();
+
+        for (res, deps) in rendered {
+            res?;
+            dependencies::record_dependencies(deps);
+        }
+        Ok(())
     }
 }
 
+/// Whether the changes between those 2 versions of a page can be handled by re-rendering only what
+/// uses it. With `wikilinks`, the title and the slug can't change since links are resolved with them.
+fn has_same_structure_as_page(previous: &Page, page: &Page, wikilinks: bool) -> bool {
+    let same_wikilink_names =
+        !wikilinks || (previous.meta.title == page.meta.title && previous.slug == page.slug);
+    same_wikilink_names
+        && previous.path == page.path
+        && previous.lang == page.lang
+        && previous.meta.draft == page.meta.draft
+        && previous.meta.publish_date == page.meta.publish_date
+        && previous.meta.expiry_date == page.meta.expiry_date
+        && previous.meta.render == page.meta.render
+        && previous.meta.aliases == page.meta.aliases
+        && previous.meta.taxonomies == page.meta.taxonomies
+}
+
+/// Same as `has_same_structure_as_page` but for sections: only the title, description, extra
+/// and template can change, the title only without `wikilinks`
+fn has_same_structure_as_section(previous: &Section, section: &Section, wikilinks: bool) -> bool {
+    let mut meta = previous.meta.clone();
+    if !wikilinks {
+        meta.title.clone_from(&section.meta.title);
+    }
+    meta.description.clone_from(&section.meta.description);
+    meta.template.clone_from(&section.meta.template);
+    meta.extra.clone_from(&section.meta.extra);
+    previous.path == section.path && previous.lang == section.lang && meta == section.meta
+}
+
+/// Template paths and the paths we get from the file watcher are not always in the same form
+fn canonicalize<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
 fn log_time(start: Instant, message: &str) -> Instant {
     let do_print = std::env::var("ZOLA_PERF_LOG").is_ok();
     let now = Instant::now();
@@ -0,0 +1,88 @@
+//! Keeps track of what each output used while being rendered in `zola serve` so a change only
+//! re-renders the outputs depending on it.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use utils::dependencies::Dependencies;
+
+/// Something rendered by the site that we can render again on its own
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Output {
+    /// The markdown content of a page or section, by path of its `.md` file
+    Markdown(PathBuf),
+    /// A page, by path of its `.md` file
+    Page(PathBuf),
+    /// A section along with its pagers and feeds, by path of its `_index.md` file
+    Section(PathBuf),
+    /// A taxonomy list and all its terms, as `{name}-{lang}`
+    Taxonomy(String),
+}
+
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    outputs: Mutex<HashMap<Output, Dependencies>>,
+}
+
+impl DependencyGraph {
+    pub fn record(&self, output: Output, dependencies: Dependencies) {
+        let mut outputs = self.outputs.lock().unwrap();
+        // Paginated sections and taxonomies record one entry per pager
+        outputs.entry(output).or_default().extend(dependencies);
+    }
+
+    pub fn forget(&self, output: &Output) {
+        self.outputs.lock().unwrap().remove(output);
+    }
+
+    pub fn clear(&self) {
+        self.outputs.lock().unwrap().clear();
+    }
+
+    /// All the outputs whose dependencies match the predicate
+    pub fn find(&self, predicate: impl Fn(&Dependencies) -> bool) -> HashSet<Output> {
+        let outputs = self.outputs.lock().unwrap();
+        outputs.iter().filter(|(_, deps)| predicate(deps)).map(|(o, _)| o.clone()).collect()
+    }
+
+    pub fn outputs_using_templates(&self, names: &HashSet<String>) -> HashSet<Output> {
+        self.find(|d| !d.templates.is_disjoint(names))
+    }
+
+    pub fn outputs_using_file(&self, path: &Path) -> HashSet<Output> {
+        self.find(|d| d.files.contains(path))
+    }
+
+    pub fn outputs_using_content(&self, paths: &HashSet<PathBuf>) -> HashSet<Output> {
+        self.find(|d| !d.content.is_disjoint(paths))
+    }
+
+    pub fn outputs_using_taxonomies(&self, keys: &HashSet<String>) -> HashSet<Output> {
+        self.find(|d| !d.taxonomies.is_disjoint(keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_find_outputs_by_dependency() {
+        let graph = DependencyGraph::default();
+        let mut deps = Dependencies::default();
+        deps.templates.insert("page.html".to_string());
+        deps.files.insert(PathBuf::from("data.toml"));
+        graph.record(Output::Page(PathBuf::from("a.md")), deps);
+        let mut deps = Dependencies::default();
+        deps.templates.insert("section.html".to_string());
+        graph.record(Output::Section(PathBuf::from("_index.md")), deps);
+
+        let found = graph.outputs_using_templates(&HashSet::from(["page.html".to_string()]));
+        assert_eq!(found, HashSet::from([Output::Page(PathBuf::from("a.md"))]));
+        assert_eq!(graph.outputs_using_file(Path::new("data.toml")).len(), 1);
+        assert!(graph.outputs_using_file(Path::new("other.toml")).is_empty());
+
+        graph.forget(&Output::Page(PathBuf::from("a.md")));
+        assert!(graph.outputs_using_file(Path::new("data.toml")).is_empty());
+    }
+}
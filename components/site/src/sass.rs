@@ -12,6 +12,20 @@ use utils::fs::{create_directory, create_file};
 pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
     create_directory(output_path)?;
 
+    for (path, css) in compile_sass_files(base_path)? {
+        let css_output_path = output_path.join(path);
+        if let Some(parent) = css_output_path.parent() {
+            create_dir_all(parent)?;
+        }
+        create_file(&css_output_path, &css)?;
+    }
+
+    Ok(())
+}
+
+/// Compiles all the non-partial Sass files of the `sass` directory in `base_path` and returns
+/// the CSS along with the path it should be written to, relative to the output directory
+pub fn compile_sass_files(base_path: &Path) -> Result<Vec<(PathBuf, String)>> {
     let sass_path = {
         let mut sass_path = PathBuf::from(base_path);
         sass_path.push("sass");
@@ -20,25 +34,19 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
 
     let options = Options::default().style(OutputStyle::Compressed);
     let files = get_non_partial_scss(&sass_path);
-    let mut compiled_paths = Vec::new();
+    let mut compiled = Vec::new();
 
     for file in files {
         let css = compile_file(&file, &options).map_err(|e| anyhow!(e))?;
 
         let path_inside_sass = file.strip_prefix(&sass_path).unwrap();
-        let parent_inside_sass = path_inside_sass.parent();
-        let css_output_path = output_path.join(path_inside_sass).with_extension("css");
-
-        if parent_inside_sass.is_some() {
-            create_dir_all(css_output_path.parent().unwrap())?;
-        }
+        let css_output_path = path_inside_sass.with_extension("css");
 
-        create_file(&css_output_path, &css)?;
-        compiled_paths.push((path_inside_sass.to_owned(), css_output_path));
+        compiled.push((path_inside_sass.to_owned(), css_output_path, css));
     }
 
-    compiled_paths.sort();
-    for window in compiled_paths.windows(2) {
+    compiled.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+    for window in compiled.windows(2) {
         if window[0].1 == window[1].1 {
             bail!(
                 "SASS path conflict: \"{}\" and \"{}\" both compile to \"{}\"",
@@ -49,7 +57,7 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
         }
     }
 
-    Ok(())
+    Ok(compiled.into_iter().map(|(_, path, css)| (path, css)).collect())
 }
 
 fn is_partial_scss(entry: &DirEntry) -> bool {
@@ -28,10 +28,29 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
             site.output_path.clone(),
         ),
     );
+    site.tera.register_function(
+        "responsive_image",
+        global_fns::ResponsiveImage::new(
+            site.base_path.clone(),
+            site.imageproc.clone(),
+            site.config.theme.clone(),
+            site.output_path.clone(),
+        ),
+    );
+    site.tera.register_function(
+        "social_card",
+        global_fns::SocialCard::new(
+            site.base_path.clone(),
+            site.config.clone(),
+            site.imageproc.clone(),
+            site.output_path.clone(),
+        ),
+    );
     site.tera.register_function(
         "get_image_metadata",
         global_fns::GetImageMetadata::new(
             site.base_path.clone(),
+            site.imageproc.clone(),
             site.config.theme.clone(),
             site.output_path.clone(),
         ),
@@ -51,9 +51,23 @@ pub fn build_site(name: &str) -> (Site, TempDir, PathBuf) {
     (site, tmp_dir, public.clone())
 }
 
-/// Same as `build_site` but has a hook to setup some config options
-pub fn build_site_with_setup<F>(name: &str, mut setup_cb: F) -> (Site, TempDir, PathBuf)
+/// Copies the site to a temporary directory, for the tests that need to modify its files.
+/// We return the tmpdir for the same reason as `build_site`, along with the path of the copy
+pub fn copy_site(name: &str) -> (TempDir, PathBuf) {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push(name);
+    let tmp_dir = tempdir().expect("create temp dir");
+    let site_path = tmp_dir.path().join("site");
+    utils::fs::copy_directory(&path, &site_path, false, None).unwrap();
+    let _ = std::fs::remove_dir_all(site_path.join("public"));
+    (tmp_dir, site_path)
+}
+
+/// Same as `build_site` but has a hook to setup some config options.
+/// The site can also be the absolute path of a copy made with `copy_site`
+pub fn build_site_with_setup<P, F>(name: P, mut setup_cb: F) -> (Site, TempDir, PathBuf)
 where
+    P: AsRef<Path>,
     F: FnMut(Site) -> (Site, bool),
 {
     let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
@@ -71,6 +85,20 @@ where
     (site, tmp_dir, public.clone())
 }
 
+/// Loads and builds the copy of a site made with `copy_site` in serve mode, rendering it in
+/// memory, with a hook to setup some config options
+pub fn serve_site_with_setup<F>(site_path: &Path, mut setup_cb: F) -> Site
+where
+    F: FnMut(Site) -> Site,
+{
+    let site = Site::new(site_path, site_path.join("config.toml")).unwrap();
+    let mut site = setup_cb(site);
+    site.enable_serve_mode();
+    site.load().unwrap();
+    site.build().expect("Couldn't build the site");
+    site
+}
+
 /// Finds the unified path (eg. _index.fr.md -> _index.md) and
 /// potential language (if not default) associated with a path
 /// When the path is not a markdown file (.md), None is returned
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 
-use common::{build_site, build_site_with_setup};
+use common::{build_site, build_site_with_setup, copy_site, serve_site_with_setup};
 use config::TaxonomyConfig;
 use content::Page;
 use libs::ahash::AHashMap;
@@ -263,6 +263,14 @@ fn can_build_site_with_live_reload_and_drafts() {
     assert!(file_exists!(public, "categories/index.html"));
     assert!(file_exists!(public, "categories/a-category/index.html"));
     assert!(file_exists!(public, "categories/a-category/atom.xml"));
+    // The term uses the data of its file, which is not a page
+    assert!(file_contains!(public, "categories/a-category/index.html", "<h1>A category</h1>"));
+    assert!(file_contains!(
+        public,
+        "categories/a-category/index.html",
+        "<p>This is <strong>a category</strong>.</p>"
+    ));
+    assert!(!file_exists!(public, "categories/a-category/a-category/index.html"));
     // But no tags
     assert!(!file_exists!(public, "tags/index.html"));
 
@@ -582,6 +590,7 @@ fn can_build_site_with_pagination_for_taxonomy() {
             paginate_path: None,
             render: true,
             feed: true,
+            hierarchical: false,
         });
         site.load().unwrap();
         {
@@ -690,6 +699,167 @@ fn can_build_feeds() {
     assert!(file_contains!(public, "posts/tutorials/programming/atom.xml", "Baz Doe"));
 }
 
+#[test]
+fn can_build_json_feeds() {
+    use libs::serde_json;
+
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.feed_filenames = vec!["atom.xml".to_string(), "feed.json".to_string()];
+        site.config.languages.get_mut("en").unwrap().feed_filenames =
+            site.config.feed_filenames.clone();
+        (site, true)
+    });
+
+    // Checks the feed against https://www.jsonfeed.org/version/1.1/
+    let is_date = |v: &serde_json::Value| {
+        libs::time::OffsetDateTime::parse(
+            v.as_str().unwrap(),
+            &libs::time::format_description::well_known::Rfc3339,
+        )
+        .is_ok()
+    };
+    for path in
+        ["feed.json", "posts/tutorials/programming/feed.json", "categories/a-category/feed.json"]
+    {
+        let content = std::fs::read_to_string(public.join(path)).unwrap();
+        let feed: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+        assert!(feed["title"].is_string());
+        assert!(feed["home_page_url"].as_str().unwrap().starts_with("https://"));
+        assert!(feed["feed_url"].as_str().unwrap().ends_with(path));
+        let items = feed["items"].as_array().unwrap();
+        assert!(!items.is_empty());
+        for item in items {
+            assert!(item["id"].is_string());
+            assert!(item["content_html"].is_string());
+            assert!(is_date(&item["date_published"]));
+            assert!(item.get("date_modified").map_or(true, is_date));
+            for author in item["authors"].as_array().unwrap() {
+                assert!(author["name"].is_string());
+            }
+            if let Some(tags) = item.get("tags") {
+                assert!(tags.as_array().unwrap().iter().all(|t| t.is_string()));
+            }
+        }
+    }
+
+    let content = std::fs::read_to_string(public.join("feed.json")).unwrap();
+    let feed: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let items = feed["items"].as_array().unwrap();
+    let first = items.iter().find(|i| i["title"] == "first").unwrap();
+    assert_eq!(first["tags"], serde_json::json!(["Some Person", "a-category"]));
+    assert_eq!(first["date_modified"], "2017-02-01T00:00:00+00:00");
+    assert_eq!(first["authors"][0]["name"], "config@example.com (Config Author)");
+    let rust =
+        std::fs::read_to_string(public.join("posts/tutorials/programming/feed.json")).unwrap();
+    let rust: serde_json::Value = serde_json::from_str(&rust).unwrap();
+    let authors = rust["items"].as_array().unwrap().iter().find(|i| i["title"] == "Rust").unwrap();
+    assert_eq!(authors["authors"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn can_build_podcast_feeds() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let podcast = site_path.join("content").join("podcast");
+    std::fs::create_dir_all(podcast.join("episode-1")).unwrap();
+    std::fs::write(
+        podcast.join("_index.md"),
+        "+++\ntitle = \"The Show\"\nsort_by = \"date\"\ngenerate_feeds = true\npodcast = true\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(
+        podcast.join("episode-1").join("index.md"),
+        "+++\ntitle = \"Pilot\"\ndate = 2024-01-01\n[podcast]\nduration = 1234\nepisode = 1\nseason = 2\nexplicit = true\n+++\n",
+    )
+    .unwrap();
+    std::fs::write(podcast.join("episode-1").join("pilot.mp3"), [0; 128]).unwrap();
+    std::fs::write(
+        podcast.join("trailer.md"),
+        "+++\ntitle = \"Trailer\"\ndate = 2023-12-01\n[podcast]\naudio = \"/trailer.ogg\"\nlength = 64\nepisode_type = \"trailer\"\n+++\n",
+    )
+    .unwrap();
+
+    let (_, _public_dir, public) = build_site_with_setup(&site_path, |mut site| {
+        site.config.feed_filenames = vec!["rss.xml".to_string()];
+        site.config.languages.get_mut("en").unwrap().feed_filenames =
+            site.config.feed_filenames.clone();
+        site.config.podcast.owner_name = Some("Owner".to_string());
+        site.config.podcast.image = Some("podcast.png".to_string());
+        site.config.podcast.categories = vec!["Technology > Tech News".to_string()];
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        r#"xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd""#
+    ));
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        "<itunes:author>config@example.com (Config Author)</itunes:author>"
+    ));
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        "<itunes:owner><itunes:name>Owner</itunes:name></itunes:owner>"
+    ));
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        r#"<itunes:image href="https://techmercenaries.com/podcast.png"/>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        r#"<itunes:category text="Technology"><itunes:category text="Tech News"/></itunes:category>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        r#"<enclosure url="https://techmercenaries.com/podcast/episode-1/pilot.mp3" length="128" type="audio/mpeg"/>"#
+    ));
+    assert!(file_contains!(public, "podcast/rss.xml", "<itunes:duration>1234</itunes:duration>"));
+    assert!(file_contains!(public, "podcast/rss.xml", "<podcast:season>2</podcast:season>"));
+    assert!(file_contains!(public, "podcast/rss.xml", "<itunes:explicit>true</itunes:explicit>"));
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        r#"<enclosure url="https://techmercenaries.com/trailer.ogg" length="64" type="audio/ogg"/>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "podcast/rss.xml",
+        "<itunes:episodeType>trailer</itunes:episodeType>"
+    ));
+    assert!(file_exists!(public, "podcast/episode-1/pilot.mp3"));
+    // Other feeds are left alone
+    assert!(!file_contains!(public, "rss.xml", "itunes"));
+}
+
+#[test]
+fn can_render_responsive_markdown_images() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let page_path = site_path.join("content").join("posts").join("with-assets").join("index.md");
+    let content = std::fs::read_to_string(&page_path).unwrap();
+    std::fs::write(&page_path, format!("{}\n![The Zola logo](zola.png)\n", content)).unwrap();
+
+    let (_, _public_dir, public) = build_site_with_setup(&site_path, |mut site| {
+        site.config.markdown.images.responsive = true;
+        site.config.markdown.images.widths = vec![100, 200, 400];
+        site.config.markdown.images.formats = vec!["webp".to_string(), "auto".to_string()];
+        (site, true)
+    });
+
+    let html = std::fs::read_to_string(public.join("posts/with-assets/index.html")).unwrap();
+    assert!(html.contains(r#"<picture><source type="image/webp" srcset="https://techmercenaries.com/processed_images/zola."#));
+    // The logo is 256px wide so it is not upscaled to 400px
+    assert!(html.contains(r#" 200w" sizes="100vw" width="200" height="200" alt="The Zola logo" loading="lazy" decoding="async">"#));
+    assert!(!html.contains("400w"));
+    let processed = std::fs::read_dir(public.join("processed_images")).unwrap().count();
+    assert_eq!(processed, 4);
+}
+
 #[test]
 fn can_build_search_index() {
     let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
@@ -880,3 +1050,220 @@ fn can_find_site_and_page_authors() {
 fn current_path(path: &str) -> String {
     format!("[current_path]({})", path)
 }
+
+#[test]
+fn can_build_site_incrementally() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let public = tmp_dir.path().join("public");
+    let cache_dir = tmp_dir.path().join("cache");
+
+    let build = |incremental: bool, drafts: bool| {
+        let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+        site.set_output_path(&public);
+        if drafts {
+            site.include_drafts();
+        }
+        site.enable_build_cache(&cache_dir, incremental);
+        site.load().unwrap();
+        site.build().expect("Couldn't build the site");
+        site.build_cache().unwrap().files_written()
+    };
+    let modified_times = || {
+        libs::walkdir::WalkDir::new(&public)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file())
+            .map(|e| (e.path().to_path_buf(), e.metadata().unwrap().modified().unwrap()))
+            .collect::<HashMap<_, _>>()
+    };
+
+    assert!(build(false, true) > 0);
+    assert!(file_exists!(public, "posts/draft/index.html"));
+    let before = modified_times();
+
+    // Nothing changed: nothing is written
+    assert_eq!(build(true, true), 0);
+    assert_eq!(before, modified_times());
+
+    // Outputs that are not generated anymore are removed, the others are kept
+    assert!(build(true, false) > 0);
+    assert!(!file_exists!(public, "posts/draft/index.html"));
+    assert!(file_exists!(public, "posts/python/index.html"));
+    assert!(file_exists!(public, "posts/with-assets/index.html"));
+    assert!(!file_contains!(public, "sitemap.xml", "draft"));
+}
+
+#[test]
+fn incremental_builds_render_pages_again_after_template_or_config_changes() {
+    let (tmp_dir, site_path) = copy_site("test_site");
+    let public = tmp_dir.path().join("public");
+    let cache_dir = tmp_dir.path().join("cache");
+    let build = || {
+        let mut site = Site::new(&site_path, site_path.join("config.toml")).unwrap();
+        site.set_output_path(&public);
+        site.enable_build_cache(&cache_dir, true);
+        site.load().unwrap();
+        site.build().expect("Couldn't build the site");
+    };
+    build();
+
+    let template = site_path.join("templates").join("page.html");
+    let content = std::fs::read_to_string(&template).unwrap();
+    let content =
+        content.replace("{{ page.title | safe }}", "{{ page.title }} - {{ config.title }}");
+    std::fs::write(&template, content).unwrap();
+    build();
+    assert!(file_contains!(public, "posts/python/index.html", "- My Integration Testing site"));
+
+    let config = site_path.join("config.toml");
+    let content = std::fs::read_to_string(&config).unwrap();
+    std::fs::write(&config, content.replace("My Integration Testing site", "Changed")).unwrap();
+    build();
+    assert!(file_contains!(public, "posts/python/index.html", "- Changed"));
+}
+
+#[test]
+fn can_rebuild_only_outputs_affected_by_a_change() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let mut site = serve_site_with_setup(&site_path, |site| site);
+    let rendered = |path: &str| {
+        site::SITE_CONTENT
+            .read()
+            .unwrap()
+            .get(libs::relative_path::RelativePath::new(path))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    // A shortcode only re-renders the pages using it
+    let shortcode = site_path.join("templates").join("shortcodes").join("youtube.html");
+    std::fs::write(&shortcode, "CHANGED YOUTUBE").unwrap();
+    let outputs = site.rebuild_after_template_changes(&[&shortcode]).unwrap().unwrap();
+    assert!(outputs.contains(&"/posts/simple/".to_string()));
+    assert!(!outputs.contains(&"/rebuild/first/".to_string()));
+    assert!(rendered("posts/simple").contains("CHANGED YOUTUBE"));
+
+    // A page re-renders itself and the sections listing it, including through `get_section`
+    let page = site_path.join("content").join("rebuild").join("first.md");
+    let content = std::fs::read_to_string(&page).unwrap();
+    let new_title = content.replace("title = ", "title = \"Changed\" #");
+    std::fs::write(&page, new_title).unwrap();
+    let outputs = site.rebuild_after_content_change(&page).unwrap().unwrap();
+    assert!(outputs.contains(&"/rebuild/first/".to_string()));
+    assert!(outputs.contains(&"/rebuild/".to_string()));
+    assert!(!outputs.contains(&"/posts/simple/".to_string()));
+    assert!(rendered("rebuild").contains("<h1>Changed</h1>"));
+
+    // A new template can change which template is used so it requires a full rebuild
+    let template = site_path.join("templates").join("new.html");
+    std::fs::write(&template, "New").unwrap();
+    assert!(site.rebuild_after_template_changes(&[&template]).unwrap().is_none());
+}
+
+#[test]
+fn images_are_processed_when_rebuilding_parts_of_the_site() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let mut site = serve_site_with_setup(&site_path, |site| site);
+    let processed_images = site_path.join("static").join("processed_images");
+    assert!(!processed_images.exists());
+
+    let template = site_path.join("templates").join("page.html");
+    let content = std::fs::read_to_string(&template).unwrap();
+    let resize = r#"{% block content %}
+    {% set image = resize_image(path="posts/with-assets/zola.png", width=10, op="fit_width") %}"#;
+    std::fs::write(&template, content.replace("{% block content %}", resize)).unwrap();
+    assert!(site.rebuild_after_template_changes(&[&template]).unwrap().is_some());
+    let processed: Vec<_> = std::fs::read_dir(&processed_images)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|f| f.ends_with(".png"))
+        .collect();
+    assert_eq!(processed.len(), 1);
+}
+
+#[test]
+fn title_changes_rebuild_the_site_with_wikilinks() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let mut site = serve_site_with_setup(&site_path, |mut site| {
+        site.config.markdown.extensions.wikilinks = true;
+        site
+    });
+
+    // Changing the content only re-renders what uses the page
+    let page = site_path.join("content").join("rebuild").join("first.md");
+    let content = std::fs::read_to_string(&page).unwrap();
+    std::fs::write(&page, format!("{}\nMore content", content)).unwrap();
+    assert!(site.rebuild_after_content_change(&page).unwrap().is_some());
+
+    // But pages can link to it by its title so they have to be rendered again when it changes
+    let content = std::fs::read_to_string(&page).unwrap();
+    std::fs::write(&page, content.replace("title = ", "title = \"Changed\" #")).unwrap();
+    assert!(site.rebuild_after_content_change(&page).unwrap().is_none());
+
+    // Same for sections
+    let section = site_path.join("content").join("rebuild").join("_index.md");
+    let content = std::fs::read_to_string(&section).unwrap();
+    std::fs::write(&section, content.replacen("+++\n", "+++\ntitle = \"Changed\"\n", 1)).unwrap();
+    assert!(site.rebuild_after_content_change(&section).unwrap().is_none());
+}
+
+#[test]
+fn skips_scheduled_and_expired_pages() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let posts = site_path.join("content").join("posts");
+    std::fs::write(posts.join("scheduled.md"), "+++\npublish_date = 2999-01-01\n+++\n").unwrap();
+    std::fs::write(posts.join("expired.md"), "+++\nexpiry_date = 2000-01-01\n+++\n").unwrap();
+    std::fs::write(posts.join("current.md"), "+++\nexpiry_date = 2999-01-01\n+++\n").unwrap();
+
+    let mut site = Site::new(&site_path, site_path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    {
+        let library = site.library.read().unwrap();
+        assert!(!library.pages.contains_key(&posts.join("scheduled.md")));
+        assert!(!library.pages.contains_key(&posts.join("expired.md")));
+        assert!(library.pages.contains_key(&posts.join("current.md")));
+    }
+    assert!(site
+        .skipped_pages
+        .contains(&(posts.join("scheduled.md"), site::SkipReason::Scheduled)));
+    assert!(site.skipped_pages.contains(&(posts.join("expired.md"), site::SkipReason::Expired)));
+    assert!(site.skipped_pages.iter().any(|(_, r)| *r == site::SkipReason::Draft));
+
+    site.include_future();
+    site.include_expired();
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    assert!(library.pages.contains_key(&posts.join("scheduled.md")));
+    assert!(library.pages.contains_key(&posts.join("expired.md")));
+    assert!(site.skipped_pages.iter().all(|(_, r)| *r == site::SkipReason::Draft));
+}
+
+#[test]
+fn taxonomy_directory_with_an_index_is_a_section() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let authors = site_path.join("content").join("podcast-authors");
+    std::fs::create_dir_all(&authors).unwrap();
+    std::fs::write(authors.join("_index.md"), "+++\ntitle = \"Authors\"\n+++\n").unwrap();
+    std::fs::write(authors.join("some-author.md"), "+++\ntitle = \"Some author\"\n+++\n").unwrap();
+
+    let mut site = Site::new(&site_path, site_path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    assert!(library.sections.contains_key(&authors.join("_index.md")));
+    assert!(library.pages.contains_key(&authors.join("some-author.md")));
+}
+
+#[test]
+fn lists_files_of_terms_directories_not_matching_any_term() {
+    let (_tmp_dir, site_path) = copy_site("test_site");
+    let mut site = Site::new(&site_path, site_path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    assert!(site.unknown_term_files.is_empty());
+
+    let typo = site_path.join("content").join("categories").join("a-categroy.md");
+    std::fs::write(&typo, "+++\ntitle = \"A category\"\n+++\n").unwrap();
+    site.load().unwrap();
+    assert_eq!(site.unknown_term_files, vec![typo]);
+}
@@ -142,6 +142,7 @@ impl TeraFn for GetPage {
         get_path_with_lang(&path, &lang, &self.default_lang, &self.supported_languages).and_then(
             |path_with_lang| {
                 let full_path = self.base_path.join(path_with_lang.as_ref());
+                utils::dependencies::record_content(&full_path);
                 let library = self.library.read().unwrap();
 
                 match library.pages.get(&full_path) {
@@ -199,6 +200,7 @@ impl TeraFn for GetSection {
         get_path_with_lang(&path, &lang, self.default_lang.as_str(), &self.supported_languages)
             .and_then(|path_with_lang| {
                 let full_path = self.base_path.join(path_with_lang.as_ref());
+                utils::dependencies::record_content(&full_path);
                 let library = self.library.read().unwrap();
 
                 match library.sections.get(&full_path) {
@@ -259,6 +261,7 @@ impl TeraFn for GetTaxonomy {
             optional_arg!(String, args.get("lang"), "`get_taxonomy`: `lang` must be a string")
                 .unwrap_or_else(|| self.default_lang.clone());
 
+        utils::dependencies::record_taxonomy(&kind, &lang);
         match (self.taxonomies.get(&format!("{}-{}", kind, lang)), required) {
             (Some(t), _) => Ok(to_value(t.to_serialized(&self.library.read().unwrap())).unwrap()),
             (None, false) => Ok(Value::Null),
@@ -317,6 +320,7 @@ impl TeraFn for GetTaxonomyTerm {
             optional_arg!(String, args.get("lang"), "`get_taxonomy_term`: `lang` must be a string")
                 .unwrap_or_else(|| self.default_lang.clone());
 
+        utils::dependencies::record_taxonomy(&kind, &lang);
         let tax: &Taxonomy = match (self.taxonomies.get(&format!("{}-{}", kind, lang)), required) {
             (Some(t), _) => t,
             (None, false) => {
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use config::{Config, SocialCardTemplate};
 use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
 
 use crate::global_fns::helpers::search_for_file;
@@ -60,8 +61,81 @@ impl TeraFn for ResizeImage {
                 return Err("`resize_image`: `quality` must be in range 1-100".to_string().into());
             }
         }
-        let resize_op = imageproc::ResizeOperation::from_args(&op, width, height)
-            .map_err(|e| format!("`resize_image`: {}", e))?;
+        let speed =
+            optional_arg!(u8, args.get("speed"), "`resize_image`: `speed` must be a number");
+        if let Some(speed) = speed {
+            if speed == 0 || speed > 10 {
+                return Err("`resize_image`: `speed` must be in range 1-10".to_string().into());
+            }
+        }
+        let placeholder = optional_arg!(
+            bool,
+            args.get("placeholder"),
+            "`resize_image`: `placeholder` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let first_frame = optional_arg!(
+            bool,
+            args.get("first_frame"),
+            "`resize_image`: `first_frame` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let focal_point = optional_arg!(
+            Vec<f64>,
+            args.get("focal_point"),
+            "`resize_image`: `focal_point` must be an array of two numbers"
+        );
+        let gravity = optional_arg!(
+            String,
+            args.get("gravity"),
+            "`resize_image`: `gravity` must be a string"
+        );
+        let focal_point = match (focal_point, gravity) {
+            (Some(_), Some(_)) => {
+                return Err(
+                    "`resize_image`: only one of `focal_point` and `gravity` can be used".into()
+                );
+            }
+            (Some(point), None) => match point[..] {
+                [x, y] => Some(imageproc::FocalPoint::new(x, y)),
+                _ => {
+                    return Err(
+                        "`resize_image`: `focal_point` must be an array of two numbers".into()
+                    );
+                }
+            },
+            (None, Some(gravity)) => Some(imageproc::FocalPoint::from_gravity(&gravity)),
+            (None, None) => None,
+        }
+        .transpose()
+        .map_err(|e| format!("`resize_image`: {}", e))?;
+        let crop = optional_arg!(
+            Vec<u32>,
+            args.get("crop"),
+            "`resize_image`: `crop` must be an array of four non-negative integers"
+        );
+        let crop = match crop.as_deref() {
+            Some(&[x, y, w, h]) => Some((x, y, w, h)),
+            Some(_) => {
+                return Err(
+                    "`resize_image`: `crop` must be an array of four non-negative integers".into(),
+                );
+            }
+            None => None,
+        };
+        let resize_op =
+            imageproc::ResizeOperation::from_args(&op, width, height, focal_point, crop)
+                .map_err(|e| format!("`resize_image`: {}", e))?;
+        let filters = optional_arg!(
+            Vec<String>,
+            args.get("filters"),
+            "`resize_image`: `filters` must be an array of strings"
+        )
+        .unwrap_or_default()
+        .iter()
+        .map(|f| imageproc::Filter::from_arg(f))
+        .collect::<errors::Result<Vec<_>>>()
+        .map_err(|e| format!("`resize_image`: {}", e))?;
         let mut imageproc = self.imageproc.lock().unwrap();
         let (file_path, unified_path) =
             match search_for_file(&self.base_path, &path, &self.theme, &self.output_path)
@@ -73,11 +147,118 @@ impl TeraFn for ResizeImage {
                 }
             };
 
+        let placeholder = if placeholder {
+            let placeholder =
+                imageproc.placeholder(&file_path).map_err(|e| format!("`resize_image`: {}", e))?;
+            Some(placeholder)
+        } else {
+            None
+        };
         let response = imageproc
-            .enqueue(resize_op, unified_path, file_path, &format, quality)
+            .enqueue(
+                resize_op,
+                filters,
+                unified_path,
+                file_path,
+                &format,
+                quality,
+                speed,
+                first_frame,
+            )
             .map_err(|e| format!("`resize_image`: {}", e))?;
 
-        to_value(response).map_err(Into::into)
+        let mut value = to_value(response)?;
+        if let Some(placeholder) = placeholder {
+            value
+                .as_object_mut()
+                .unwrap()
+                .insert("placeholder".to_string(), to_value(placeholder)?);
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug)]
+pub struct ResponsiveImage {
+    /// The base path of the Zola site
+    base_path: PathBuf,
+    theme: Option<String>,
+    imageproc: Arc<Mutex<imageproc::Processor>>,
+    output_path: PathBuf,
+}
+
+impl ResponsiveImage {
+    pub fn new(
+        base_path: PathBuf,
+        imageproc: Arc<Mutex<imageproc::Processor>>,
+        theme: Option<String>,
+        output_path: PathBuf,
+    ) -> Self {
+        Self { base_path, imageproc, theme, output_path }
+    }
+}
+
+impl TeraFn for ResponsiveImage {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`responsive_image` requires a `path` argument with a string value"
+        );
+        let widths = required_arg!(
+            Vec<u32>,
+            args.get("widths"),
+            "`responsive_image` requires a `widths` argument with an array of non-negative integers"
+        );
+        let formats = optional_arg!(
+            Vec<String>,
+            args.get("formats"),
+            "`responsive_image`: `formats` must be an array of strings"
+        )
+        .unwrap_or_else(|| vec![DEFAULT_FMT.to_string()]);
+        let sizes = optional_arg!(
+            String,
+            args.get("sizes"),
+            "`responsive_image`: `sizes` must be a string"
+        );
+        let alt =
+            optional_arg!(String, args.get("alt"), "`responsive_image`: `alt` must be a string")
+                .unwrap_or_default();
+        let quality = optional_arg!(
+            u8,
+            args.get("quality"),
+            "`responsive_image`: `quality` must be a number"
+        );
+        if let Some(quality) = quality {
+            if quality == 0 || quality > 100 {
+                return Err("`responsive_image`: `quality` must be in range 1-100".into());
+            }
+        }
+        let speed =
+            optional_arg!(u8, args.get("speed"), "`responsive_image`: `speed` must be a number");
+
+        let mut imageproc = self.imageproc.lock().unwrap();
+        let (file_path, unified_path) =
+            match search_for_file(&self.base_path, &path, &self.theme, &self.output_path)
+                .map_err(|e| format!("`responsive_image`: {}", e))?
+            {
+                Some(f) => f,
+                None => {
+                    return Err(format!("`responsive_image`: Cannot find file: {}", path).into());
+                }
+            };
+
+        let options = imageproc::ResponsiveOptions { widths, formats, sizes, quality, speed };
+        let response = imageproc
+            .enqueue_responsive(unified_path, file_path, &options)
+            .map_err(|e| format!("`responsive_image`: {}", e))?;
+
+        let mut value = to_value(&response)?;
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("html".to_string(), Value::String(response.to_html(&alt)));
+        Ok(value)
     }
 }
 
@@ -85,14 +266,26 @@ impl TeraFn for ResizeImage {
 pub struct GetImageMetadata {
     /// The base path of the Zola site
     base_path: PathBuf,
+    imageproc: Arc<Mutex<imageproc::Processor>>,
     theme: Option<String>,
     result_cache: Arc<Mutex<HashMap<String, Value>>>,
     output_path: PathBuf,
 }
 
 impl GetImageMetadata {
-    pub fn new(base_path: PathBuf, theme: Option<String>, output_path: PathBuf) -> Self {
-        Self { base_path, result_cache: Arc::new(Mutex::new(HashMap::new())), theme, output_path }
+    pub fn new(
+        base_path: PathBuf,
+        imageproc: Arc<Mutex<imageproc::Processor>>,
+        theme: Option<String>,
+        output_path: PathBuf,
+    ) -> Self {
+        Self {
+            base_path,
+            imageproc,
+            result_cache: Arc::new(Mutex::new(HashMap::new())),
+            theme,
+            output_path,
+        }
     }
 }
 
@@ -109,6 +302,18 @@ impl TeraFn for GetImageMetadata {
             "`get_image_metadata`: `allow_missing` must be a boolean (true or false)"
         )
         .unwrap_or(false);
+        let placeholder = optional_arg!(
+            bool,
+            args.get("placeholder"),
+            "`get_image_metadata`: `placeholder` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let exif = optional_arg!(
+            bool,
+            args.get("exif"),
+            "`get_image_metadata`: `exif` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
 
         let (src_path, unified_path) =
             match search_for_file(&self.base_path, &path, &self.theme, &self.output_path)
@@ -124,22 +329,118 @@ impl TeraFn for GetImageMetadata {
             };
 
         let mut cache = self.result_cache.lock().expect("result cache lock");
-        if let Some(cached_result) = cache.get(&unified_path) {
-            return Ok(cached_result.clone());
-        }
+        let mut out = match cache.get(&unified_path) {
+            Some(cached_result) => cached_result.clone(),
+            None => {
+                let response = imageproc::read_image_metadata(&src_path)
+                    .map_err(|e| format!("`get_image_metadata`: {}", e))?;
+                let out = to_value(response).unwrap();
+                cache.insert(unified_path, out.clone());
+                out
+            }
+        };
 
-        let response = imageproc::read_image_metadata(src_path)
-            .map_err(|e| format!("`resize_image`: {}", e))?;
-        let out = to_value(response).unwrap();
-        cache.insert(unified_path, out.clone());
+        if placeholder {
+            let placeholder = self
+                .imageproc
+                .lock()
+                .unwrap()
+                .placeholder(&src_path)
+                .map_err(|e| format!("`get_image_metadata`: {}", e))?;
+            out.as_object_mut().unwrap().insert("placeholder".to_string(), to_value(placeholder)?);
+        }
+        if exif {
+            let exif = self.imageproc.lock().unwrap().exif_data(&src_path);
+            out.as_object_mut().unwrap().insert("exif".to_string(), to_value(exif)?);
+        }
 
         Ok(out)
     }
 }
 
+#[derive(Debug)]
+pub struct SocialCard {
+    /// The base path of the Zola site
+    base_path: PathBuf,
+    config: Config,
+    imageproc: Arc<Mutex<imageproc::Processor>>,
+    output_path: PathBuf,
+}
+
+impl SocialCard {
+    pub fn new(
+        base_path: PathBuf,
+        config: Config,
+        imageproc: Arc<Mutex<imageproc::Processor>>,
+        output_path: PathBuf,
+    ) -> Self {
+        Self { base_path, config, imageproc, output_path }
+    }
+
+    fn find_file(&self, path: &Option<String>, kind: &str) -> Result<Option<PathBuf>> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        match search_for_file(&self.base_path, path, &self.config.theme, &self.output_path)
+            .map_err(|e| format!("`social_card`: {}", e))?
+        {
+            Some((file_path, _)) => Ok(Some(file_path)),
+            None => Err(format!("`social_card`: Cannot find {}: {}", kind, path).into()),
+        }
+    }
+}
+
+static DEFAULT_CARD_TEMPLATE: &str = "default";
+
+impl TeraFn for SocialCard {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let title = required_arg!(
+            String,
+            args.get("title"),
+            "`social_card` requires a `title` argument with a string value"
+        );
+        let metadata = optional_arg!(
+            Vec<String>,
+            args.get("metadata"),
+            "`social_card`: `metadata` must be an array of strings"
+        )
+        .unwrap_or_default();
+        let template_name = optional_arg!(
+            String,
+            args.get("template"),
+            "`social_card`: `template` must be a string"
+        )
+        .unwrap_or_else(|| DEFAULT_CARD_TEMPLATE.to_string());
+
+        let template = match self.config.image_processing.social_cards.get(&template_name) {
+            Some(template) => template.clone(),
+            None if template_name == DEFAULT_CARD_TEMPLATE => SocialCardTemplate::default(),
+            None => {
+                return Err(format!(
+                    "`social_card`: There is no `{}` template in `[image_processing.social_cards]`",
+                    template_name
+                )
+                .into());
+            }
+        };
+        let background_path = self.find_file(&template.background, "background image")?;
+        let font_path = self.find_file(&template.font, "font")?;
+
+        let card = imageproc::SocialCard { title, metadata, template, background_path, font_path };
+        let response = self
+            .imageproc
+            .lock()
+            .unwrap()
+            .enqueue_social_card(card)
+            .map_err(|e| format!("`social_card`: {}", e))?;
+        to_value(response).map_err(|e| e.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{GetImageMetadata, ResizeImage};
+    use super::{GetImageMetadata, ResizeImage, ResponsiveImage, SocialCard};
 
     use std::collections::HashMap;
     use std::fs::{copy, create_dir_all};
@@ -259,7 +560,13 @@ mod tests {
     fn can_get_image_metadata() {
         let dir = create_dir_with_image();
 
-        let static_fn = GetImageMetadata::new(dir.path().to_path_buf(), None, PathBuf::new());
+        let imageproc = imageproc::Processor::new(dir.path().to_path_buf(), &Config::default());
+        let static_fn = GetImageMetadata::new(
+            dir.path().to_path_buf(),
+            Arc::new(Mutex::new(imageproc)),
+            None,
+            PathBuf::new(),
+        );
 
         // Let's test a few scenarii
         let mut args = HashMap::new();
@@ -298,5 +605,121 @@ mod tests {
         assert_eq!(data["width"], to_value(300).unwrap());
         assert_eq!(data["format"], to_value("jpg").unwrap());
         assert_eq!(data["mime"], to_value("image/jpeg").unwrap());
+        assert!(data.get("placeholder").is_none());
+
+        // 5. asking for a placeholder as well
+        args.insert("placeholder".to_string(), to_value(true).unwrap());
+        let data = static_fn.call(&args).unwrap().as_object().unwrap().clone();
+        assert_eq!(data["width"], to_value(300).unwrap());
+        let placeholder = data["placeholder"].as_object().unwrap();
+        assert!(placeholder["preview"].as_str().unwrap().starts_with("data:image/jpeg;base64,"));
+        assert!(placeholder["color"].as_str().unwrap().starts_with('#'));
+        assert!(!placeholder["blurhash"].as_str().unwrap().is_empty());
+
+        // 6. and its EXIF data, which it doesn't have
+        args.insert("exif".to_string(), to_value(true).unwrap());
+        let data = static_fn.call(&args).unwrap().as_object().unwrap().clone();
+        assert!(data["exif"].is_null());
+    }
+
+    #[test]
+    fn can_make_responsive_image() {
+        let dir = create_dir_with_image();
+        let imageproc = imageproc::Processor::new(dir.path().to_path_buf(), &Config::default());
+        let imageproc = Arc::new(Mutex::new(imageproc));
+
+        let static_fn =
+            ResponsiveImage::new(dir.path().to_path_buf(), imageproc.clone(), None, PathBuf::new());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("static/gutenberg.jpg").unwrap());
+        // The image is 300px wide so 400 is skipped
+        args.insert("widths".to_string(), to_value([200, 100, 400]).unwrap());
+        args.insert("formats".to_string(), to_value(["webp", "auto"]).unwrap());
+        args.insert("alt".to_string(), to_value("Gutenberg \"portrait\"").unwrap());
+        let data = static_fn.call(&args).unwrap();
+        assert_eq!(imageproc.lock().unwrap().num_img_ops(), 4);
+
+        assert_eq!(data["width"], to_value(200).unwrap());
+        assert_eq!(data["height"], to_value(253).unwrap());
+        assert_eq!(data["orig_width"], to_value(300).unwrap());
+        assert_eq!(data["sizes"], to_value("100vw").unwrap());
+        let sources = data["sources"].as_array().unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0]["mime"], to_value("image/webp").unwrap());
+        assert_eq!(sources[1]["format"], to_value("jpg").unwrap());
+        let fallback = sources[1]["images"].as_array().unwrap();
+        assert_eq!(data["src"], fallback[1]["url"]);
+        assert_eq!(
+            sources[1]["srcset"],
+            to_value(format!(
+                "{} 100w, {} 200w",
+                fallback[0]["url"].as_str().unwrap(),
+                fallback[1]["url"].as_str().unwrap()
+            ))
+            .unwrap()
+        );
+
+        let html = data["html"].as_str().unwrap();
+        assert!(html.starts_with(r#"<picture><source type="image/webp" srcset=""#));
+        assert!(html.contains(r#"width="200" height="253" alt="Gutenberg &quot;portrait&quot;""#));
+        assert!(html.ends_with("</picture>"));
+
+        args.insert("formats".to_string(), to_value(Vec::<String>::new()).unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_make_social_card() {
+        let dir = create_dir_with_image();
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+[image_processing.social_cards.photo]
+background = "gutenberg.jpg"
+width = 600
+height = 315
+[image_processing.social_cards.broken]
+text_color = "white"
+"#,
+        )
+        .unwrap();
+        let imageproc = imageproc::Processor::new(dir.path().to_path_buf(), &config);
+        let imageproc = Arc::new(Mutex::new(imageproc));
+        let static_fn =
+            SocialCard::new(dir.path().to_path_buf(), config, imageproc.clone(), PathBuf::new());
+
+        // 1. with the default template
+        let mut args = HashMap::new();
+        args.insert("title".to_string(), to_value("Hello world").unwrap());
+        args.insert("metadata".to_string(), to_value(["2024-01-01", "My blog"]).unwrap());
+        let data = static_fn.call(&args).unwrap();
+        assert!(data["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://example.com/processed_images/social_card."));
+        assert!(data["static_path"].as_str().unwrap().ends_with(".png"));
+        assert_eq!(data["width"], to_value(1200).unwrap());
+        assert_eq!(data["height"], to_value(630).unwrap());
+
+        // 2. the same card isn't rendered twice, another title is another card
+        assert_eq!(static_fn.call(&args).unwrap(), data);
+        args.insert("title".to_string(), to_value("Hello again").unwrap());
+        assert_ne!(static_fn.call(&args).unwrap()["url"], data["url"]);
+
+        // 3. with a background image from static
+        args.insert("template".to_string(), to_value("photo").unwrap());
+        let photo = static_fn.call(&args).unwrap();
+        assert_eq!(photo["width"], to_value(600).unwrap());
+
+        imageproc.lock().unwrap().do_process().unwrap();
+        let path = dir.path().join(photo["static_path"].as_str().unwrap());
+        let meta = imageproc::read_image_metadata(path).unwrap();
+        assert_eq!((meta.width, meta.height), (600, 315));
+
+        // 4. errors
+        args.insert("template".to_string(), to_value("unknown").unwrap());
+        assert!(static_fn.call(&args).unwrap_err().to_string().contains("no `unknown` template"));
+        args.insert("template".to_string(), to_value("broken").unwrap());
+        assert!(static_fn.call(&args).is_err());
     }
 }
@@ -310,6 +310,10 @@ impl TeraFn for LoadData {
             (Ok(Some(data_source)), _) => data_source,
         };
 
+        if let DataSource::Path(ref path) = data_source {
+            utils::dependencies::record_file(path);
+        }
+
         let file_format = get_output_format_from_args(format_arg, &data_source)?;
         let cache_key = data_source.get_cache_key(
             &file_format,
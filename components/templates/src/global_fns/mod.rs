@@ -11,5 +11,5 @@ mod load_data;
 pub use self::content::{GetPage, GetSection, GetTaxonomy, GetTaxonomyTerm, GetTaxonomyUrl};
 pub use self::files::{GetHash, GetUrl};
 pub use self::i18n::Trans;
-pub use self::images::{GetImageMetadata, ResizeImage};
+pub use self::images::{GetImageMetadata, ResizeImage, ResponsiveImage, SocialCard};
 pub use self::load_data::LoadData;
@@ -10,12 +10,17 @@ use libs::tera::{Context, Tera};
 use errors::{bail, Context as ErrorContext, Result};
 use utils::templates::rewrite_theme_paths;
 
+/// The templates not matched by the globs, which are only loaded with these names so that
+/// other `.txt` or `.json` files in `templates` are not parsed
+const NAMED_TEMPLATES: [&str; 2] = ["robots.txt", "feed.json"];
+
 pub static ZOLA_TERA: Lazy<Tera> = Lazy::new(|| {
     let mut tera = Tera::default();
     tera.add_raw_templates(vec![
         ("__zola_builtins/404.html", include_str!("builtins/404.html")),
         ("__zola_builtins/atom.xml", include_str!("builtins/atom.xml")),
         ("__zola_builtins/rss.xml", include_str!("builtins/rss.xml")),
+        ("__zola_builtins/feed.json", include_str!("builtins/feed.json")),
         ("__zola_builtins/sitemap.xml", include_str!("builtins/sitemap.xml")),
         ("__zola_builtins/robots.txt", include_str!("builtins/robots.txt")),
         (
@@ -23,6 +28,14 @@ pub static ZOLA_TERA: Lazy<Tera> = Lazy::new(|| {
             include_str!("builtins/split_sitemap_index.xml"),
         ),
         ("__zola_builtins/anchor-link.html", include_str!("builtins/anchor-link.html")),
+        (
+            "__zola_builtins/markdown/responsive_image.html",
+            include_str!("builtins/markdown/responsive_image.html"),
+        ),
+        (
+            "__zola_builtins/markdown/admonition.html",
+            include_str!("builtins/markdown/admonition.html"),
+        ),
         ("internal/alias.html", include_str!("builtins/internal/alias.html")),
     ])
     .unwrap();
@@ -68,20 +81,56 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
         rewrite_theme_paths(&mut tera_theme, theme);
 
         // TODO: add tests for theme-provided robots.txt (https://github.com/getzola/zola/pull/1722)
-        if theme_path.join("templates").join("robots.txt").exists() {
-            tera_theme.add_template_file(
-                theme_path.join("templates").join("robots.txt"),
-                Some("robots.txt"),
-            )?;
+        for name in NAMED_TEMPLATES {
+            if theme_path.join("templates").join(name).exists() {
+                tera_theme
+                    .add_template_file(theme_path.join("templates").join(name), Some(name))?;
+            }
         }
         tera.extend(&tera_theme)?;
     }
     tera.extend(&ZOLA_TERA)?;
     tera.build_inheritance_chains()?;
+    add_named_templates(&mut tera, path)?;
+
+    Ok(tera)
+}
+
+/// Reloads the templates of the site at the given path, used when they change in `zola serve`
+pub fn reload_tera(tera: &mut Tera, path: &Path) -> Result<()> {
+    tera.full_reload()?;
+    // The named templates are not part of the glob so they are not reloaded
+    add_named_templates(tera, path)
+}
 
-    if path.join("templates").join("robots.txt").exists() {
-        tera.add_template_file(path.join("templates").join("robots.txt"), Some("robots.txt"))?;
+fn add_named_templates(tera: &mut Tera, path: &Path) -> Result<()> {
+    for name in NAMED_TEMPLATES {
+        if path.join("templates").join(name).exists() {
+            tera.add_template_file(path.join("templates").join(name), Some(name))?;
+        }
     }
+    Ok(())
+}
 
-    Ok(tera)
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, write};
+
+    use config::Config;
+    use tempfile::tempdir;
+
+    use super::load_tera;
+
+    #[test]
+    fn only_loads_the_json_feed_template_by_name() {
+        let dir = tempdir().unwrap();
+        create_dir(dir.path().join("templates")).unwrap();
+        write(dir.path().join("templates").join("feed.json"), "{{ config.title }}").unwrap();
+        // Not valid Tera, but not a template either
+        write(dir.path().join("templates").join("data.json"), "{\"a\": \"{{\"}").unwrap();
+
+        let tera = load_tera(dir.path(), &Config::default()).unwrap();
+        assert!(tera.get_template_names().any(|name| name == "feed.json"));
+        assert!(!tera.get_template_names().any(|name| name == "data.json"));
+    }
 }
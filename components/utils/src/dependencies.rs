@@ -0,0 +1,116 @@
+//! Records what a render used (templates, data files, content lookups) so `zola serve` can
+//! re-render only the outputs affected by a change.
+//!
+//! Rendering a single output is synchronous so the recording is done per thread: `track` runs
+//! a closure and collects everything recorded on the current thread while it runs.
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Everything a render depended on
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Dependencies {
+    /// The names of the Tera templates rendered directly, shortcodes included
+    pub templates: HashSet<String>,
+    /// The files loaded with `load_data`
+    pub files: HashSet<PathBuf>,
+    /// The full path of the pages and sections fetched with `get_page`/`get_section`
+    pub content: HashSet<PathBuf>,
+    /// The taxonomies fetched with `get_taxonomy`/`get_taxonomy_term`, as `{name}-{lang}`
+    pub taxonomies: HashSet<String>,
+}
+
+impl Dependencies {
+    pub fn extend(&mut self, other: Dependencies) {
+        self.templates.extend(other.templates);
+        self.files.extend(other.files);
+        self.content.extend(other.content);
+        self.taxonomies.extend(other.taxonomies);
+    }
+}
+
+thread_local! {
+    // A stack so tracking can be nested, eg rendering markdown from a template
+    static TRACKED: RefCell<Vec<Dependencies>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs `f` and returns its result along with the dependencies recorded while it ran.
+/// If we are already tracking on this thread, the outer tracking also gets those dependencies.
+pub fn track<T>(f: impl FnOnce() -> T) -> (T, Dependencies) {
+    TRACKED.with(|t| t.borrow_mut().push(Dependencies::default()));
+    let res = f();
+    let deps = TRACKED.with(|t| {
+        let mut stack = t.borrow_mut();
+        let deps = stack.pop().unwrap_or_default();
+        if let Some(outer) = stack.last_mut() {
+            outer.extend(deps.clone());
+        }
+        deps
+    });
+    (res, deps)
+}
+
+fn record(f: impl FnOnce(&mut Dependencies)) {
+    TRACKED.with(|t| {
+        if let Some(current) = t.borrow_mut().last_mut() {
+            f(current);
+        }
+    });
+}
+
+/// Adds dependencies tracked elsewhere, eg on another thread, to the ones currently tracked
+pub fn record_dependencies(dependencies: Dependencies) {
+    record(|d| d.extend(dependencies));
+}
+
+pub fn record_template(name: &str) {
+    record(|d| {
+        d.templates.insert(name.to_string());
+    });
+}
+
+pub fn record_file(path: &Path) {
+    record(|d| {
+        d.files.insert(path.to_path_buf());
+    });
+}
+
+pub fn record_content(path: &Path) {
+    record(|d| {
+        d.content.insert(path.to_path_buf());
+    });
+}
+
+pub fn record_taxonomy(name: &str, lang: &str) {
+    record(|d| {
+        d.taxonomies.insert(format!("{}-{}", name, lang));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_while_tracking() {
+        record_template("ignored.html");
+        let (_, deps) = track(|| {
+            record_template("page.html");
+            record_file(Path::new("data.toml"));
+        });
+        assert_eq!(deps.templates, HashSet::from(["page.html".to_string()]));
+        assert_eq!(deps.files, HashSet::from([PathBuf::from("data.toml")]));
+        assert!(deps.content.is_empty());
+    }
+
+    #[test]
+    fn nested_tracking_bubbles_up() {
+        let (inner, outer) = track(|| {
+            record_template("page.html");
+            track(|| record_template("shortcodes/youtube.html")).1
+        });
+        assert_eq!(inner.templates.len(), 1);
+        assert!(outer.templates.contains("shortcodes/youtube.html"));
+        assert!(outer.templates.contains("page.html"));
+    }
+}
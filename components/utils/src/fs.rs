@@ -114,6 +114,18 @@ pub fn copy_directory(
     dest: &Path,
     hard_link: bool,
     ignore_globset: Option<&GlobSet>,
+) -> Result<()> {
+    copy_directory_except(src, dest, hard_link, ignore_globset, |_| false)
+}
+
+/// Same as `copy_directory` but the files for which `skip` returns true, given their path
+/// relative to `src`, are left to the caller
+pub fn copy_directory_except(
+    src: &Path,
+    dest: &Path,
+    hard_link: bool,
+    ignore_globset: Option<&GlobSet>,
+    mut skip: impl FnMut(&Path) -> bool,
 ) -> Result<()> {
     for entry in
         WalkDir::new(src).follow_links(true).into_iter().filter_map(std::result::Result::ok)
@@ -132,7 +144,7 @@ pub fn copy_directory(
             if !target_path.exists() {
                 create_directory(&target_path)?;
             }
-        } else {
+        } else if !skip(relative_path) {
             copy_file(entry.path(), dest, src, hard_link).with_context(|| {
                 format!(
                     "Was not able to copy {} to {} (hard_link={})",
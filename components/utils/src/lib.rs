@@ -1,5 +1,6 @@
 pub mod anchors;
 pub mod de;
+pub mod dependencies;
 pub mod fs;
 pub mod globs;
 pub mod net;
@@ -1,7 +1,7 @@
 use libs::percent_encoding::percent_decode;
 use std::collections::HashMap;
 
-use errors::{anyhow, Result};
+use errors::{anyhow, bail, Result};
 
 /// Result of a successful resolution of an internal link.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -42,11 +42,96 @@ pub fn resolve_internal_link(
     }
 }
 
+/// A page or section a `[[wikilink]]` can point to
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct WikilinkTarget {
+    md_path: String,
+    lang: String,
+    /// Whether it was found by its path rather than by its title or slug
+    by_path: bool,
+}
+
+/// The pages and sections of the site by the names `[[wikilinks]]` can use for them:
+/// their title, their slug and their path
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct WikilinkIndex {
+    /// Lowercased name -> the content having it
+    names: HashMap<String, Vec<WikilinkTarget>>,
+    /// .md path -> its names, to remove them when the content is added again
+    files: HashMap<String, Vec<String>>,
+}
+
+fn normalize_wikilink_name(name: &str) -> String {
+    let name = name.trim().trim_matches('/');
+    name.strip_suffix(".md").unwrap_or(name).to_lowercase()
+}
+
+impl WikilinkIndex {
+    /// Adds a page or section, replacing what was known about it.
+    /// `path` is its path without the extension nor the language, eg `blog/hello`, and
+    /// `names` its title and slug.
+    pub fn insert(&mut self, md_path: &str, lang: &str, path: &str, names: &[&str]) {
+        self.remove(md_path);
+
+        let mut keys = Vec::new();
+        let path = normalize_wikilink_name(path);
+        let names = names.iter().map(|n| (normalize_wikilink_name(n), false));
+        for (key, by_path) in std::iter::once((path, true)).chain(names) {
+            if key.is_empty() || keys.contains(&key) {
+                continue;
+            }
+            self.names.entry(key.clone()).or_default().push(WikilinkTarget {
+                md_path: md_path.to_owned(),
+                lang: lang.to_owned(),
+                by_path,
+            });
+            keys.push(key);
+        }
+        self.files.insert(md_path.to_owned(), keys);
+    }
+
+    /// Removes a page or section
+    pub fn remove(&mut self, md_path: &str) {
+        for key in self.files.remove(md_path).unwrap_or_default() {
+            if let Some(targets) = self.names.get_mut(&key) {
+                targets.retain(|t| t.md_path != md_path);
+                if targets.is_empty() {
+                    self.names.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Finds the .md path of the content in the given language that `name` points to.
+    /// A path takes precedence over the titles and slugs, which have to be unique.
+    pub fn resolve(&self, name: &str, lang: &str) -> Result<&str> {
+        let targets: Vec<_> = self
+            .names
+            .get(&normalize_wikilink_name(name))
+            .map(|targets| targets.iter().filter(|t| t.lang == lang).collect())
+            .unwrap_or_default();
+
+        if let Some(target) = targets.iter().find(|t| t.by_path) {
+            return Ok(&target.md_path);
+        }
+        match targets.as_slice() {
+            [] => bail!("no page or section has this title, slug or path"),
+            [target] => Ok(&target.md_path),
+            _ => {
+                let mut paths: Vec<_> =
+                    targets.iter().map(|t| format!("`{}`", t.md_path)).collect();
+                paths.sort();
+                bail!("it could be any of {}", paths.join(", "))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
-    use super::resolve_internal_link;
+    use super::{resolve_internal_link, WikilinkIndex};
 
     #[test]
     fn can_resolve_valid_internal_link() {
@@ -92,4 +177,33 @@ mod tests {
         let res = resolve_internal_link("@/pages/about.md#hello", &HashMap::new());
         assert!(res.is_err());
     }
+
+    #[test]
+    fn can_resolve_wikilinks() {
+        let mut index = WikilinkIndex::default();
+        index.insert("blog/hello.md", "en", "blog/hello", &["Hello World", "hello"]);
+        index.insert("blog/hello.fr.md", "fr", "blog/hello", &["Bonjour", "bonjour"]);
+        index.insert("blog/_index.md", "en", "blog", &["Blog"]);
+
+        assert_eq!(index.resolve("hello world", "en").unwrap(), "blog/hello.md");
+        assert_eq!(index.resolve("blog/hello", "fr").unwrap(), "blog/hello.fr.md");
+        assert_eq!(index.resolve("Blog", "en").unwrap(), "blog/_index.md");
+        assert!(index.resolve("Bonjour", "en").is_err());
+
+        // Added again with another title
+        index.insert("blog/hello.md", "en", "blog/hello", &["Hi", "hello"]);
+        assert!(index.resolve("Hello World", "en").is_err());
+        assert_eq!(index.resolve("hi", "en").unwrap(), "blog/hello.md");
+    }
+
+    #[test]
+    fn errors_on_ambiguous_wikilinks() {
+        let mut index = WikilinkIndex::default();
+        index.insert("blog/setup.md", "en", "blog/setup", &["Setup", "setup"]);
+        index.insert("docs/setup.md", "en", "docs/setup", &["Setup", "setup"]);
+
+        let err = index.resolve("Setup", "en").unwrap_err();
+        assert_eq!(err.to_string(), "it could be any of `blog/setup.md`, `docs/setup.md`");
+        assert_eq!(index.resolve("docs/setup", "en").unwrap(), "docs/setup.md");
+    }
 }
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use libs::tera::ast::Node;
 use libs::tera::{Context, Tera};
 
 use errors::{bail, Result};
@@ -80,6 +81,7 @@ pub fn render_template(
     theme: &Option<String>,
 ) -> Result<String> {
     if let Some(template) = check_template_fallbacks(name, tera, theme) {
+        crate::dependencies::record_template(template);
         return tera.render(template, &context).map_err(std::convert::Into::into);
     }
 
@@ -147,9 +149,69 @@ pub fn check_template_fallbacks<'a>(
     None
 }
 
+/// Returns the given template names along with the name of every template extending, including
+/// or importing macros from them, directly or not
+pub fn find_dependent_templates(tera: &Tera, changed: &HashSet<String>) -> HashSet<String> {
+    fn collect_includes<'a>(nodes: &'a [Node], out: &mut Vec<&'a str>) {
+        for node in nodes {
+            match node {
+                Node::Include(_, names, _) => out.extend(names.iter().map(|n| n.as_str())),
+                Node::Block(_, block, _) => collect_includes(&block.body, out),
+                Node::MacroDefinition(_, def, _) => collect_includes(&def.body, out),
+                Node::FilterSection(_, section, _) => collect_includes(&section.body, out),
+                Node::Forloop(_, forloop, _) => {
+                    collect_includes(&forloop.body, out);
+                    if let Some(ref body) = forloop.empty_body {
+                        collect_includes(body, out);
+                    }
+                }
+                Node::If(if_node, _) => {
+                    for (_, _, body) in &if_node.conditions {
+                        collect_includes(body, out);
+                    }
+                    if let Some((_, ref body)) = if_node.otherwise {
+                        collect_includes(body, out);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut uses: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (name, template) in &tera.templates {
+        let mut used = Vec::new();
+        collect_includes(&template.ast, &mut used);
+        // Blocks can be overridden so we need to look at all the definitions
+        for definitions in template.blocks_definitions.values() {
+            for (_, block) in definitions {
+                collect_includes(&block.body, &mut used);
+            }
+        }
+        used.extend(template.parents.iter().map(|p| p.as_str()));
+        used.extend(template.imported_macro_files.iter().map(|(f, _)| f.as_str()));
+        uses.insert(name, used);
+    }
+
+    let mut affected = changed.clone();
+    loop {
+        let mut found_new = false;
+        for (name, used) in &uses {
+            if !affected.contains(*name) && used.iter().any(|u| affected.contains(*u)) {
+                affected.insert(name.to_string());
+                found_new = true;
+            }
+        }
+        if !found_new {
+            return affected;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::templates::{check_template_fallbacks, get_shortcodes};
+    use crate::templates::{check_template_fallbacks, find_dependent_templates, get_shortcodes};
+    use std::collections::HashSet;
 
     use super::rewrite_theme_paths;
     use libs::tera::Tera;
@@ -204,4 +266,30 @@ mod tests {
         let definitions = get_shortcodes(&tera);
         assert_eq!(definitions["youtube"].tera_name, "shortcodes/youtube.html");
     }
+
+    #[test]
+    fn can_find_dependent_templates() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            ("macros.html", "{% macro hello() %}{% include \"hello.html\" %}{% endmacro %}"),
+            ("hello.html", "Hello"),
+            ("base.html", "{% import \"macros.html\" as m %}{% block content %}{% endblock %}"),
+            (
+                "page.html",
+                "{% extends \"base.html\" %}{% block content %}{{ m::hello() }}{% endblock %}",
+            ),
+            ("section.html", "{% if true %}{% include \"nav.html\" %}{% endif %}"),
+            ("nav.html", "Nav"),
+        ])
+        .unwrap();
+
+        let affected = find_dependent_templates(&tera, &HashSet::from(["hello.html".to_string()]));
+        let expected: HashSet<_> =
+            ["hello.html", "macros.html", "base.html", "page.html"].map(String::from).into();
+        assert_eq!(affected, expected);
+
+        let affected = find_dependent_templates(&tera, &HashSet::from(["nav.html".to_string()]));
+        let expected: HashSet<_> = ["nav.html", "section.html"].map(String::from).into();
+        assert_eq!(affected, expected);
+    }
 }
@@ -15,6 +15,11 @@ pub struct Cli {
     #[clap(short = 'c', long, default_value = "config.toml")]
     pub config: PathBuf,
 
+    /// Environment to use: its config.<env>.toml file is merged over the config file.
+    /// Defaults to the ZOLA_ENV environment variable
+    #[clap(short = 'e', long, global = true)]
+    pub env: Option<String>,
+
     #[clap(subcommand)]
     pub command: Command,
 }
@@ -32,7 +37,8 @@ pub enum Command {
         force: bool,
     },
 
-    /// Deletes the output directory if there is one and builds the site
+    /// Deletes the output directory if there is one and builds the site.
+    /// With --incremental, only the outputs whose inputs changed are rebuilt
     Build {
         /// Force the base URL to be that value (defaults to the one in config.toml)
         #[clap(short = 'u', long)]
@@ -49,6 +55,23 @@ pub enum Command {
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
+
+        /// Include pages with a `publish_date` in the future when loading the site
+        #[clap(long)]
+        future: bool,
+
+        /// Include pages with an `expiry_date` in the past when loading the site
+        #[clap(long)]
+        expired: bool,
+
+        /// Use the build cache to only re-render and write the outputs whose inputs changed
+        /// since the last build, and remove the outputs that are not generated anymore
+        #[clap(long, conflicts_with = "no_cache")]
+        incremental: bool,
+
+        /// Do not read or write the build cache
+        #[clap(long)]
+        no_cache: bool,
     },
 
     /// Serve the site. Rebuild and reload on change automatically
@@ -78,6 +101,14 @@ pub enum Command {
         #[clap(long)]
         drafts: bool,
 
+        /// Include pages with a `publish_date` in the future when loading the site
+        #[clap(long)]
+        future: bool,
+
+        /// Include pages with an `expiry_date` in the past when loading the site
+        #[clap(long)]
+        expired: bool,
+
         /// Open site in the default browser
         #[clap(short = 'O', long)]
         open: bool,
@@ -96,6 +127,22 @@ pub enum Command {
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
+
+        /// Include pages with a `publish_date` in the future when loading the site
+        #[clap(long)]
+        future: bool,
+
+        /// Include pages with an `expiry_date` in the past when loading the site
+        #[clap(long)]
+        expired: bool,
+    },
+
+    /// Deletes the output directory and the build cache
+    Clean {
+        /// Also remove the processed images whose source changed or doesn't exist anymore,
+        /// and the files of the processed images directory that are not in its manifest
+        #[clap(long)]
+        images: bool,
     },
 
     /// Generate shell completion
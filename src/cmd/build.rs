@@ -1,21 +1,27 @@
 use std::path::Path;
 
 use errors::{Error, Result};
-use site::Site;
+use site::{cache::CACHE_DIR, Site};
 
 use crate::messages;
 
+#[allow(clippy::too_many_arguments)]
 pub fn build(
     root_dir: &Path,
     config_file: &Path,
+    env: Option<&str>,
     base_url: Option<&str>,
     output_dir: Option<&Path>,
     force: bool,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
+    incremental: bool,
+    no_cache: bool,
 ) -> Result<()> {
-    let mut site = Site::new(root_dir, config_file)?;
+    let mut site = Site::new_with_env(root_dir, config_file, env)?;
     if let Some(output_dir) = output_dir {
-        if !force && output_dir.exists() {
+        if !force && !incremental && output_dir.exists() {
             return Err(Error::msg(format!(
                 "Directory '{}' already exists. Use --force to overwrite.",
                 output_dir.display(),
@@ -30,8 +36,18 @@ pub fn build(
     if include_drafts {
         site.include_drafts();
     }
+    if include_future {
+        site.include_future();
+    }
+    if include_expired {
+        site.include_expired();
+    }
+    if !no_cache {
+        site.enable_build_cache(root_dir.join(CACHE_DIR), incremental);
+    }
     site.load()?;
     messages::notify_site_size(&site);
     messages::warn_about_ignored_pages(&site);
+    messages::warn_about_unknown_term_files(&site);
     site.build()
 }
@@ -5,15 +5,19 @@ use site::Site;
 
 use crate::messages;
 
+#[allow(clippy::too_many_arguments)]
 pub fn check(
     root_dir: &Path,
     config_file: &Path,
+    env: Option<&str>,
     base_path: Option<&str>,
     base_url: Option<&str>,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
 ) -> Result<()> {
     let bp = base_path.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(root_dir));
-    let mut site = Site::new(bp, config_file)?;
+    let mut site = Site::new_with_env(bp, config_file, env)?;
     // Force the checking of external links
     site.config.enable_check_mode();
     if let Some(b) = base_url {
@@ -22,8 +26,16 @@ pub fn check(
     if include_drafts {
         site.include_drafts();
     }
+    if include_future {
+        site.include_future();
+    }
+    if include_expired {
+        site.include_expired();
+    }
     site.load()?;
     messages::check_site_summary(&site);
+    messages::report_skipped_pages(&site);
     messages::warn_about_ignored_pages(&site);
+    messages::warn_about_unknown_term_files(&site);
     Ok(())
 }
@@ -0,0 +1,23 @@
+use std::fs::remove_dir_all;
+use std::path::Path;
+
+use errors::{Context, Result};
+use site::{cache::CACHE_DIR, Site};
+
+/// Deletes the output directory and the build cache, and with `images` the processed images
+/// that are outdated
+pub fn clean(root_dir: &Path, config_file: &Path, env: Option<&str>, images: bool) -> Result<()> {
+    let site = Site::new_with_env(root_dir, config_file, env)?;
+    site.clean()?;
+
+    let cache_dir = root_dir.join(CACHE_DIR);
+    if cache_dir.exists() {
+        remove_dir_all(&cache_dir).context("Couldn't delete the build cache")?;
+    }
+
+    if images {
+        let removed = site.clean_processed_images()?;
+        console::info(&format!("Removed {} outdated processed image(s)", removed));
+    }
+    Ok(())
+}
@@ -1,9 +1,11 @@
 mod build;
 mod check;
+mod clean;
 mod init;
 mod serve;
 
 pub use self::build::build;
 pub use self::check::check;
+pub use self::clean::clean;
 pub use self::init::create_new_project;
 pub use self::serve::serve;
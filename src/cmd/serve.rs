@@ -48,7 +48,7 @@ use ws::{Message, Sender, WebSocket};
 use errors::{anyhow, Context, Error, Result};
 use site::sass::compile_sass;
 use site::{Site, SITE_CONTENT};
-use utils::fs::{clean_site_output_folder, copy_file, create_directory};
+use utils::fs::{clean_site_output_folder, create_directory};
 
 use crate::fs_utils::{filter_events, ChangeKind, SimpleFileSystemEventKind};
 use crate::messages;
@@ -324,6 +324,21 @@ fn rebuild_done_handling(broadcaster: &Sender, res: Result<()>, reload_path: &st
     }
 }
 
+/// Logs which pages, sections and taxonomies were rendered again after a change
+fn report_rerendered_outputs(outputs: &[String]) {
+    const MAX_LISTED: usize = 10;
+
+    let mut msg = format!("-> Re-rendered {} output(s)", outputs.len());
+    if !outputs.is_empty() {
+        msg.push_str(": ");
+        msg.push_str(&outputs.iter().take(MAX_LISTED).cloned().collect::<Vec<_>>().join(", "));
+        if outputs.len() > MAX_LISTED {
+            msg.push_str(&format!(" and {} more", outputs.len() - MAX_LISTED));
+        }
+    }
+    console::info(&msg);
+}
+
 fn construct_url(base_url: &str, no_port_append: bool, interface_port: u16) -> String {
     if base_url == "/" {
         return String::from("/");
@@ -366,13 +381,16 @@ fn create_new_site(
     force: bool,
     base_url: Option<&str>,
     config_file: &Path,
+    env: Option<&str>,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
     mut no_port_append: bool,
     ws_port: Option<u16>,
 ) -> Result<(Site, SocketAddr, String)> {
     SITE_CONTENT.write().unwrap().clear();
 
-    let mut site = Site::new(root_dir, config_file)?;
+    let mut site = Site::new_with_env(root_dir, config_file, env)?;
     let address = SocketAddr::new(interface, interface_port);
 
     // if no base URL provided, use socket address
@@ -404,6 +422,12 @@ fn create_new_site(
     if include_drafts {
         site.include_drafts();
     }
+    if include_future {
+        site.include_future();
+    }
+    if include_expired {
+        site.include_expired();
+    }
     site.load()?;
     if let Some(p) = ws_port {
         site.enable_live_reload_with_port(p);
@@ -412,6 +436,7 @@ fn create_new_site(
     }
     messages::notify_site_size(&site);
     messages::warn_about_ignored_pages(&site);
+    messages::warn_about_unknown_term_files(&site);
     site.build()?;
     Ok((site, address, constructed_base_url))
 }
@@ -425,8 +450,11 @@ pub fn serve(
     force: bool,
     base_url: Option<&str>,
     config_file: &Path,
+    env: Option<&str>,
     open: bool,
     include_drafts: bool,
+    include_future: bool,
+    include_expired: bool,
     fast_rebuild: bool,
     no_port_append: bool,
     utc_offset: UtcOffset,
@@ -440,7 +468,10 @@ pub fn serve(
         force,
         base_url,
         config_file,
+        env,
         include_drafts,
+        include_future,
+        include_expired,
         no_port_append,
         None,
     )?;
@@ -615,14 +646,6 @@ pub fn serve(
         );
     };
 
-    let reload_templates = |site: &mut Site| {
-        rebuild_done_handling(
-            &broadcaster,
-            site.reload_templates(),
-            &site.templates_path.to_string_lossy(),
-        );
-    };
-
     let copy_static = |site: &Site, path: &Path, partial_path: &Path| {
         // Do nothing if the file/dir is on the ignore list
         if let Some(gs) = &site.config.ignored_static_globset {
@@ -651,7 +674,7 @@ pub fn serve(
         } else {
             rebuild_done_handling(
                 &broadcaster,
-                copy_file(path, &site.output_path, &site.static_path, site.config.hard_link_static),
+                site.copy_static_file(path),
                 &partial_path.to_string_lossy(),
             );
         }
@@ -665,7 +688,10 @@ pub fn serve(
         force,
         base_url,
         config_file,
+        env,
         include_drafts,
+        include_future,
+        include_expired,
         no_port_append,
         ws_port,
     ) {
@@ -754,16 +780,31 @@ pub fn serve(
                                             site = s;
                                         }
                                     }
+                                } else if *event_kind == SimpleFileSystemEventKind::Modify {
+                                    // Only re-render what depends on that file if we can
+                                    match site.rebuild_after_content_change(full_path) {
+                                        Ok(Some(outputs)) => {
+                                            report_rerendered_outputs(&outputs);
+                                            rebuild_done_handling(
+                                                &broadcaster,
+                                                Ok(()),
+                                                &full_path.to_string_lossy(),
+                                            );
+                                        }
+                                        Ok(None) | Err(_) => {
+                                            if let Some(s) = recreate_site() {
+                                                site = s;
+                                            }
+                                        }
+                                    }
                                 } else if let Some(s) = recreate_site() {
                                     site = s;
                                 }
                             }
                         }
                         ChangeKind::Templates => {
-                            let partial_paths: Vec<&PathBuf> =
-                                change_group.iter().map(|(p, _, _)| p).collect();
-                            let full_paths: Vec<&PathBuf> =
-                                change_group.iter().map(|(_, p, _)| p).collect();
+                            let full_paths: Vec<&Path> =
+                                change_group.iter().map(|(_, p, _)| p.as_path()).collect();
                             let combined_paths = full_paths
                                 .iter()
                                 .map(|p| p.display().to_string())
@@ -772,17 +813,27 @@ pub fn serve(
                             let msg = format!("-> Template file(s) changed {}", combined_paths);
                             console::info(&msg);
 
-                            let shortcodes_updated = partial_paths
-                                .iter()
-                                .any(|p| p.starts_with("/templates/shortcodes"));
-                            // Rebuild site if shortcodes change; otherwise, just update template.
-                            if shortcodes_updated {
-                                if let Some(s) = recreate_site() {
-                                    site = s;
+                            // Only re-render what used those templates, unless templates were
+                            // added or removed
+                            match site.rebuild_after_template_changes(&full_paths) {
+                                Ok(Some(outputs)) => {
+                                    report_rerendered_outputs(&outputs);
+                                    rebuild_done_handling(
+                                        &broadcaster,
+                                        Ok(()),
+                                        &site.templates_path.to_string_lossy(),
+                                    );
+                                }
+                                Ok(None) => {
+                                    if let Some(s) = recreate_site() {
+                                        site = s;
+                                    }
                                 }
-                            } else {
-                                println!("Reloading only template");
-                                reload_templates(&mut site)
+                                Err(e) => rebuild_done_handling(
+                                    &broadcaster,
+                                    Err(e),
+                                    &site.templates_path.to_string_lossy(),
+                                ),
                             }
                         }
                         ChangeKind::StaticFiles => {
@@ -892,7 +943,10 @@ mod tests {
             force,
             base_url.as_deref(),
             &config_file,
+            None,
             include_drafts,
+            false,
+            false,
             no_port_append,
             ws_port,
         )
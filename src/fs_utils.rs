@@ -105,7 +105,10 @@ pub fn filter_events(
 
         // Ignore ordinary files peer to config.toml. This assumes all other files we care
         // about are nested more deeply than config.toml or are directories peer to config.toml.
-        if path != config_path && path.is_file() && path.parent() == config_path.parent() {
+        if !is_config_file(&path, config_path)
+            && path.is_file()
+            && path.parent() == config_path.parent()
+        {
             continue;
         }
 
@@ -137,6 +140,27 @@ fn is_folder_empty(dir: &Path) -> bool {
     read_dir(dir).expect("Failed to read a directory to see if it was empty").next().is_none()
 }
 
+/// Whether the path is the config file or the config file of an environment, eg
+/// `config.production.toml` for `config.toml`
+fn is_config_file(path: &Path, config_path: &Path) -> bool {
+    if path == config_path {
+        return true;
+    }
+    if path.parent() != config_path.parent() {
+        return false;
+    }
+    match (path.file_name(), config_path.file_stem(), config_path.extension()) {
+        (Some(name), Some(stem), Some(ext)) => {
+            let (name, stem, ext) =
+                (name.to_string_lossy(), stem.to_string_lossy(), ext.to_string_lossy());
+            name.len() > stem.len() + ext.len() + 2
+                && name.starts_with(&format!("{}.", stem))
+                && name.ends_with(&format!(".{}", ext))
+        }
+        _ => false,
+    }
+}
+
 /// Detect what changed from the given path so we have an idea what needs
 /// to be reloaded
 fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKind, PathBuf) {
@@ -153,7 +177,7 @@ fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKin
         ChangeKind::StaticFiles
     } else if partial_path.starts_with("/sass") {
         ChangeKind::Sass
-    } else if path == config_path {
+    } else if is_config_file(path, config_path) {
         ChangeKind::Config
     } else {
         unreachable!("Got a change in an unexpected path: {}", partial_path.display());
@@ -288,6 +312,12 @@ mod tests {
                 Path::new("/home/vincent/site/config.staging.toml"),
                 Path::new("/home/vincent/site/config.staging.toml"),
             ),
+            (
+                (ChangeKind::Config, PathBuf::from("/config.production.toml")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/config.production.toml"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
         ];
 
         for (expected, pwd, path, config_filename) in test_cases {
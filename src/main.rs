@@ -57,17 +57,31 @@ fn main() {
                 std::process::exit(1);
             }
         }
-        Command::Build { base_url, output_dir, force, drafts } => {
+        Command::Build {
+            base_url,
+            output_dir,
+            force,
+            drafts,
+            future,
+            expired,
+            incremental,
+            no_cache,
+        } => {
             console::info("Building site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
             match cmd::build(
                 &root_dir,
                 &config_file,
+                cli.env.as_deref(),
                 base_url.as_deref(),
                 output_dir.as_deref(),
                 force,
                 drafts,
+                future,
+                expired,
+                incremental,
+                no_cache,
             ) {
                 Ok(()) => messages::report_elapsed_time(start),
                 Err(e) => {
@@ -83,6 +97,8 @@ fn main() {
             force,
             base_url,
             drafts,
+            future,
+            expired,
             open,
             fast,
             no_port_append,
@@ -109,8 +125,11 @@ fn main() {
                 force,
                 base_url.as_deref(),
                 &config_file,
+                cli.env.as_deref(),
                 open,
                 drafts,
+                future,
+                expired,
                 fast,
                 no_port_append,
                 UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
@@ -119,11 +138,20 @@ fn main() {
                 std::process::exit(1);
             }
         }
-        Command::Check { drafts } => {
+        Command::Check { drafts, future, expired } => {
             console::info("Checking site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
-            match cmd::check(&root_dir, &config_file, None, None, drafts) {
+            match cmd::check(
+                &root_dir,
+                &config_file,
+                cli.env.as_deref(),
+                None,
+                None,
+                drafts,
+                future,
+                expired,
+            ) {
                 Ok(()) => messages::report_elapsed_time(start),
                 Err(e) => {
                     messages::unravel_errors("Failed to check the site", &e);
@@ -131,6 +159,13 @@ fn main() {
                 }
             }
         }
+        Command::Clean { images } => {
+            let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
+            if let Err(e) = cmd::clean(&root_dir, &config_file, cli.env.as_deref(), images) {
+                messages::unravel_errors("Failed to clean the site", &e);
+                std::process::exit(1);
+            }
+        }
         Command::Completion { shell } => {
             let cmd = &mut Cli::command();
             clap_complete::generate(shell, cmd, cmd.get_name().to_string(), &mut std::io::stdout());
@@ -3,7 +3,7 @@ use std::convert::TryInto;
 use std::time::Instant;
 
 use errors::Error;
-use site::Site;
+use site::{Site, SkipReason};
 
 /// Display in the console the number of pages/sections in the site
 pub fn notify_site_size(site: &Site) {
@@ -52,6 +52,45 @@ pub fn warn_about_ignored_pages(site: &Site) {
     }
 }
 
+/// Display a warning in the console if some files of the terms directories don't match any term
+pub fn warn_about_unknown_term_files(site: &Site) {
+    if site.unknown_term_files.is_empty() {
+        return;
+    }
+
+    console::warn(&format!(
+        "{} file(s) ignored (not matching any taxonomy term or language):",
+        site.unknown_term_files.len()
+    ));
+    for path in &site.unknown_term_files {
+        console::warn(&format!("- {}", path.display()));
+    }
+}
+
+/// Display in the console how many pages were not loaded and why
+pub fn report_skipped_pages(site: &Site) {
+    if site.skipped_pages.is_empty() {
+        return;
+    }
+
+    let count = |reason| site.skipped_pages.iter().filter(|(_, r)| *r == reason).count();
+    println!(
+        "-> Skipped {} page(s): {} draft(s), {} scheduled in the future, {} expired",
+        site.skipped_pages.len(),
+        count(SkipReason::Draft),
+        count(SkipReason::Scheduled),
+        count(SkipReason::Expired),
+    );
+    for (path, reason) in &site.skipped_pages {
+        let reason = match reason {
+            SkipReason::Draft => "draft, use --drafts to include it",
+            SkipReason::Scheduled => "publish_date in the future, use --future to include it",
+            SkipReason::Expired => "expiry_date in the past, use --expired to include it",
+        };
+        println!("   - {} ({})", path.display(), reason);
+    }
+}
+
 /// Print the time elapsed rounded to 1 decimal
 pub fn report_elapsed_time(instant: Instant) {
     let duration: Duration = instant.elapsed().try_into().unwrap();